@@ -24,9 +24,41 @@ impl From<EncodedAudio> for Vec<u8> {
     }
 }
 
+/// Sampling rate of [`DecodedAudio`].
+const SAMPLE_RATE: u32 = 48_000;
+/// Number of channels of [`DecodedAudio`].
+const CHANNELS: u16 = 1;
+const BYTES_PER_SAMPLE: u16 = 2;
+
 /// Representation of wav audio (16-bit signed little-endian samples).
 pub struct DecodedAudio(Vec<u8>);
 
+impl DecodedAudio {
+    /// Wrap the samples in a RIFF/WAVE container so that it can be shared as an audio file.
+    pub fn to_wav(&self) -> Vec<u8> {
+        let data_len = self.0.len() as u32;
+        let block_align = CHANNELS * BYTES_PER_SAMPLE;
+
+        let mut wav = Vec::with_capacity(44 + self.0.len());
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+        wav.extend_from_slice(b"WAVE");
+        wav.extend_from_slice(b"fmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        // format: PCM
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&CHANNELS.to_le_bytes());
+        wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+        wav.extend_from_slice(&(SAMPLE_RATE * block_align as u32).to_le_bytes());
+        wav.extend_from_slice(&block_align.to_le_bytes());
+        wav.extend_from_slice(&(BYTES_PER_SAMPLE * 8).to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_len.to_le_bytes());
+        wav.extend_from_slice(&self.0);
+        wav
+    }
+}
+
 impl From<Vec<u8>> for DecodedAudio {
     fn from(buf: Vec<u8>) -> Self {
         Self(buf)
@@ -1,11 +1,19 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use serde::Deserialize;
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct Config {
     pub discord: DiscordConfig,
     pub voicevox: VoicevoxConfig,
+    /// VOICEVOX以外に併用する音声合成エンジン
+    #[serde(default)]
+    pub engines: Vec<EngineConfig>,
     pub redis: RedisConfig,
+    #[serde(default)]
+    pub audio_cache: AudioCacheConfig,
+    /// 同梱の辞書に加えて使う、bep-eng形式の英語カタカナ辞書のパス
+    #[serde(default)]
+    pub english_dict_path: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -16,7 +24,129 @@ pub struct DiscordConfig {
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct VoicevoxConfig {
-    pub api_base: String,
+    /// エンドポイントが1つの場合の指定方法。`endpoints`と併用した場合は先頭に加える
+    #[serde(default)]
+    pub api_base: Option<String>,
+    /// 負荷分散・障害時の切り替えを行うエンドポイントの一覧
+    #[serde(default)]
+    pub endpoints: Vec<VoicevoxEndpointConfig>,
+    #[serde(default)]
+    pub load_balancing: LoadBalancing,
+    /// 各エンドポイントの`/version`を確認する間隔の秒数
+    #[serde(default = "default_health_check_interval_secs")]
+    pub health_check_interval_secs: u64,
+    /// リクエストのタイムアウトの秒数（エンドポイントごとに指定がない場合）
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// プリセット・話者一覧の取得などに失敗したときに再試行する回数
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// プリセット・話者一覧をキャッシュする秒数
+    #[serde(default = "default_catalog_ttl_secs")]
+    pub catalog_ttl_secs: u64,
+    /// アクセント付きの辞書登録を許可し、エンジンのユーザー辞書に同期する
+    /// エンジンの辞書はすべてのサーバーで共有されるため、既定では無効
+    #[serde(default)]
+    pub user_dict_sync: bool,
+    /// アクセント付きの辞書登録を許可するサーバーのID
+    /// エンジンに登録した単語はすべてのサーバーの読み方に影響するため、運用者が信頼できるサーバーに限る
+    #[serde(default)]
+    pub user_dict_guilds: Vec<u64>,
+}
+
+impl VoicevoxConfig {
+    /// エンドポイントとそのタイムアウトの秒数の一覧を返す
+    pub fn endpoint_list(&self) -> Vec<(String, u64)> {
+        let api_base = self
+            .api_base
+            .iter()
+            .map(|api_base| (api_base.clone(), self.timeout_secs));
+        let endpoints = self.endpoints.iter().map(|endpoint| match endpoint {
+            VoicevoxEndpointConfig::Url(api_base) => (api_base.clone(), self.timeout_secs),
+            VoicevoxEndpointConfig::Detailed {
+                api_base,
+                timeout_secs,
+            } => (api_base.clone(), timeout_secs.unwrap_or(self.timeout_secs)),
+        });
+        api_base.chain(endpoints).collect()
+    }
+}
+
+/// エンドポイントはURLだけでも、タイムアウトと合わせても指定できる
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum VoicevoxEndpointConfig {
+    Url(String),
+    Detailed {
+        api_base: String,
+        #[serde(default)]
+        timeout_secs: Option<u64>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoadBalancing {
+    #[default]
+    RoundRobin,
+    LeastBusy,
+}
+
+fn default_health_check_interval_secs() -> u64 {
+    10
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+fn default_max_retries() -> u32 {
+    2
+}
+
+fn default_catalog_ttl_secs() -> u64 {
+    300
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EngineConfig {
+    /// AivisSpeech や COEIROINK など VOICEVOX 互換の API を持つエンジン
+    Compatible { namespace: String, api_base: String },
+    /// ネットワークを使わずにダミーの音声を返すエンジン（動作確認用）
+    Stub { namespace: String },
+}
+
+/// 合成した音声のキャッシュの設定
+#[derive(Debug, Clone, Deserialize)]
+pub struct AudioCacheConfig {
+    /// メモリ上に保持する上限（MB）。0の場合はメモリ上にキャッシュしない
+    #[serde(default = "default_audio_cache_max_mb")]
+    pub max_mb: u64,
+    /// 指定した場合はディスクにも保存し、再起動後も使う
+    #[serde(default)]
+    pub dir: Option<String>,
+    /// ディスクに保存する上限（MB）
+    #[serde(default = "default_audio_cache_disk_max_mb")]
+    pub disk_max_mb: u64,
+}
+
+impl Default for AudioCacheConfig {
+    fn default() -> Self {
+        Self {
+            max_mb: default_audio_cache_max_mb(),
+            dir: None,
+            disk_max_mb: default_audio_cache_disk_max_mb(),
+        }
+    }
+}
+
+fn default_audio_cache_max_mb() -> u64 {
+    64
+}
+
+fn default_audio_cache_disk_max_mb() -> u64 {
+    512
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -35,8 +165,83 @@ pub async fn load() -> Result<Config> {
             .parse::<u64>()
             .context("Failed to parse DISCORD_CLIENT_ID as u64")?;
 
-        let voicevox_api_base = std::env::var("VOICEVOX_API_BASE")
-            .unwrap_or_else(|_| "http://voicevox:50021".to_string());
+        // カンマ区切りで複数のエンドポイントを指定できる
+        let voicevox_endpoints = std::env::var("VOICEVOX_API_BASE")
+            .unwrap_or_else(|_| "http://voicevox:50021".to_string())
+            .split(',')
+            .map(|x| x.trim().to_string())
+            .filter(|x| !x.is_empty())
+            .map(VoicevoxEndpointConfig::Url)
+            .collect();
+        let load_balancing = match std::env::var("VOICEVOX_LOAD_BALANCING").as_deref() {
+            Ok("round_robin") | Err(_) => LoadBalancing::RoundRobin,
+            Ok("least_busy") => LoadBalancing::LeastBusy,
+            Ok(x) => bail!("Unknown VOICEVOX_LOAD_BALANCING: {}", x),
+        };
+        let health_check_interval_secs = match std::env::var("VOICEVOX_HEALTH_CHECK_INTERVAL_SECS")
+        {
+            Ok(secs) => secs
+                .parse::<u64>()
+                .context("Failed to parse VOICEVOX_HEALTH_CHECK_INTERVAL_SECS as u64")?,
+            Err(_) => default_health_check_interval_secs(),
+        };
+        let timeout_secs = match std::env::var("VOICEVOX_TIMEOUT_SECS") {
+            Ok(secs) => secs
+                .parse::<u64>()
+                .context("Failed to parse VOICEVOX_TIMEOUT_SECS as u64")?,
+            Err(_) => default_timeout_secs(),
+        };
+        let max_retries = match std::env::var("VOICEVOX_MAX_RETRIES") {
+            Ok(n) => n
+                .parse::<u32>()
+                .context("Failed to parse VOICEVOX_MAX_RETRIES as u32")?,
+            Err(_) => default_max_retries(),
+        };
+        let user_dict_sync = matches!(
+            std::env::var("VOICEVOX_USER_DICT_SYNC").as_deref(),
+            Ok("true") | Ok("1")
+        );
+        let user_dict_guilds = match std::env::var("VOICEVOX_USER_DICT_GUILDS") {
+            Ok(list) => list
+                .split(',')
+                .map(str::trim)
+                .filter(|x| !x.is_empty())
+                .map(|x| {
+                    x.parse::<u64>()
+                        .with_context(|| format!("Failed to parse guild ID {:?}", x))
+                })
+                .collect::<Result<Vec<_>>>()
+                .context("Failed to parse VOICEVOX_USER_DICT_GUILDS")?,
+            Err(_) => Vec::new(),
+        };
+        let catalog_ttl_secs = match std::env::var("VOICEVOX_CATALOG_TTL_SECS") {
+            Ok(secs) => secs
+                .parse::<u64>()
+                .context("Failed to parse VOICEVOX_CATALOG_TTL_SECS as u64")?,
+            Err(_) => default_catalog_ttl_secs(),
+        };
+
+        // 形式: `<名前空間>=<URL>` をカンマ区切りで列挙する (例: `aivis=http://aivisspeech:10101`)
+        let engines = match std::env::var("COMPATIBLE_ENGINES") {
+            Ok(list) => parse_compatible_engines(&list)?,
+            Err(_) => Vec::new(),
+        };
+
+        let audio_cache = AudioCacheConfig {
+            max_mb: match std::env::var("AUDIO_CACHE_MAX_MB") {
+                Ok(mb) => mb
+                    .parse::<u64>()
+                    .context("Failed to parse AUDIO_CACHE_MAX_MB as u64")?,
+                Err(_) => default_audio_cache_max_mb(),
+            },
+            dir: std::env::var("AUDIO_CACHE_DIR").ok(),
+            disk_max_mb: match std::env::var("AUDIO_CACHE_DISK_MAX_MB") {
+                Ok(mb) => mb
+                    .parse::<u64>()
+                    .context("Failed to parse AUDIO_CACHE_DISK_MAX_MB as u64")?,
+                Err(_) => default_audio_cache_disk_max_mb(),
+            },
+        };
 
         return Ok(Config {
             discord: DiscordConfig {
@@ -44,9 +249,20 @@ pub async fn load() -> Result<Config> {
                 bot_token,
             },
             voicevox: VoicevoxConfig {
-                api_base: voicevox_api_base,
+                api_base: None,
+                endpoints: voicevox_endpoints,
+                load_balancing,
+                health_check_interval_secs,
+                timeout_secs,
+                max_retries,
+                catalog_ttl_secs,
+                user_dict_sync,
+                user_dict_guilds,
             },
+            engines,
             redis: RedisConfig { url: redis_url },
+            audio_cache,
+            english_dict_path: std::env::var("ENGLISH_DICT_PATH").ok(),
         });
     }
 
@@ -61,3 +277,19 @@ pub async fn load() -> Result<Config> {
 
     Ok(config)
 }
+
+fn parse_compatible_engines(list: &str) -> Result<Vec<EngineConfig>> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (namespace, api_base) = entry
+                .split_once('=')
+                .with_context(|| format!("Invalid COMPATIBLE_ENGINES entry: {}", entry))?;
+            Ok(EngineConfig::Compatible {
+                namespace: namespace.trim().to_string(),
+                api_base: api_base.trim().to_string(),
+            })
+        })
+        .collect()
+}
@@ -1,12 +1,41 @@
+//! 読み替え辞書
+//!
+//! 語句をそのまま置き換える項目と、正規表現で置き換える項目を別々に保存する。
+//! 語句と正規表現は同じ名前空間を共有し、同じ文字列を両方に登録することはできない。
+//! 優先度は既定値（0）以外のものだけを保存する。
+
 use anyhow::{bail, Result};
 use redis::aio::Connection;
 use redis::AsyncCommands;
+use std::collections::HashMap;
+
+/// 辞書の項目の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    /// 語句をそのまま置き換える
+    Literal,
+    /// 正規表現に一致した部分を置き換える。置き換え後の文字列では`$1`などでキャプチャを参照できる
+    Regex,
+}
+
+/// 辞書の項目
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// 語句、または正規表現
+    pub word: String,
+    pub read_as: String,
+    pub kind: EntryKind,
+    /// 大きいものから先に適用する
+    pub priority: i64,
+}
 
 #[derive(Debug, Clone)]
 pub struct InsertOption {
     pub guild_id: u64,
     pub word: String,
     pub read_as: String,
+    pub kind: EntryKind,
+    pub priority: i64,
 }
 
 #[derive(Debug, Clone)]
@@ -16,16 +45,32 @@ pub enum InsertResponse {
 }
 
 /// 辞書に語句を追加する
+/// 正規表現の妥当性はこの関数では確かめないため、呼び出し側で検証しておく
 pub async fn insert(connection: &mut Connection, option: InsertOption) -> Result<InsertResponse> {
+    let (key, other_key) = match option.kind {
+        EntryKind::Literal => (dict_key(option.guild_id), regex_key(option.guild_id)),
+        EntryKind::Regex => (regex_key(option.guild_id), dict_key(option.guild_id)),
+    };
+    let exists: bool = connection.hexists(other_key, &option.word).await?;
+    if exists {
+        return Ok(InsertResponse::WordAlreadyExists);
+    }
+
     let resp = connection
-        .hset_nx(dict_key(option.guild_id), option.word, option.read_as)
+        .hset_nx(key, &option.word, option.read_as)
         .await?;
-
-    Ok(match resp {
+    let resp = match resp {
         0 => InsertResponse::WordAlreadyExists,
         1 => InsertResponse::Success,
         x => bail!("Unknown HSETNX response from Redis: {}", x),
-    })
+    };
+
+    if matches!(resp, InsertResponse::Success) && option.priority != 0 {
+        let _: () = connection
+            .hset(priority_key(option.guild_id), &option.word, option.priority)
+            .await?;
+    }
+    Ok(resp)
 }
 
 #[derive(Debug, Clone)]
@@ -42,23 +87,64 @@ pub enum RemoveResponse {
 
 /// 辞書から語句を削除する
 pub async fn remove(connection: &mut Connection, option: RemoveOption) -> Result<RemoveResponse> {
-    let resp = connection
-        .hdel(dict_key(option.guild_id), option.word)
+    let (literal, regex): (i64, i64) = redis::pipe()
+        .atomic()
+        .hdel(dict_key(option.guild_id), &option.word)
+        .hdel(regex_key(option.guild_id), &option.word)
+        .hdel(priority_key(option.guild_id), &option.word)
+        .ignore()
+        .query_async(connection)
         .await?;
 
-    Ok(match resp {
+    Ok(match literal + regex {
         0 => RemoveResponse::WordDoesNotExist,
         1 => RemoveResponse::Success,
         x => bail!("Unknown HDEL response from Redis: {}", x),
     })
 }
 
+#[derive(Debug, Clone)]
+pub struct SetPriorityOption {
+    pub guild_id: u64,
+    pub word: String,
+    pub priority: i64,
+}
+
+#[derive(Debug, Clone)]
+pub enum SetPriorityResponse {
+    Success,
+    WordDoesNotExist,
+}
+
+/// 登録済みの項目の優先度を変更する
+pub async fn set_priority(
+    connection: &mut Connection,
+    option: SetPriorityOption,
+) -> Result<SetPriorityResponse> {
+    let (literal, regex): (bool, bool) = redis::pipe()
+        .hexists(dict_key(option.guild_id), &option.word)
+        .hexists(regex_key(option.guild_id), &option.word)
+        .query_async(connection)
+        .await?;
+    if !literal && !regex {
+        return Ok(SetPriorityResponse::WordDoesNotExist);
+    }
+
+    let key = priority_key(option.guild_id);
+    if option.priority == 0 {
+        let _: () = connection.hdel(key, &option.word).await?;
+    } else {
+        let _: () = connection.hset(key, &option.word, option.priority).await?;
+    }
+    Ok(SetPriorityResponse::Success)
+}
+
 #[derive(Debug, Clone)]
 pub struct GetAllOption {
     pub guild_id: u64,
 }
 
-/// 辞書全体を返す
+/// 語句をそのまま置き換える項目の一覧を返す
 /// 辞書が存在しないときは空の[`Vec`]を返す
 pub async fn get_all(
     connection: &mut Connection,
@@ -68,6 +154,62 @@ pub async fn get_all(
     Ok(resp)
 }
 
+/// 正規表現の項目と優先度を含む、辞書のすべての項目を返す
+/// 辞書が存在しないときは空の[`Vec`]を返す
+pub async fn get_entries(connection: &mut Connection, option: GetAllOption) -> Result<Vec<Entry>> {
+    // (語句, 正規表現, 優先度)
+    type Hashes = (
+        Vec<(String, String)>,
+        Vec<(String, String)>,
+        HashMap<String, i64>,
+    );
+    let (literals, regexes, priorities): Hashes = redis::pipe()
+        .hgetall(dict_key(option.guild_id))
+        .hgetall(regex_key(option.guild_id))
+        .hgetall(priority_key(option.guild_id))
+        .query_async(connection)
+        .await?;
+
+    let literals = literals
+        .into_iter()
+        .map(|(word, read_as)| (word, read_as, EntryKind::Literal));
+    let regexes = regexes
+        .into_iter()
+        .map(|(word, read_as)| (word, read_as, EntryKind::Regex));
+    Ok(literals
+        .chain(regexes)
+        .map(|(word, read_as, kind)| Entry {
+            priority: priorities.get(&word).copied().unwrap_or(0),
+            word,
+            read_as,
+            kind,
+        })
+        .collect())
+}
+
+#[derive(Debug, Clone)]
+pub struct GetOption {
+    pub guild_id: u64,
+    pub word: String,
+}
+
+/// 語句の読みを返す
+/// 登録されていないときは`None`を返す
+pub async fn get(connection: &mut Connection, option: GetOption) -> Result<Option<String>> {
+    let resp = connection
+        .hget(dict_key(option.guild_id), option.word)
+        .await?;
+    Ok(resp)
+}
+
 fn dict_key(guild_id: u64) -> String {
     format!("guild:{}:dict", guild_id)
 }
+
+fn regex_key(guild_id: u64) -> String {
+    format!("guild:{}:dict_regex", guild_id)
+}
+
+fn priority_key(guild_id: u64) -> String {
+    format!("guild:{}:dict_priority", guild_id)
+}
@@ -0,0 +1,167 @@
+//! 音声合成エンジンのユーザー辞書に登録する単語
+//!
+//! エンジンのユーザー辞書はすべてのサーバーで共有されるため、同じ表記を登録できるのは1つのサーバーだけとする。
+//! 最初に登録したサーバーがその表記の所有者となり、所有者が削除するまで他のサーバーはエンジンに登録できない。
+//! 他のサーバーでは通常の読み替え（[`crate::dict`]）として扱われる。
+
+use anyhow::{bail, Context, Result};
+use redis::aio::Connection;
+use redis::AsyncCommands;
+
+/// エンジンに登録する単語の発音の情報
+/// 読みは[`crate::dict`]に登録したものを使う
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Accent {
+    pub accent_type: u32,
+    /// VOICEVOX ENGINEの`word_type`（例: `PROPER_NOUN`）
+    pub word_type: String,
+}
+
+impl Accent {
+    fn to_field(&self) -> String {
+        format!("{}:{}", self.accent_type, self.word_type)
+    }
+
+    fn from_field(field: &str) -> Result<Self> {
+        let (accent_type, word_type) = field
+            .split_once(':')
+            .with_context(|| format!("Invalid accent field: {}", field))?;
+        Ok(Self {
+            accent_type: accent_type
+                .parse()
+                .with_context(|| format!("Invalid accent type: {}", accent_type))?,
+            word_type: word_type.to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ClaimOption {
+    pub guild_id: u64,
+    pub word: String,
+    pub accent: Accent,
+}
+
+#[derive(Debug, Clone)]
+pub enum ClaimResponse {
+    Success,
+    /// 他のサーバーがすでに同じ表記を登録している
+    OwnedByOtherGuild,
+}
+
+/// 単語をエンジンに登録するものとして記録する
+pub async fn claim(connection: &mut Connection, option: ClaimOption) -> Result<ClaimResponse> {
+    let _: bool = connection
+        .hset_nx(OWNERS_KEY, &option.word, option.guild_id)
+        .await?;
+    let owner: u64 = connection.hget(OWNERS_KEY, &option.word).await?;
+    if owner != option.guild_id {
+        return Ok(ClaimResponse::OwnedByOtherGuild);
+    }
+
+    let _: () = redis::pipe()
+        .atomic()
+        .hset(
+            accent_key(option.guild_id),
+            &option.word,
+            option.accent.to_field(),
+        )
+        .sadd(MANAGED_KEY, &option.word)
+        .query_async(connection)
+        .await?;
+
+    Ok(ClaimResponse::Success)
+}
+
+#[derive(Debug, Clone)]
+pub struct ReleaseOption {
+    pub guild_id: u64,
+    pub word: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum ReleaseResponse {
+    Success,
+    /// このサーバーはその表記をエンジンに登録していない
+    NotOwned,
+}
+
+/// エンジンへの登録をやめる
+/// 表記は[`managed_surfaces`]に残り、次の同期でエンジンから削除される
+pub async fn release(
+    connection: &mut Connection,
+    option: ReleaseOption,
+) -> Result<ReleaseResponse> {
+    let owner: Option<u64> = connection.hget(OWNERS_KEY, &option.word).await?;
+    if owner != Some(option.guild_id) {
+        return Ok(ReleaseResponse::NotOwned);
+    }
+
+    let (removed,): (i64,) = redis::pipe()
+        .atomic()
+        .hdel(OWNERS_KEY, &option.word)
+        .hdel(accent_key(option.guild_id), &option.word)
+        .ignore()
+        .query_async(connection)
+        .await?;
+
+    Ok(match removed {
+        1 => ReleaseResponse::Success,
+        0 => ReleaseResponse::NotOwned,
+        x => bail!("Unknown HDEL response from Redis: {}", x),
+    })
+}
+
+/// 単語の発音の情報を返す
+/// このサーバーがエンジンに登録していない単語の場合は`None`を返す
+pub async fn get_accent(
+    connection: &mut Connection,
+    guild_id: u64,
+    word: &str,
+) -> Result<Option<Accent>> {
+    let field: Option<String> = connection.hget(accent_key(guild_id), word).await?;
+    field.as_deref().map(Accent::from_field).transpose()
+}
+
+/// サーバーがエンジンに登録している単語の一覧
+pub async fn get_guild_words(connection: &mut Connection, guild_id: u64) -> Result<Vec<String>> {
+    let words = connection.hkeys(accent_key(guild_id)).await?;
+    Ok(words)
+}
+
+#[derive(Debug, Clone)]
+pub struct OwnedWord {
+    pub guild_id: u64,
+    pub word: String,
+    pub accent: Accent,
+}
+
+/// エンジンに登録するすべての単語を返す
+pub async fn get_all(connection: &mut Connection) -> Result<Vec<OwnedWord>> {
+    let owners: Vec<(String, u64)> = connection.hgetall(OWNERS_KEY).await?;
+
+    let mut words = Vec::new();
+    for (word, guild_id) in owners {
+        if let Some(accent) = get_accent(connection, guild_id, &word).await? {
+            words.push(OwnedWord {
+                guild_id,
+                word,
+                accent,
+            });
+        }
+    }
+    Ok(words)
+}
+
+/// これまでにエンジンに登録したことのある表記
+pub async fn managed_surfaces(connection: &mut Connection) -> Result<Vec<String>> {
+    let surfaces = connection.smembers(MANAGED_KEY).await?;
+    Ok(surfaces)
+}
+
+const OWNERS_KEY: &str = "engine_dict:owners";
+const MANAGED_KEY: &str = "engine_dict:managed";
+
+fn accent_key(guild_id: u64) -> String {
+    format!("guild:{}:dict:accent", guild_id)
+}
@@ -1,4 +1,9 @@
 pub mod dict;
+pub mod engine_dict;
+pub mod read_settings;
+pub mod text_rule;
+pub mod user_name;
 pub mod voice;
+pub mod voice_profile;
 
 pub use redis;
@@ -0,0 +1,178 @@
+//! 読み上げる文章の作り方に関するサーバーごとの設定
+//!
+//! 値の解釈や既定値はBot側で扱い、ここでは文字列として保存する。
+
+use anyhow::{Context, Result};
+use redis::aio::Connection;
+use redis::AsyncCommands;
+use std::collections::HashMap;
+
+/// 読み上げの設定
+/// 未設定の項目は`None`となり、Botの既定値が使われる
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReadSettings {
+    /// 読み上げる最大の文字数
+    pub max_length: Option<u32>,
+    /// 長い文章の省略のしかた（`chars`, `sentence`, `first_line`, `none`）
+    pub truncation: Option<String>,
+    /// 省略したときに末尾に付ける文字列
+    pub suffix: Option<String>,
+    /// 話者が変わったときなどに送信者の名前を読み上げるかどうか
+    pub author_name: Option<bool>,
+    /// 同じ送信者でも名前を読み上げ直すまでの間隔（秒）
+    pub author_name_interval: Option<u32>,
+    /// 送信者の名前の後に付ける文字列（例: `さん`）
+    pub author_suffix: Option<String>,
+    /// 絵文字の読み方（`read`, `collapse`, `drop`）
+    pub emoji: Option<String>,
+    /// 説明を読む添付ファイルなどの種類（カンマ区切り）
+    pub describe: Option<String>,
+    /// 返信先を読むかどうか
+    pub reply: Option<bool>,
+    /// スレッドのメッセージの扱い（`off`, `on`, `name`）
+    pub thread: Option<String>,
+    /// コードブロックとインラインコードの読み方（`skip`, `replace`, `first_line`）
+    pub code: Option<String>,
+    /// 同じ文字を続けて読む最大の回数
+    pub max_repeat: Option<u32>,
+}
+
+impl ReadSettings {
+    fn to_fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = Vec::new();
+        if let Some(x) = self.max_length {
+            fields.push((FIELD_MAX_LENGTH, x.to_string()));
+        }
+        if let Some(x) = &self.truncation {
+            fields.push((FIELD_TRUNCATION, x.clone()));
+        }
+        if let Some(x) = &self.suffix {
+            fields.push((FIELD_SUFFIX, x.clone()));
+        }
+        if let Some(x) = self.author_name {
+            fields.push((FIELD_AUTHOR_NAME, x.to_string()));
+        }
+        if let Some(x) = self.author_name_interval {
+            fields.push((FIELD_AUTHOR_NAME_INTERVAL, x.to_string()));
+        }
+        if let Some(x) = &self.author_suffix {
+            fields.push((FIELD_AUTHOR_SUFFIX, x.clone()));
+        }
+        if let Some(x) = &self.emoji {
+            fields.push((FIELD_EMOJI, x.clone()));
+        }
+        if let Some(x) = &self.describe {
+            fields.push((FIELD_DESCRIBE, x.clone()));
+        }
+        if let Some(x) = self.reply {
+            fields.push((FIELD_REPLY, x.to_string()));
+        }
+        if let Some(x) = &self.thread {
+            fields.push((FIELD_THREAD, x.clone()));
+        }
+        if let Some(x) = &self.code {
+            fields.push((FIELD_CODE, x.clone()));
+        }
+        if let Some(x) = self.max_repeat {
+            fields.push((FIELD_MAX_REPEAT, x.to_string()));
+        }
+        fields
+    }
+
+    fn from_fields(fields: HashMap<String, String>) -> Result<Self> {
+        Ok(ReadSettings {
+            max_length: parse_field(&fields, FIELD_MAX_LENGTH)?,
+            truncation: fields.get(FIELD_TRUNCATION).cloned(),
+            suffix: fields.get(FIELD_SUFFIX).cloned(),
+            author_name: parse_field(&fields, FIELD_AUTHOR_NAME)?,
+            author_name_interval: parse_field(&fields, FIELD_AUTHOR_NAME_INTERVAL)?,
+            author_suffix: fields.get(FIELD_AUTHOR_SUFFIX).cloned(),
+            emoji: fields.get(FIELD_EMOJI).cloned(),
+            describe: fields.get(FIELD_DESCRIBE).cloned(),
+            reply: parse_field(&fields, FIELD_REPLY)?,
+            thread: fields.get(FIELD_THREAD).cloned(),
+            code: fields.get(FIELD_CODE).cloned(),
+            max_repeat: parse_field(&fields, FIELD_MAX_REPEAT)?,
+        })
+    }
+}
+
+fn parse_field<T: std::str::FromStr>(
+    fields: &HashMap<String, String>,
+    name: &str,
+) -> Result<Option<T>>
+where
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    fields
+        .get(name)
+        .map(|x| x.parse::<T>())
+        .transpose()
+        .with_context(|| format!("Invalid read setting field: {}", name))
+}
+
+pub const FIELD_MAX_LENGTH: &str = "max_length";
+pub const FIELD_TRUNCATION: &str = "truncation";
+pub const FIELD_SUFFIX: &str = "suffix";
+pub const FIELD_AUTHOR_NAME: &str = "author_name";
+pub const FIELD_AUTHOR_NAME_INTERVAL: &str = "author_name_interval";
+pub const FIELD_AUTHOR_SUFFIX: &str = "author_suffix";
+pub const FIELD_EMOJI: &str = "emoji";
+pub const FIELD_DESCRIBE: &str = "describe";
+pub const FIELD_REPLY: &str = "reply";
+pub const FIELD_THREAD: &str = "thread";
+pub const FIELD_CODE: &str = "code";
+pub const FIELD_MAX_REPEAT: &str = "max_repeat";
+
+#[derive(Debug, Clone)]
+pub struct GetOption {
+    pub guild_id: u64,
+}
+
+/// 読み上げの設定を返す
+/// 未設定の場合はすべての項目が`None`の設定を返す
+pub async fn get(connection: &mut Connection, option: GetOption) -> Result<ReadSettings> {
+    let fields: HashMap<String, String> = connection.hgetall(settings_key(option.guild_id)).await?;
+    ReadSettings::from_fields(fields)
+}
+
+#[derive(Debug, Clone)]
+pub struct SetOption {
+    pub guild_id: u64,
+    pub settings: ReadSettings,
+}
+
+/// 読み上げの設定を更新する
+/// `option.settings`のうち`None`の項目は変更しない
+pub async fn set(connection: &mut Connection, option: SetOption) -> Result<()> {
+    let fields = option.settings.to_fields();
+    if fields.is_empty() {
+        return Ok(());
+    }
+
+    let _: () = connection
+        .hset_multiple(settings_key(option.guild_id), &fields)
+        .await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct ResetOption {
+    pub guild_id: u64,
+    /// 削除する項目の名前。`None`の場合はすべての項目を削除する
+    pub field: Option<String>,
+}
+
+/// 読み上げの設定を削除し、既定に戻す
+pub async fn reset(connection: &mut Connection, option: ResetOption) -> Result<()> {
+    let key = settings_key(option.guild_id);
+    let _: () = match option.field {
+        Some(field) => connection.hdel(key, field).await?,
+        None => connection.del(key).await?,
+    };
+    Ok(())
+}
+
+fn settings_key(guild_id: u64) -> String {
+    format!("guild:{}:read_settings", guild_id)
+}
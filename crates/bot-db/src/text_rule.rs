@@ -0,0 +1,93 @@
+//! 読み上げる文章に適用する変換ルールのサーバーごとの設定
+//!
+//! ルールの適用順と有効・無効をリストとして保存する。ルールの中身はBot側で定義し、ここでは名前だけを扱う。
+
+use anyhow::{Context, Result};
+use redis::aio::Connection;
+use redis::AsyncCommands;
+
+/// ルールの設定
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleSetting {
+    pub name: String,
+    pub enabled: bool,
+}
+
+impl RuleSetting {
+    fn to_entry(&self) -> String {
+        format!("{}:{}", self.name, if self.enabled { "on" } else { "off" })
+    }
+
+    fn from_entry(entry: &str) -> Result<Self> {
+        let (name, state) = entry
+            .rsplit_once(':')
+            .with_context(|| format!("Invalid text rule entry: {}", entry))?;
+        Ok(Self {
+            name: name.to_string(),
+            enabled: state == "on",
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GetOption {
+    pub guild_id: u64,
+}
+
+/// 適用順に並んだルールの設定を返す
+/// 一度も変更していない場合は`None`を返す
+pub async fn get(
+    connection: &mut Connection,
+    option: GetOption,
+) -> Result<Option<Vec<RuleSetting>>> {
+    let entries: Vec<String> = connection.lrange(rules_key(option.guild_id), 0, -1).await?;
+    if entries.is_empty() {
+        return Ok(None);
+    }
+
+    let rules = entries
+        .iter()
+        .map(|entry| RuleSetting::from_entry(entry))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Some(rules))
+}
+
+#[derive(Debug, Clone)]
+pub struct SetOption {
+    pub guild_id: u64,
+    /// 適用順に並べたルール
+    pub rules: Vec<RuleSetting>,
+}
+
+/// ルールの設定を置き換える
+pub async fn set(connection: &mut Connection, option: SetOption) -> Result<()> {
+    let key = rules_key(option.guild_id);
+    let entries = option
+        .rules
+        .iter()
+        .map(RuleSetting::to_entry)
+        .collect::<Vec<_>>();
+
+    let mut pipe = redis::pipe();
+    pipe.atomic().del(&key).ignore();
+    if !entries.is_empty() {
+        pipe.rpush(&key, entries).ignore();
+    }
+    let _: () = pipe.query_async(connection).await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct ResetOption {
+    pub guild_id: u64,
+}
+
+/// ルールの設定を削除し、既定に戻す
+pub async fn reset(connection: &mut Connection, option: ResetOption) -> Result<()> {
+    let _: () = connection.del(rules_key(option.guild_id)).await?;
+    Ok(())
+}
+
+fn rules_key(guild_id: u64) -> String {
+    format!("guild:{}:text_rules", guild_id)
+}
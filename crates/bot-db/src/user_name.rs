@@ -0,0 +1,101 @@
+//! サーバー内のユーザーごとの名前の読み上げの設定
+
+use anyhow::{Context, Result};
+use redis::aio::Connection;
+use redis::AsyncCommands;
+use std::collections::HashMap;
+
+/// 名前の読み上げの設定
+/// 未設定の項目は`None`となり、サーバーの設定や表示名が使われる
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserNameSettings {
+    /// 名前の読み方
+    pub reading: Option<String>,
+    /// 自分のメッセージの前に名前を読み上げるかどうか
+    pub announce: Option<bool>,
+}
+
+impl UserNameSettings {
+    fn to_fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = Vec::new();
+        if let Some(x) = &self.reading {
+            fields.push((FIELD_READING, x.clone()));
+        }
+        if let Some(x) = self.announce {
+            fields.push((FIELD_ANNOUNCE, x.to_string()));
+        }
+        fields
+    }
+
+    fn from_fields(fields: HashMap<String, String>) -> Result<Self> {
+        Ok(UserNameSettings {
+            reading: fields.get(FIELD_READING).cloned(),
+            announce: fields
+                .get(FIELD_ANNOUNCE)
+                .map(|x| x.parse::<bool>())
+                .transpose()
+                .with_context(|| format!("Invalid user name field: {}", FIELD_ANNOUNCE))?,
+        })
+    }
+}
+
+pub const FIELD_READING: &str = "reading";
+pub const FIELD_ANNOUNCE: &str = "announce";
+
+#[derive(Debug, Clone)]
+pub struct GetOption {
+    pub guild_id: u64,
+    pub user_id: u64,
+}
+
+/// 名前の読み上げの設定を返す
+/// 未設定の場合はすべての項目が`None`の設定を返す
+pub async fn get(connection: &mut Connection, option: GetOption) -> Result<UserNameSettings> {
+    let fields: HashMap<String, String> = connection
+        .hgetall(name_key(option.guild_id, option.user_id))
+        .await?;
+    UserNameSettings::from_fields(fields)
+}
+
+#[derive(Debug, Clone)]
+pub struct SetOption {
+    pub guild_id: u64,
+    pub user_id: u64,
+    pub settings: UserNameSettings,
+}
+
+/// 名前の読み上げの設定を更新する
+/// `option.settings`のうち`None`の項目は変更しない
+pub async fn set(connection: &mut Connection, option: SetOption) -> Result<()> {
+    let fields = option.settings.to_fields();
+    if fields.is_empty() {
+        return Ok(());
+    }
+
+    let _: () = connection
+        .hset_multiple(name_key(option.guild_id, option.user_id), &fields)
+        .await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct ResetOption {
+    pub guild_id: u64,
+    pub user_id: u64,
+    /// 削除する項目の名前。`None`の場合はすべての項目を削除する
+    pub field: Option<&'static str>,
+}
+
+/// 名前の読み上げの設定を削除する
+pub async fn reset(connection: &mut Connection, option: ResetOption) -> Result<()> {
+    let key = name_key(option.guild_id, option.user_id);
+    let _: () = match option.field {
+        Some(field) => connection.hdel(key, field).await?,
+        None => connection.del(key).await?,
+    };
+    Ok(())
+}
+
+fn name_key(guild_id: u64, user_id: u64) -> String {
+    format!("guild:{}:user:{}:name", guild_id, user_id)
+}
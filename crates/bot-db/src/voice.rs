@@ -6,21 +6,13 @@ use redis::AsyncCommands;
 pub struct GetOption {
     pub guild_id: u64,
     pub user_id: u64,
-    pub fallback: i64,
 }
 
-/// ユーザーの声を返す
-/// 未設定の場合は`option.fallback`の値を設定して返す
-pub async fn get(connection: &mut Connection, option: GetOption) -> Result<i64> {
+/// ユーザーの声（プリセットID）を返す
+/// 未設定の場合は`None`を返す
+pub async fn get(connection: &mut Connection, option: GetOption) -> Result<Option<i64>> {
     let key = voice_key(option.guild_id, option.user_id);
-
-    let (resp,) = redis::pipe()
-        .set_nx(&key, option.fallback)
-        .ignore()
-        .get(&key)
-        .query_async(connection)
-        .await?;
-
+    let resp: Option<i64> = connection.get(&key).await?;
     Ok(resp)
 }
 
@@ -42,3 +34,61 @@ pub async fn set(connection: &mut Connection, option: SetOption) -> Result<()> {
 fn voice_key(guild_id: u64, user_id: u64) -> String {
     format!("guild:{}:user:{}:voice", guild_id, user_id)
 }
+
+#[derive(Debug, Clone)]
+pub struct GetChannelOption {
+    pub guild_id: u64,
+    pub channel_id: u64,
+}
+
+/// チャンネルの声（スタイルID）を返す
+/// 未設定の場合は`None`を返す
+pub async fn get_channel(
+    connection: &mut Connection,
+    option: GetChannelOption,
+) -> Result<Option<String>> {
+    let resp: Option<String> = connection
+        .hget(channel_voice_key(option.guild_id), option.channel_id)
+        .await?;
+    Ok(resp)
+}
+
+#[derive(Debug, Clone)]
+pub struct SetChannelOption {
+    pub guild_id: u64,
+    pub channel_id: u64,
+    pub voice_id: String,
+}
+
+/// チャンネルで読み上げるメッセージの声を設定する
+pub async fn set_channel(connection: &mut Connection, option: SetChannelOption) -> Result<()> {
+    let _: () = connection
+        .hset(
+            channel_voice_key(option.guild_id),
+            option.channel_id,
+            option.voice_id,
+        )
+        .await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct RemoveChannelOption {
+    pub guild_id: u64,
+    pub channel_id: u64,
+}
+
+/// チャンネルの声の設定を削除する
+pub async fn remove_channel(
+    connection: &mut Connection,
+    option: RemoveChannelOption,
+) -> Result<()> {
+    let _: () = connection
+        .hdel(channel_voice_key(option.guild_id), option.channel_id)
+        .await?;
+    Ok(())
+}
+
+fn channel_voice_key(guild_id: u64) -> String {
+    format!("guild:{}:channel_voice", guild_id)
+}
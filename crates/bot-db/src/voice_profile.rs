@@ -0,0 +1,177 @@
+use anyhow::{Context, Result};
+use redis::aio::Connection;
+use redis::AsyncCommands;
+use std::collections::HashMap;
+
+/// 声の設定
+/// 未設定の項目は`None`となり、サーバーの既定値やエンジンの既定値が使われる
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VoiceProfile {
+    /// 名前空間付きのスタイルID（例: `3`, `aivis:888753760`）
+    pub voice_id: Option<String>,
+    pub speed_scale: Option<f64>,
+    pub pitch_scale: Option<f64>,
+    pub intonation_scale: Option<f64>,
+    pub volume_scale: Option<f64>,
+    pub pre_phoneme_length: Option<f64>,
+    pub post_phoneme_length: Option<f64>,
+    /// `voice_id`と混ぜ合わせる（モーフィングする）スタイルID
+    pub morph_target: Option<String>,
+    /// モーフィングの割合（0.0〜1.0）
+    pub morph_rate: Option<f64>,
+}
+
+impl VoiceProfile {
+    /// 未設定の項目を`fallback`の値で埋める
+    pub fn or(self, fallback: VoiceProfile) -> VoiceProfile {
+        VoiceProfile {
+            voice_id: self.voice_id.or(fallback.voice_id),
+            speed_scale: self.speed_scale.or(fallback.speed_scale),
+            pitch_scale: self.pitch_scale.or(fallback.pitch_scale),
+            intonation_scale: self.intonation_scale.or(fallback.intonation_scale),
+            volume_scale: self.volume_scale.or(fallback.volume_scale),
+            pre_phoneme_length: self.pre_phoneme_length.or(fallback.pre_phoneme_length),
+            post_phoneme_length: self.post_phoneme_length.or(fallback.post_phoneme_length),
+            morph_target: self.morph_target.or(fallback.morph_target),
+            morph_rate: self.morph_rate.or(fallback.morph_rate),
+        }
+    }
+
+    fn to_fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = Vec::new();
+        if let Some(x) = &self.voice_id {
+            fields.push((FIELD_VOICE_ID, x.clone()));
+        }
+        if let Some(x) = &self.morph_target {
+            fields.push((FIELD_MORPH_TARGET, x.clone()));
+        }
+        let scales = [
+            (FIELD_SPEED_SCALE, self.speed_scale),
+            (FIELD_PITCH_SCALE, self.pitch_scale),
+            (FIELD_INTONATION_SCALE, self.intonation_scale),
+            (FIELD_VOLUME_SCALE, self.volume_scale),
+            (FIELD_PRE_PHONEME_LENGTH, self.pre_phoneme_length),
+            (FIELD_POST_PHONEME_LENGTH, self.post_phoneme_length),
+            (FIELD_MORPH_RATE, self.morph_rate),
+        ];
+        for (name, value) in scales {
+            if let Some(x) = value {
+                fields.push((name, x.to_string()));
+            }
+        }
+        fields
+    }
+
+    fn from_fields(fields: HashMap<String, String>) -> Result<Self> {
+        let parse_f64 = |name: &str| -> Result<Option<f64>> {
+            fields
+                .get(name)
+                .map(|x| x.parse::<f64>())
+                .transpose()
+                .with_context(|| format!("Invalid voice profile field: {}", name))
+        };
+
+        Ok(VoiceProfile {
+            voice_id: fields.get(FIELD_VOICE_ID).cloned(),
+            speed_scale: parse_f64(FIELD_SPEED_SCALE)?,
+            pitch_scale: parse_f64(FIELD_PITCH_SCALE)?,
+            intonation_scale: parse_f64(FIELD_INTONATION_SCALE)?,
+            volume_scale: parse_f64(FIELD_VOLUME_SCALE)?,
+            pre_phoneme_length: parse_f64(FIELD_PRE_PHONEME_LENGTH)?,
+            post_phoneme_length: parse_f64(FIELD_POST_PHONEME_LENGTH)?,
+            morph_target: fields.get(FIELD_MORPH_TARGET).cloned(),
+            morph_rate: parse_f64(FIELD_MORPH_RATE)?,
+        })
+    }
+}
+
+const FIELD_VOICE_ID: &str = "voice_id";
+const FIELD_SPEED_SCALE: &str = "speed_scale";
+const FIELD_PITCH_SCALE: &str = "pitch_scale";
+const FIELD_INTONATION_SCALE: &str = "intonation_scale";
+const FIELD_VOLUME_SCALE: &str = "volume_scale";
+const FIELD_PRE_PHONEME_LENGTH: &str = "pre_phoneme_length";
+const FIELD_POST_PHONEME_LENGTH: &str = "post_phoneme_length";
+const FIELD_MORPH_TARGET: &str = "morph_target";
+const FIELD_MORPH_RATE: &str = "morph_rate";
+
+/// 設定の対象
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    /// サーバー全体の既定値
+    Guild { guild_id: u64 },
+    /// サーバー内のユーザー
+    User { guild_id: u64, user_id: u64 },
+}
+
+#[derive(Debug, Clone)]
+pub struct GetOption {
+    pub scope: Scope,
+}
+
+/// 声の設定を返す
+/// 未設定の場合はすべての項目が`None`の設定を返す
+pub async fn get(connection: &mut Connection, option: GetOption) -> Result<VoiceProfile> {
+    let fields: HashMap<String, String> = connection.hgetall(profile_key(option.scope)).await?;
+    VoiceProfile::from_fields(fields)
+}
+
+#[derive(Debug, Clone)]
+pub struct SetOption {
+    pub scope: Scope,
+    pub profile: VoiceProfile,
+}
+
+/// 声の設定を更新する
+/// `option.profile`のうち`None`の項目は変更しない
+pub async fn set(connection: &mut Connection, option: SetOption) -> Result<()> {
+    let fields = option.profile.to_fields();
+    if fields.is_empty() {
+        return Ok(());
+    }
+
+    let _: () = connection
+        .hset_multiple(profile_key(option.scope), &fields)
+        .await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct ResetOption {
+    pub scope: Scope,
+}
+
+/// 声の設定をすべて削除する
+pub async fn reset(connection: &mut Connection, option: ResetOption) -> Result<()> {
+    let _: () = connection.del(profile_key(option.scope)).await?;
+    Ok(())
+}
+
+/// 声の指定（スタイルID）だけを削除する
+/// 話速などの他の項目は変更しない
+pub async fn reset_voice(connection: &mut Connection, option: ResetOption) -> Result<()> {
+    let _: () = connection
+        .hdel(profile_key(option.scope), FIELD_VOICE_ID)
+        .await?;
+    Ok(())
+}
+
+/// モーフィングの指定だけを削除する
+pub async fn reset_morph(connection: &mut Connection, option: ResetOption) -> Result<()> {
+    let _: () = connection
+        .hdel(
+            profile_key(option.scope),
+            &[FIELD_MORPH_TARGET, FIELD_MORPH_RATE],
+        )
+        .await?;
+    Ok(())
+}
+
+fn profile_key(scope: Scope) -> String {
+    match scope {
+        Scope::Guild { guild_id } => format!("guild:{}:voice_profile", guild_id),
+        Scope::User { guild_id, user_id } => {
+            format!("guild:{}:user:{}:voice_profile", guild_id, user_id)
+        }
+    }
+}
@@ -0,0 +1,534 @@
+use crate::engine::{Morph, SynthesisRequest, TextFormat, UserDictSync, VoiceId};
+use crate::speech::VoiceParams;
+use anyhow::{Context, Result};
+use log::{info, warn};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// この文字数を超える文章はキャッシュしない
+/// 長い文章は繰り返し読まれることがほとんどなく、キャッシュを無駄に押し出してしまうため
+pub const MAX_CACHEABLE_TEXT_CHARS: usize = 40;
+
+/// 合成・デコード済みの音声（PCM）のキャッシュ
+/// メモリ上で最近使われた順に保持し、ディレクトリを指定した場合はディスクにも保存する
+pub struct AudioCache {
+    memory: Mutex<Lru>,
+    disk: Option<DiskCache>,
+    /// エンジンのユーザー辞書に反映した内容のハッシュ
+    user_dict: AtomicU64,
+    hits: AtomicU64,
+    disk_hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// キャッシュの利用状況
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub disk_hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub bytes: usize,
+    pub capacity_bytes: usize,
+    pub disk_bytes: Option<u64>,
+}
+
+impl CacheStats {
+    /// ヒット率（0.0〜1.0）。まだ1度も参照されていなければ`None`
+    pub fn hit_rate(&self) -> Option<f64> {
+        let total = self.hits + self.disk_hits + self.misses;
+        (total > 0).then(|| (self.hits + self.disk_hits) as f64 / total as f64)
+    }
+}
+
+/// キャッシュのキー
+/// 同じ文章・声・パラメータなら同じ音声が合成されるものとみなす
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey(String);
+
+impl CacheKey {
+    /// キャッシュの対象外（長すぎる文章など）の場合は`None`を返す
+    pub fn new(text: &str, voice: &VoiceId, params: &VoiceParams) -> Option<Self> {
+        Self::build(text, voice, None, params)
+    }
+
+    pub fn from_request(request: &SynthesisRequest) -> Option<Self> {
+        let key = Self::build(
+            &request.text,
+            &request.voice,
+            request.morph.as_ref(),
+            &request.params,
+        )?;
+        Some(match request.format {
+            TextFormat::Plain => key,
+            // 同じ文字列でも普通の文章とカナでは読み方が異なる
+            TextFormat::Kana => Self(format!("kana\n{}", key.0)),
+        })
+    }
+
+    fn build(
+        text: &str,
+        voice: &VoiceId,
+        morph: Option<&Morph>,
+        params: &VoiceParams,
+    ) -> Option<Self> {
+        let text = normalize_text(text);
+        if text.is_empty() || text.chars().count() > MAX_CACHEABLE_TEXT_CHARS {
+            return None;
+        }
+
+        let params = [
+            params.speed_scale,
+            params.pitch_scale,
+            params.intonation_scale,
+            params.volume_scale,
+            params.pre_phoneme_length,
+            params.post_phoneme_length,
+        ]
+        .iter()
+        .map(|x| x.map_or_else(|| "-".to_string(), |x| x.to_string()))
+        .collect::<Vec<_>>()
+        .join(",");
+
+        let mut voice = format!("{}:{}", voice.namespace, voice.style_id);
+        if let Some(morph) = morph {
+            voice.push_str(&format!(
+                ">{}:{}@{}",
+                morph.target.namespace, morph.target.style_id, morph.rate
+            ));
+        }
+
+        Some(Self(format!("{}\n{}\n{}", voice, params, text)))
+    }
+
+    /// エンジンのバージョンとユーザー辞書の状態をキーに含める
+    /// どちらかが変われば同じ文章でも読み方が変わりうるため、以前の音声は使わない
+    pub fn with_engine_state(self, version: &str, user_dict: u64) -> Self {
+        Self(format!("{}\n{:016x}\n{}", version, user_dict, self.0))
+    }
+
+    fn file_name(&self) -> String {
+        format!("{:016x}.pcm", fnv1a(self.0.as_bytes()))
+    }
+}
+
+/// 前後の空白を除き、連続する空白を1つにまとめる
+fn normalize_text(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+impl AudioCache {
+    /// `capacity_bytes`が0の場合はメモリ上にキャッシュしない
+    pub fn new(capacity_bytes: usize) -> Self {
+        Self {
+            memory: Mutex::new(Lru::new(capacity_bytes)),
+            disk: None,
+            user_dict: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            disk_hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// ディスクにもキャッシュを保存する
+    /// 既存のファイルは読み込んで引き続き使う
+    pub fn with_disk(mut self, dir: impl Into<PathBuf>, capacity_bytes: u64) -> Result<Self> {
+        self.disk = Some(DiskCache::open(dir.into(), capacity_bytes)?);
+        Ok(self)
+    }
+
+    /// エンジンのユーザー辞書に反映する内容を記録する
+    /// 内容が変わると、それ以前にキャッシュした音声は[`CacheKey::with_engine_state`]のキーで使われなくなる
+    pub fn set_user_dict(&self, dict: &UserDictSync) {
+        let mut words = dict
+            .words
+            .iter()
+            .map(|x| {
+                format!(
+                    "{}\t{}\t{}\t{}",
+                    x.surface,
+                    x.pronunciation,
+                    x.accent_type,
+                    x.word_type.api_name()
+                )
+            })
+            .collect::<Vec<_>>();
+        words.sort_unstable();
+        let hash = fnv1a(words.join("\n").as_bytes());
+        self.user_dict.store(hash, Ordering::Relaxed);
+    }
+
+    /// エンジンのユーザー辞書に反映した内容のハッシュ。まだ反映していなければ0
+    pub fn user_dict_hash(&self) -> u64 {
+        self.user_dict.load(Ordering::Relaxed)
+    }
+
+    pub async fn get(&self, key: &CacheKey) -> Option<Arc<Vec<u8>>> {
+        if let Some(pcm) = self.memory.lock().unwrap().get(key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Some(pcm);
+        }
+
+        if let Some(disk) = &self.disk {
+            match disk.get(key).await {
+                Ok(Some(pcm)) => {
+                    let pcm = Arc::new(pcm);
+                    self.memory.lock().unwrap().insert(key.clone(), pcm.clone());
+                    self.disk_hits.fetch_add(1, Ordering::Relaxed);
+                    return Some(pcm);
+                }
+                Ok(None) => {}
+                Err(err) => warn!("Failed to read audio cache: {:#}", err),
+            }
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        None
+    }
+
+    pub async fn insert(&self, key: CacheKey, pcm: Arc<Vec<u8>>) {
+        self.memory.lock().unwrap().insert(key.clone(), pcm.clone());
+
+        if let Some(disk) = &self.disk {
+            if let Err(err) = disk.insert(&key, &pcm).await {
+                warn!("Failed to write audio cache: {:#}", err);
+            }
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let memory = self.memory.lock().unwrap();
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            disk_hits: self.disk_hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: memory.entries.len(),
+            bytes: memory.bytes,
+            capacity_bytes: memory.capacity_bytes,
+            disk_bytes: self.disk.as_ref().map(DiskCache::bytes),
+        }
+    }
+}
+
+/// 容量（バイト数）で制限したLRUキャッシュ
+struct Lru {
+    entries: HashMap<CacheKey, LruEntry>,
+    bytes: usize,
+    capacity_bytes: usize,
+    clock: u64,
+}
+
+struct LruEntry {
+    pcm: Arc<Vec<u8>>,
+    last_used: u64,
+}
+
+impl Lru {
+    fn new(capacity_bytes: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            bytes: 0,
+            capacity_bytes,
+            clock: 0,
+        }
+    }
+
+    fn get(&mut self, key: &CacheKey) -> Option<Arc<Vec<u8>>> {
+        self.clock += 1;
+        let entry = self.entries.get_mut(key)?;
+        entry.last_used = self.clock;
+        Some(entry.pcm.clone())
+    }
+
+    fn insert(&mut self, key: CacheKey, pcm: Arc<Vec<u8>>) {
+        if pcm.len() > self.capacity_bytes {
+            return;
+        }
+
+        self.clock += 1;
+        self.bytes += pcm.len();
+        let entry = LruEntry {
+            pcm,
+            last_used: self.clock,
+        };
+        if let Some(old) = self.entries.insert(key, entry) {
+            self.bytes -= old.pcm.len();
+        }
+
+        while self.bytes > self.capacity_bytes {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            if let Some(entry) = self.entries.remove(&oldest) {
+                self.bytes -= entry.pcm.len();
+            }
+        }
+    }
+}
+
+/// ディスク上のキャッシュ
+/// ファイルの先頭にキーを書き込み、ハッシュが衝突しても別の音声を返さないようにする
+struct DiskCache {
+    dir: PathBuf,
+    capacity_bytes: u64,
+    /// ファイル名 -> (サイズ, 最後に使った順番)
+    index: Mutex<DiskIndex>,
+}
+
+#[derive(Default)]
+struct DiskIndex {
+    files: HashMap<String, (u64, u64)>,
+    bytes: u64,
+    clock: u64,
+}
+
+impl DiskCache {
+    fn open(dir: PathBuf, capacity_bytes: u64) -> Result<Self> {
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create audio cache directory {:?}", dir))?;
+
+        // 前回の起動時のファイルは更新日時の古い順に使われたものとみなす
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.ends_with(".pcm") {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            files.push((name, metadata.len(), metadata.modified()?));
+        }
+        files.sort_by_key(|(_, _, modified)| *modified);
+
+        let mut index = DiskIndex::default();
+        for (name, size, _) in files {
+            index.clock += 1;
+            index.bytes += size;
+            index.files.insert(name, (size, index.clock));
+        }
+        info!(
+            "Audio cache directory {:?}: {} files, {} bytes",
+            dir,
+            index.files.len(),
+            index.bytes
+        );
+
+        let cache = Self {
+            dir,
+            capacity_bytes,
+            index: Mutex::new(index),
+        };
+        cache.evict(&mut cache.index.lock().unwrap());
+        Ok(cache)
+    }
+
+    fn bytes(&self) -> u64 {
+        self.index.lock().unwrap().bytes
+    }
+
+    async fn get(&self, key: &CacheKey) -> Result<Option<Vec<u8>>> {
+        let name = key.file_name();
+        {
+            let mut index = self.index.lock().unwrap();
+            index.clock += 1;
+            let clock = index.clock;
+            match index.files.get_mut(&name) {
+                Some((_, last_used)) => *last_used = clock,
+                None => return Ok(None),
+            }
+        }
+
+        let data = match tokio::fs::read(self.dir.join(&name)).await {
+            Ok(data) => data,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                self.forget(&name);
+                return Ok(None);
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(decode_file(&data, key).map(<[u8]>::to_vec))
+    }
+
+    async fn insert(&self, key: &CacheKey, pcm: &[u8]) -> Result<()> {
+        let name = key.file_name();
+        let mut data = Vec::with_capacity(4 + key.0.len() + pcm.len());
+        data.extend_from_slice(&(key.0.len() as u32).to_le_bytes());
+        data.extend_from_slice(key.0.as_bytes());
+        data.extend_from_slice(pcm);
+
+        let size = data.len() as u64;
+        if size > self.capacity_bytes {
+            return Ok(());
+        }
+        write_atomically(&self.dir, &name, &data).await?;
+
+        let mut index = self.index.lock().unwrap();
+        index.clock += 1;
+        let clock = index.clock;
+        index.bytes += size;
+        if let Some((old_size, _)) = index.files.insert(name, (size, clock)) {
+            index.bytes -= old_size;
+        }
+        self.evict(&mut index);
+
+        Ok(())
+    }
+
+    fn forget(&self, name: &str) {
+        let mut index = self.index.lock().unwrap();
+        if let Some((size, _)) = index.files.remove(name) {
+            index.bytes -= size;
+        }
+    }
+
+    fn evict(&self, index: &mut DiskIndex) {
+        while index.bytes > self.capacity_bytes {
+            let Some(oldest) = index
+                .files
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(name, _)| name.clone())
+            else {
+                break;
+            };
+            if let Some((size, _)) = index.files.remove(&oldest) {
+                index.bytes -= size;
+            }
+            if let Err(err) = std::fs::remove_file(self.dir.join(&oldest)) {
+                warn!("Failed to remove audio cache file {}: {}", oldest, err);
+            }
+        }
+    }
+}
+
+/// 書き込み途中のファイルを読まないよう、一時ファイルに書いてから置き換える
+/// 同じ文章を同時に合成した場合に一時ファイルが重ならないよう、プロセスIDと連番を名前に含める
+async fn write_atomically(dir: &Path, name: &str, data: &[u8]) -> Result<()> {
+    static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+    let tmp = dir.join(format!(
+        "{}.{}.{}.tmp",
+        name,
+        std::process::id(),
+        TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let result = async {
+        tokio::fs::write(&tmp, data).await?;
+        tokio::fs::rename(&tmp, dir.join(name)).await
+    }
+    .await;
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&tmp).await;
+    }
+    Ok(result?)
+}
+
+fn decode_file<'a>(data: &'a [u8], key: &CacheKey) -> Option<&'a [u8]> {
+    let len = u32::from_le_bytes(data.get(..4)?.try_into().ok()?) as usize;
+    let stored_key = data.get(4..4 + len)?;
+    (stored_key == key.0.as_bytes()).then(|| &data[4 + len..])
+}
+
+/// 64ビットのFNV-1aハッシュ
+/// ファイル名に使うため、Rustのバージョンによらず同じ値になるものを使う
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(text: &str) -> CacheKey {
+        CacheKey::new(text, &"3".parse().unwrap(), &VoiceParams::default()).unwrap()
+    }
+
+    #[test]
+    fn key_normalizes_whitespace() {
+        assert_eq!(key("  リンク 　省略 "), key("リンク 省略"));
+        assert_ne!(key("草"), key("草草"));
+    }
+
+    #[test]
+    fn key_depends_on_voice_and_params() {
+        let params = VoiceParams {
+            speed_scale: Some(1.5),
+            ..Default::default()
+        };
+        let with_params = CacheKey::new("草", &"3".parse().unwrap(), &params).unwrap();
+        let other_voice =
+            CacheKey::new("草", &"1".parse().unwrap(), &VoiceParams::default()).unwrap();
+
+        assert_ne!(key("草"), with_params);
+        assert_ne!(key("草"), other_voice);
+    }
+
+    #[test]
+    fn key_depends_on_morph() {
+        let request = |rate: f64| SynthesisRequest {
+            text: "草".to_string(),
+            voice: "3".parse().unwrap(),
+            params: VoiceParams::default(),
+            morph: Some(Morph {
+                target: "1".parse().unwrap(),
+                rate,
+            }),
+            format: TextFormat::Plain,
+        };
+        let half = CacheKey::from_request(&request(0.5)).unwrap();
+
+        assert_ne!(key("草"), half);
+        assert_ne!(CacheKey::from_request(&request(0.8)).unwrap(), half);
+    }
+
+    #[test]
+    fn long_text_is_not_cached() {
+        let text = "あ".repeat(MAX_CACHEABLE_TEXT_CHARS + 1);
+        let voice = "3".parse().unwrap();
+        assert!(CacheKey::new(&text, &voice, &VoiceParams::default()).is_none());
+    }
+
+    #[tokio::test]
+    async fn evicts_least_recently_used() {
+        let cache = AudioCache::new(10);
+        cache.insert(key("a"), Arc::new(vec![0; 4])).await;
+        cache.insert(key("b"), Arc::new(vec![0; 4])).await;
+        assert!(cache.get(&key("a")).await.is_some());
+
+        cache.insert(key("c"), Arc::new(vec![0; 4])).await;
+        assert!(cache.get(&key("a")).await.is_some());
+        assert!(cache.get(&key("b")).await.is_none());
+        assert!(cache.get(&key("c")).await.is_some());
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (3, 1));
+        assert_eq!((stats.entries, stats.bytes), (2, 8));
+    }
+
+    #[tokio::test]
+    async fn disk_cache_survives_restart() {
+        let dir = std::env::temp_dir().join(format!("bot-speech-cache-{}", std::process::id()));
+
+        let cache = AudioCache::new(0).with_disk(&dir, 1024).unwrap();
+        cache.insert(key("草"), Arc::new(vec![1, 2, 3])).await;
+
+        let reopened = AudioCache::new(0).with_disk(&dir, 1024).unwrap();
+        assert_eq!(
+            reopened.get(&key("草")).await.as_deref(),
+            Some(&vec![1, 2, 3])
+        );
+        assert_eq!(reopened.stats().disk_hits, 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,225 @@
+use log::{info, warn};
+use std::fmt::Display;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 連続してこの回数だけ失敗したエンドポイントは一時的に切り離す
+const FAILURE_THRESHOLD: u32 = 3;
+/// 切り離したエンドポイントに再びリクエストを送るまでの時間
+const OPEN_DURATION: Duration = Duration::from_secs(30);
+
+/// リクエストのタイムアウトの既定値
+/// 長い文章の合成には時間がかかるため、余裕を持たせている
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// エンドポイントごとの設定
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndpointOptions {
+    pub api_base: String,
+    /// 1回のリクエスト（応答の受信を含む）のタイムアウト
+    pub timeout: Duration,
+}
+
+impl EndpointOptions {
+    pub fn new(api_base: impl Into<String>) -> Self {
+        Self {
+            api_base: api_base.into(),
+            timeout: DEFAULT_REQUEST_TIMEOUT,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+impl From<String> for EndpointOptions {
+    fn from(api_base: String) -> Self {
+        Self::new(api_base)
+    }
+}
+
+/// 複数のエンドポイントへのリクエストの振り分け方
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LoadBalancing {
+    /// 順番に振り分ける
+    #[default]
+    RoundRobin,
+    /// 処理中のリクエストが最も少ないエンドポイントに振り分ける
+    LeastBusy,
+}
+
+/// エンドポイントの状態（管理者向けの表示用）
+#[derive(Debug, Clone)]
+pub struct EndpointStatus {
+    pub api_base: String,
+    pub healthy: bool,
+    /// 障害により切り離されているか
+    pub circuit_open: bool,
+    pub in_flight: usize,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+    /// ヘルスチェックで取得したエンジンのバージョン
+    pub version: Option<String>,
+}
+
+pub(crate) struct EndpointPool {
+    endpoints: Vec<Endpoint>,
+    strategy: LoadBalancing,
+    cursor: AtomicUsize,
+}
+
+impl EndpointPool {
+    pub(crate) fn new(endpoints: Vec<EndpointOptions>, strategy: LoadBalancing) -> Self {
+        Self {
+            endpoints: endpoints.into_iter().map(Endpoint::new).collect(),
+            strategy,
+            cursor: AtomicUsize::new(0),
+        }
+    }
+
+    pub(crate) fn endpoints(&self) -> &[Endpoint] {
+        &self.endpoints
+    }
+
+    /// リクエストを送る順にエンドポイントを返す
+    /// 切り離されているエンドポイントは、他がすべて失敗したときのために末尾に置く
+    pub(crate) fn candidates(&self) -> Vec<&Endpoint> {
+        let len = self.endpoints.len();
+        if len == 0 {
+            return Vec::new();
+        }
+
+        let start = self.cursor.fetch_add(1, Ordering::Relaxed) % len;
+        let mut ordered = (0..len)
+            .map(|i| &self.endpoints[(start + i) % len])
+            .collect::<Vec<_>>();
+
+        if self.strategy == LoadBalancing::LeastBusy {
+            // 安定ソートなので、処理中の数が同じなら順番に振り分けられる
+            ordered.sort_by_key(|endpoint| endpoint.in_flight.load(Ordering::Relaxed));
+        }
+        ordered.sort_by_key(|endpoint| !endpoint.is_available());
+
+        ordered
+    }
+
+    pub(crate) fn status(&self) -> Vec<EndpointStatus> {
+        self.endpoints.iter().map(Endpoint::status).collect()
+    }
+}
+
+pub(crate) struct Endpoint {
+    pub(crate) api_base: String,
+    pub(crate) timeout: Duration,
+    in_flight: AtomicUsize,
+    state: Mutex<EndpointState>,
+}
+
+#[derive(Default)]
+struct EndpointState {
+    healthy: bool,
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    last_error: Option<String>,
+    version: Option<String>,
+}
+
+impl Endpoint {
+    fn new(options: EndpointOptions) -> Self {
+        Self {
+            api_base: options.api_base,
+            timeout: options.timeout,
+            in_flight: AtomicUsize::new(0),
+            state: Mutex::new(EndpointState {
+                healthy: true,
+                ..Default::default()
+            }),
+        }
+    }
+
+    fn is_available(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state.open_until.is_none_or(|t| Instant::now() >= t)
+    }
+
+    /// 処理中のリクエストとして数える
+    /// 戻り値がdropされるまで処理中とみなす
+    pub(crate) fn begin(&self) -> InFlightGuard<'_> {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        InFlightGuard(self)
+    }
+
+    pub(crate) fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        if !state.healthy {
+            info!("VOICEVOX endpoint {} recovered", self.api_base);
+        }
+        state.healthy = true;
+        state.consecutive_failures = 0;
+        state.open_until = None;
+    }
+
+    pub(crate) fn record_failure(&self, err: &impl Display) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures += 1;
+        state.last_error = Some(err.to_string());
+
+        if state.consecutive_failures >= FAILURE_THRESHOLD {
+            if state.healthy {
+                warn!(
+                    "VOICEVOX endpoint {} is down after {} consecutive failures: {}",
+                    self.api_base, state.consecutive_failures, err
+                );
+            }
+            state.healthy = false;
+            state.open_until = Some(Instant::now() + OPEN_DURATION);
+        }
+    }
+
+    pub(crate) fn record_health<E: Display>(&self, version: Result<String, E>) {
+        match version {
+            Ok(version) => {
+                self.state.lock().unwrap().version = Some(version);
+                self.record_success();
+            }
+            Err(err) => {
+                // ヘルスチェックの失敗は即座に切り離す
+                let mut state = self.state.lock().unwrap();
+                if state.healthy {
+                    warn!(
+                        "VOICEVOX endpoint {} failed health check: {}",
+                        self.api_base, err
+                    );
+                }
+                state.healthy = false;
+                state.consecutive_failures = state.consecutive_failures.max(FAILURE_THRESHOLD);
+                state.open_until = Some(Instant::now() + OPEN_DURATION);
+                state.last_error = Some(err.to_string());
+            }
+        }
+    }
+
+    fn status(&self) -> EndpointStatus {
+        let state = self.state.lock().unwrap();
+        EndpointStatus {
+            api_base: self.api_base.clone(),
+            healthy: state.healthy,
+            circuit_open: state.open_until.is_some_and(|t| Instant::now() < t),
+            in_flight: self.in_flight.load(Ordering::Relaxed),
+            consecutive_failures: state.consecutive_failures,
+            last_error: state.last_error.clone(),
+            version: state.version.clone(),
+        }
+    }
+}
+
+pub(crate) struct InFlightGuard<'a>(&'a Endpoint);
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,79 @@
+use super::voicevox::{morphable_targets_with, sing_with, speakers_to_voices, synthesize_with};
+use super::{EngineStatus, SingRequest, SpeechEngine, SynthesisRequest, Voice, VoiceId};
+use crate::voicevox::VoicevoxClient;
+use anyhow::Result;
+use async_trait::async_trait;
+use bot_audio::EncodedAudio;
+use log::info;
+
+/// AivisSpeech や COEIROINK など、VOICEVOX互換のAPIを持つエンジン
+///
+/// スタイルIDはVOICEVOXと衝突しうるため、エンジンごとに名前空間を割り当てる。
+/// プリセットや話者の事前初期化はエンジンによって対応状況が異なるため使用しない。
+pub struct CompatibleEngine {
+    namespace: String,
+    client: VoicevoxClient,
+}
+
+impl CompatibleEngine {
+    pub fn new(namespace: impl Into<String>, client: VoicevoxClient) -> Self {
+        Self {
+            namespace: namespace.into(),
+            client,
+        }
+    }
+}
+
+#[async_trait]
+impl SpeechEngine for CompatibleEngine {
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    async fn initialize(&self) -> Result<()> {
+        let capabilities = self.client.detect_capabilities().await?;
+        info!(
+            "Engine {} capabilities: {:?}",
+            self.namespace(),
+            capabilities
+        );
+
+        // 話者一覧を取得できることだけ確認し、モデルの読み込みはエンジンに任せる
+        self.client.speakers().await?;
+        Ok(())
+    }
+
+    async fn voices(&self) -> Result<Vec<Voice>> {
+        let speakers = self.client.speakers().await?;
+        Ok(speakers_to_voices(self.namespace(), speakers))
+    }
+
+    async fn invalidate_catalog(&self) {
+        self.client.invalidate_catalog().await;
+    }
+
+    fn status(&self) -> Vec<EngineStatus> {
+        vec![EngineStatus {
+            namespace: self.namespace().to_string(),
+            endpoints: self.client.endpoint_status(),
+            capabilities: self.client.capabilities(),
+        }]
+    }
+
+    async fn morphable_targets(&self, base: &VoiceId) -> Result<Vec<VoiceId>> {
+        morphable_targets_with(&self.client, self.namespace(), base).await
+    }
+
+    async fn singers(&self) -> Result<Vec<Voice>> {
+        let singers = self.client.singers().await?;
+        Ok(speakers_to_voices(self.namespace(), singers))
+    }
+
+    async fn sing(&self, request: SingRequest) -> Result<EncodedAudio> {
+        sing_with(&self.client, self.namespace(), request).await
+    }
+
+    async fn synthesize(&self, request: SynthesisRequest) -> Result<EncodedAudio> {
+        synthesize_with(&self.client, self.namespace(), request).await
+    }
+}
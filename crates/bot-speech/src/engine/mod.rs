@@ -0,0 +1,262 @@
+mod compatible;
+mod registry;
+mod stub;
+mod voicevox;
+
+pub use compatible::CompatibleEngine;
+pub use registry::EngineRegistry;
+pub use stub::StubEngine;
+pub use voicevox::VoicevoxEngine;
+
+use crate::endpoint::EndpointStatus;
+use crate::sing::Score;
+use crate::speech::VoiceParams;
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use bot_audio::EncodedAudio;
+use std::fmt;
+use std::str::FromStr;
+
+/// 名前空間を省略したスタイルIDが属するエンジン
+pub const DEFAULT_NAMESPACE: &str = "voicevox";
+
+/// 音声合成エンジン
+#[async_trait]
+pub trait SpeechEngine: Send + Sync {
+    /// このエンジンのスタイルIDが属する名前空間
+    fn namespace(&self) -> &str;
+
+    /// 起動時の初期化処理（モデルの読み込みなど）
+    async fn initialize(&self) -> Result<()>;
+
+    /// 利用できる声の一覧
+    async fn voices(&self) -> Result<Vec<Voice>>;
+
+    /// 利用できるプリセットの一覧
+    /// プリセットに対応していないエンジンは空の[`Vec`]を返す
+    async fn presets(&self) -> Result<Vec<VoicePreset>> {
+        Ok(Vec::new())
+    }
+
+    /// 声やプリセットの一覧のキャッシュを破棄する
+    async fn invalidate_catalog(&self) {}
+
+    /// エンジンの状態（管理者向けの表示用）
+    fn status(&self) -> Vec<EngineStatus> {
+        vec![EngineStatus {
+            namespace: self.namespace().to_string(),
+            endpoints: Vec::new(),
+            capabilities: None,
+        }]
+    }
+
+    /// ユーザー辞書を`dict`の内容に合わせる
+    /// ユーザー辞書に対応していないエンジンは何もしない
+    async fn sync_user_dict(&self, _dict: &UserDictSync) -> Result<()> {
+        Ok(())
+    }
+
+    /// `base`とモーフィングできるスタイルの一覧
+    /// モーフィングに対応していないエンジンは空の[`Vec`]を返す
+    async fn morphable_targets(&self, _base: &VoiceId) -> Result<Vec<VoiceId>> {
+        Ok(Vec::new())
+    }
+
+    /// 歌唱に使える声の一覧
+    /// 歌唱に対応していないエンジンは空の[`Vec`]を返す
+    async fn singers(&self) -> Result<Vec<Voice>> {
+        Ok(Vec::new())
+    }
+
+    /// 楽譜を歌わせる
+    async fn sing(&self, _request: SingRequest) -> Result<EncodedAudio> {
+        bail!("Engine {} does not support singing", self.namespace())
+    }
+
+    /// テキストを音声に変換する
+    async fn synthesize(&self, request: SynthesisRequest) -> Result<EncodedAudio>;
+}
+
+/// 名前空間付きのスタイルID
+/// 文字列では`aivis:888753760`のように表し、名前空間を省略した場合は[`DEFAULT_NAMESPACE`]とみなす
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VoiceId {
+    pub namespace: String,
+    pub style_id: i64,
+}
+
+impl VoiceId {
+    pub fn new(namespace: impl Into<String>, style_id: i64) -> Self {
+        Self {
+            namespace: namespace.into(),
+            style_id,
+        }
+    }
+}
+
+impl fmt::Display for VoiceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.namespace == DEFAULT_NAMESPACE {
+            write!(f, "{}", self.style_id)
+        } else {
+            write!(f, "{}:{}", self.namespace, self.style_id)
+        }
+    }
+}
+
+impl FromStr for VoiceId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (namespace, style_id) = match s.rsplit_once(':') {
+            Some((namespace, style_id)) => (namespace, style_id),
+            None => (DEFAULT_NAMESPACE, s),
+        };
+        if namespace.is_empty() {
+            bail!("Empty namespace in voice ID: {}", s);
+        }
+        let style_id = style_id
+            .trim()
+            .parse::<i64>()
+            .map_err(|_| anyhow!("Invalid style ID in voice ID: {}", s))?;
+
+        Ok(Self::new(namespace, style_id))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Voice {
+    pub id: VoiceId,
+    pub speaker_name: String,
+    pub speaker_uuid: String,
+    pub style_name: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct VoicePreset {
+    pub id: i64,
+    pub name: String,
+    pub voice: VoiceId,
+    pub params: VoiceParams,
+}
+
+#[derive(Debug, Clone)]
+pub struct SynthesisRequest {
+    pub text: String,
+    pub voice: VoiceId,
+    pub params: VoiceParams,
+    /// 指定した場合は`voice`と`morph.target`を混ぜ合わせた声で合成する
+    pub morph: Option<Morph>,
+    pub format: TextFormat,
+}
+
+#[derive(Debug, Clone)]
+pub struct SingRequest {
+    pub score: Score,
+    /// [`SpeechEngine::singers`]に含まれる声
+    pub voice: VoiceId,
+}
+
+/// 合成する文章の書き方
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextFormat {
+    /// 普通の文章
+    #[default]
+    Plain,
+    /// AquesTalk風記法のカナ（例: `コンニチワ'`）
+    /// 読みとアクセントをそのまま使い、エンジンによる解析を行わない
+    Kana,
+}
+
+/// 2つのスタイルを混ぜ合わせる（モーフィング）指定
+#[derive(Debug, Clone, PartialEq)]
+pub struct Morph {
+    /// 混ぜ合わせる相手のスタイル
+    pub target: VoiceId,
+    /// 0.0で元のスタイル、1.0で`target`の声となる
+    pub rate: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct EngineStatus {
+    pub namespace: String,
+    pub endpoints: Vec<EndpointStatus>,
+    /// 対応している機能。まだ確認できていない場合は`None`
+    pub capabilities: Option<EngineCapabilities>,
+}
+
+/// エンジンのバージョンと対応している機能
+/// `/version`・`/engine_manifest`・`/supported_devices`などから起動時に判定する
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EngineCapabilities {
+    pub version: String,
+    /// `/engine_manifest`の`name`。マニフェストのない古いエンジンでは`None`
+    pub engine_name: Option<String>,
+    /// 合成に使える装置（`cpu`・`cuda`・`dml`など）
+    pub devices: Vec<String>,
+    /// `/presets`と`/audio_query_from_preset`
+    pub presets: bool,
+    /// `/user_dict`
+    pub user_dict: bool,
+    /// `/synthesis_morphing`と`/morphable_targets`
+    pub morphing: bool,
+    /// 歌唱音声の合成
+    pub sing: bool,
+    /// `/engine_manifest`の`default_sampling_rate`。`/audio_query`が返す音声クエリの出力のサンプリングレート
+    pub sampling_rate: Option<u32>,
+}
+
+/// エンジンのユーザー辞書に登録する単語
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserDictWord {
+    pub surface: String,
+    /// カタカナの読み
+    pub pronunciation: String,
+    /// アクセント核の位置（何モーラ目で音が下がるか）。0は平板型
+    pub accent_type: u32,
+    pub word_type: WordType,
+}
+
+/// ユーザー辞書に登録する単語の品詞
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordType {
+    ProperNoun,
+    CommonNoun,
+    Verb,
+    Adjective,
+    Suffix,
+}
+
+impl WordType {
+    pub const ALL: [WordType; 5] = [
+        WordType::ProperNoun,
+        WordType::CommonNoun,
+        WordType::Verb,
+        WordType::Adjective,
+        WordType::Suffix,
+    ];
+
+    /// VOICEVOX ENGINEの`word_type`に指定する名前
+    pub fn api_name(self) -> &'static str {
+        match self {
+            WordType::ProperNoun => "PROPER_NOUN",
+            WordType::CommonNoun => "COMMON_NOUN",
+            WordType::Verb => "VERB",
+            WordType::Adjective => "ADJECTIVE",
+            WordType::Suffix => "SUFFIX",
+        }
+    }
+
+    pub fn from_api_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|x| x.api_name() == name)
+    }
+}
+
+/// ユーザー辞書の同期内容
+#[derive(Debug, Clone, Default)]
+pub struct UserDictSync {
+    pub words: Vec<UserDictWord>,
+    /// Botが登録したことのある表記
+    /// これらの表記のうち`words`に含まれないものはエンジンから削除する。含まれない表記の単語には手を触れない
+    pub managed_surfaces: Vec<String>,
+}
@@ -0,0 +1,164 @@
+use super::{
+    EngineStatus, SingRequest, SpeechEngine, SynthesisRequest, UserDictSync, Voice, VoiceId,
+    VoicePreset,
+};
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use bot_audio::EncodedAudio;
+use log::warn;
+use std::sync::Arc;
+
+/// 複数のエンジンを束ね、スタイルIDの名前空間に応じて振り分けるエンジン
+pub struct EngineRegistry {
+    engines: Vec<Arc<dyn SpeechEngine>>,
+}
+
+impl EngineRegistry {
+    /// `default`は名前空間を省略したスタイルIDを扱うエンジンとなる
+    pub fn new(default: Arc<dyn SpeechEngine>) -> Self {
+        Self {
+            engines: vec![default],
+        }
+    }
+
+    pub fn register(&mut self, engine: Arc<dyn SpeechEngine>) -> Result<()> {
+        let namespace = engine.namespace();
+        if namespace.is_empty() || namespace.contains(':') {
+            bail!("Invalid engine namespace: {:?}", namespace);
+        }
+        if self.engines.iter().any(|e| e.namespace() == namespace) {
+            bail!("Engine namespace {} is already registered", namespace);
+        }
+
+        self.engines.push(engine);
+        Ok(())
+    }
+
+    fn get(&self, namespace: &str) -> Result<&Arc<dyn SpeechEngine>> {
+        self.engines
+            .iter()
+            .find(|e| e.namespace() == namespace)
+            .ok_or_else(|| anyhow!("Engine {} is not registered", namespace))
+    }
+}
+
+#[async_trait]
+impl SpeechEngine for EngineRegistry {
+    fn namespace(&self) -> &str {
+        self.engines[0].namespace()
+    }
+
+    async fn initialize(&self) -> Result<()> {
+        let mut result = Ok(());
+        for engine in &self.engines {
+            if let Err(err) = engine.initialize().await {
+                warn!(
+                    "Failed to initialize engine {}: {:?}",
+                    engine.namespace(),
+                    err
+                );
+                result = Err(err);
+            }
+        }
+        result
+    }
+
+    /// すべてのエンジンの声を返す
+    /// 一部のエンジンが応答しなくても、応答したエンジンの声は返す
+    async fn voices(&self) -> Result<Vec<Voice>> {
+        let mut voices = Vec::new();
+        let mut last_err = None;
+        for engine in &self.engines {
+            match engine.voices().await {
+                Ok(list) => voices.extend(list),
+                Err(err) => {
+                    warn!(
+                        "Failed to list voices of engine {}: {:?}",
+                        engine.namespace(),
+                        err
+                    );
+                    last_err = Some(err);
+                }
+            }
+        }
+
+        match last_err {
+            Some(err) if voices.is_empty() => Err(err),
+            _ => Ok(voices),
+        }
+    }
+
+    async fn presets(&self) -> Result<Vec<VoicePreset>> {
+        let mut presets = Vec::new();
+        for engine in &self.engines {
+            match engine.presets().await {
+                Ok(list) => presets.extend(list),
+                Err(err) => {
+                    warn!(
+                        "Failed to list presets of engine {}: {:?}",
+                        engine.namespace(),
+                        err
+                    )
+                }
+            }
+        }
+        Ok(presets)
+    }
+
+    async fn invalidate_catalog(&self) {
+        for engine in &self.engines {
+            engine.invalidate_catalog().await;
+        }
+    }
+
+    fn status(&self) -> Vec<EngineStatus> {
+        self.engines.iter().flat_map(|e| e.status()).collect()
+    }
+
+    async fn sync_user_dict(&self, dict: &UserDictSync) -> Result<()> {
+        let mut result = Ok(());
+        for engine in &self.engines {
+            if let Err(err) = engine.sync_user_dict(dict).await {
+                warn!(
+                    "Failed to sync user dictionary of engine {}: {:?}",
+                    engine.namespace(),
+                    err
+                );
+                result = Err(err);
+            }
+        }
+        result
+    }
+
+    async fn morphable_targets(&self, base: &VoiceId) -> Result<Vec<VoiceId>> {
+        self.get(&base.namespace)?.morphable_targets(base).await
+    }
+
+    /// 歌唱に対応しているすべてのエンジンの声を返す
+    async fn singers(&self) -> Result<Vec<Voice>> {
+        let mut singers = Vec::new();
+        for engine in &self.engines {
+            match engine.singers().await {
+                Ok(list) => singers.extend(list),
+                Err(err) => {
+                    warn!(
+                        "Failed to list singers of engine {}: {:?}",
+                        engine.namespace(),
+                        err
+                    )
+                }
+            }
+        }
+        Ok(singers)
+    }
+
+    async fn sing(&self, request: SingRequest) -> Result<EncodedAudio> {
+        self.get(&request.voice.namespace)?.sing(request).await
+    }
+
+    async fn synthesize(&self, request: SynthesisRequest) -> Result<EncodedAudio> {
+        self.get(&request.voice.namespace)?
+            .synthesize(request)
+            .await
+    }
+}
@@ -0,0 +1,171 @@
+use super::{SpeechEngine, SynthesisRequest, Voice, VoiceId};
+use anyhow::{ensure, Result};
+use async_trait::async_trait;
+use bot_audio::EncodedAudio;
+
+const SAMPLE_RATE: u32 = 24000;
+const MILLIS_PER_CHAR: u32 = 80;
+const STYLE_NAMES: [&str; 2] = ["ノーマル", "高め"];
+
+/// ネットワークを使わずに決定的な音声を返すエンジン
+///
+/// テキストの長さに比例した長さの正弦波をWAV形式で返す。テストや、エンジンを用意できない環境での動作確認に使う。
+pub struct StubEngine {
+    namespace: String,
+}
+
+impl StubEngine {
+    pub fn new(namespace: impl Into<String>) -> Self {
+        Self {
+            namespace: namespace.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl SpeechEngine for StubEngine {
+    fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    async fn initialize(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn voices(&self) -> Result<Vec<Voice>> {
+        let voices = STYLE_NAMES
+            .iter()
+            .enumerate()
+            .map(|(i, name)| Voice {
+                id: VoiceId::new(self.namespace(), i as i64),
+                speaker_name: "スタブ".to_string(),
+                speaker_uuid: "00000000-0000-0000-0000-000000000000".to_string(),
+                style_name: name.to_string(),
+            })
+            .collect();
+        Ok(voices)
+    }
+
+    async fn synthesize(&self, request: SynthesisRequest) -> Result<EncodedAudio> {
+        ensure!(
+            request.voice.namespace == self.namespace
+                && (0..STYLE_NAMES.len() as i64).contains(&request.voice.style_id),
+            "Voice {} is not available in stub engine",
+            request.voice
+        );
+        ensure!(
+            request.morph.is_none(),
+            "Stub engine does not support morphing"
+        );
+
+        let speed = request.params.speed_scale.unwrap_or(1.0).max(0.1);
+        let volume = request.params.volume_scale.unwrap_or(1.0).clamp(0.0, 2.0);
+        let frequency = 440.0 * (1.0 + request.voice.style_id as f64 * 0.5);
+
+        let chars = request.text.chars().count() as u32;
+        let millis = (chars * MILLIS_PER_CHAR) as f64 / speed;
+        let samples = (SAMPLE_RATE as f64 * millis / 1000.0) as u32;
+
+        let pcm = (0..samples)
+            .map(|n| {
+                let t = n as f64 / SAMPLE_RATE as f64;
+                let x = (2.0 * std::f64::consts::PI * frequency * t).sin() * 0.3 * volume;
+                (x * i16::MAX as f64) as i16
+            })
+            .collect::<Vec<_>>();
+
+        Ok(EncodedAudio::from(encode_wav(&pcm)))
+    }
+}
+
+/// 16-bit モノラルのWAVを作る
+fn encode_wav(samples: &[i16]) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut buf = Vec::with_capacity(44 + data_len as usize);
+
+    buf.extend_from_slice(b"RIFF");
+    buf.extend_from_slice(&(36 + data_len).to_le_bytes());
+    buf.extend_from_slice(b"WAVE");
+    buf.extend_from_slice(b"fmt ");
+    buf.extend_from_slice(&16u32.to_le_bytes());
+    buf.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    buf.extend_from_slice(&1u16.to_le_bytes()); // mono
+    buf.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    buf.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    buf.extend_from_slice(&2u16.to_le_bytes());
+    buf.extend_from_slice(&16u16.to_le_bytes());
+    buf.extend_from_slice(b"data");
+    buf.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        buf.extend_from_slice(&sample.to_le_bytes());
+    }
+
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{EngineRegistry, TextFormat};
+    use crate::speech::VoiceParams;
+    use std::sync::Arc;
+
+    fn request(voice: &str, text: &str) -> SynthesisRequest {
+        SynthesisRequest {
+            text: text.to_string(),
+            voice: voice.parse().unwrap(),
+            params: VoiceParams::default(),
+            morph: None,
+            format: TextFormat::Plain,
+        }
+    }
+
+    #[tokio::test]
+    async fn synthesis_is_deterministic() {
+        let engine = StubEngine::new("stub");
+
+        let a: Vec<u8> = engine
+            .synthesize(request("stub:0", "こんにちは"))
+            .await
+            .unwrap()
+            .into();
+        let b: Vec<u8> = engine
+            .synthesize(request("stub:0", "こんにちは"))
+            .await
+            .unwrap()
+            .into();
+        let c: Vec<u8> = engine
+            .synthesize(request("stub:1", "こんにちは"))
+            .await
+            .unwrap()
+            .into();
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(&a[..4], b"RIFF");
+        assert_eq!(a.len(), 44 + 5 * 80 * 24 * 2);
+    }
+
+    #[tokio::test]
+    async fn registry_routes_by_namespace() {
+        let mut registry = EngineRegistry::new(Arc::new(StubEngine::new("voicevox")));
+        registry
+            .register(Arc::new(StubEngine::new("aivis")))
+            .unwrap();
+        assert!(registry
+            .register(Arc::new(StubEngine::new("aivis")))
+            .is_err());
+
+        let voices = registry.voices().await.unwrap();
+        assert_eq!(voices.len(), 4);
+        assert_eq!(voices[0].id.to_string(), "0");
+        assert_eq!(voices[2].id.to_string(), "aivis:0");
+
+        assert!(registry.synthesize(request("1", "あ")).await.is_ok());
+        assert!(registry.synthesize(request("aivis:1", "あ")).await.is_ok());
+        assert!(registry
+            .synthesize(request("coeiroink:1", "あ"))
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,243 @@
+use super::{
+    EngineStatus, SingRequest, SpeechEngine, SynthesisRequest, TextFormat, UserDictSync, Voice,
+    VoiceId, VoicePreset, DEFAULT_NAMESPACE,
+};
+use crate::speech::VoiceParams;
+use crate::voicevox::{MorphingParams, Speaker, SynthesisParams, VoicevoxClient};
+use anyhow::{anyhow, ensure, Result};
+use async_trait::async_trait;
+use bot_audio::EncodedAudio;
+use log::info;
+
+/// VOICEVOX ENGINE
+pub struct VoicevoxEngine {
+    client: VoicevoxClient,
+}
+
+impl VoicevoxEngine {
+    pub fn new(client: VoicevoxClient) -> Self {
+        Self { client }
+    }
+
+    pub fn client(&self) -> &VoicevoxClient {
+        &self.client
+    }
+}
+
+#[async_trait]
+impl SpeechEngine for VoicevoxEngine {
+    fn namespace(&self) -> &str {
+        DEFAULT_NAMESPACE
+    }
+
+    async fn initialize(&self) -> Result<()> {
+        let capabilities = self.client.detect_capabilities().await?;
+        info!(
+            "Engine {} capabilities: {:?}",
+            self.namespace(),
+            capabilities
+        );
+
+        // プリセットに対応していないエンジンでは空になる
+        let preset_list = self.client.presets().await?;
+        for preset in preset_list {
+            self.client.initialize_speaker(preset.style_id).await?;
+        }
+        Ok(())
+    }
+
+    async fn voices(&self) -> Result<Vec<Voice>> {
+        let speakers = self.client.speakers().await?;
+        Ok(speakers_to_voices(self.namespace(), speakers))
+    }
+
+    async fn presets(&self) -> Result<Vec<VoicePreset>> {
+        let preset_list = self.client.presets().await?;
+        let presets = preset_list
+            .into_iter()
+            .map(|p| VoicePreset {
+                id: p.id,
+                name: p.name,
+                voice: VoiceId::new(self.namespace(), p.style_id),
+                params: VoiceParams {
+                    speed_scale: Some(p.speed_scale),
+                    pitch_scale: Some(p.pitch_scale),
+                    intonation_scale: Some(p.intonation_scale),
+                    volume_scale: Some(p.volume_scale),
+                    pre_phoneme_length: Some(p.pre_phoneme_length),
+                    post_phoneme_length: Some(p.post_phoneme_length),
+                },
+            })
+            .collect();
+        Ok(presets)
+    }
+
+    async fn invalidate_catalog(&self) {
+        self.client.invalidate_catalog().await;
+    }
+
+    fn status(&self) -> Vec<EngineStatus> {
+        vec![EngineStatus {
+            namespace: self.namespace().to_string(),
+            endpoints: self.client.endpoint_status(),
+            capabilities: self.client.capabilities(),
+        }]
+    }
+
+    async fn sync_user_dict(&self, dict: &UserDictSync) -> Result<()> {
+        Ok(self.client.sync_user_dict(dict).await?)
+    }
+
+    async fn morphable_targets(&self, base: &VoiceId) -> Result<Vec<VoiceId>> {
+        morphable_targets_with(&self.client, self.namespace(), base).await
+    }
+
+    async fn singers(&self) -> Result<Vec<Voice>> {
+        let singers = self.client.singers().await?;
+        Ok(speakers_to_voices(self.namespace(), singers))
+    }
+
+    async fn sing(&self, request: SingRequest) -> Result<EncodedAudio> {
+        sing_with(&self.client, self.namespace(), request).await
+    }
+
+    async fn synthesize(&self, request: SynthesisRequest) -> Result<EncodedAudio> {
+        synthesize_with(&self.client, self.namespace(), request).await
+    }
+}
+
+/// VOICEVOX互換APIの`/speakers`を[`Voice`]の一覧に変換する
+pub(super) fn speakers_to_voices(namespace: &str, speakers: Vec<Speaker>) -> Vec<Voice> {
+    speakers
+        .into_iter()
+        .flat_map(|sp| {
+            sp.styles.into_iter().map(move |st| Voice {
+                id: VoiceId::new(namespace, st.id),
+                speaker_name: sp.name.clone(),
+                speaker_uuid: sp.speaker_uuid.clone(),
+                style_name: st.name,
+            })
+        })
+        .collect()
+}
+
+/// VOICEVOX互換APIの`/audio_query`と`/synthesis`で音声を合成する
+pub(super) async fn synthesize_with(
+    client: &VoicevoxClient,
+    namespace: &str,
+    request: SynthesisRequest,
+) -> Result<EncodedAudio> {
+    ensure!(
+        request.voice.namespace == namespace,
+        "Voice {} does not belong to engine {}",
+        request.voice,
+        namespace
+    );
+
+    let style_id = request.voice.style_id;
+    let query = match request.format {
+        TextFormat::Plain => client.generate_query(request.text, style_id).await?,
+        TextFormat::Kana => {
+            client
+                .generate_query_from_kana(request.text, style_id)
+                .await?
+        }
+    };
+    let query = request.params.apply(&query)?;
+
+    let Some(morph) = request.morph else {
+        return Ok(client
+            .synthesis(SynthesisParams { style_id, query })
+            .await?);
+    };
+
+    ensure!(
+        morph.target.namespace == namespace,
+        "Voice {} cannot be morphed with {}",
+        request.voice,
+        morph.target
+    );
+    Ok(client
+        .synthesis_morphing(MorphingParams {
+            base_style_id: style_id,
+            target_style_id: morph.target.style_id,
+            morph_rate: morph.rate,
+            query,
+        })
+        .await?)
+}
+
+/// 楽譜の前後に入れる休符の長さ（フレーム数）
+/// `/sing_frame_audio_query`は先頭が休符であることを求める
+const EDGE_REST_FRAMES: u32 = 15;
+
+/// VOICEVOX互換APIの`/sing_frame_audio_query`と`/frame_synthesis`で楽譜を歌わせる
+pub(super) async fn sing_with(
+    client: &VoicevoxClient,
+    namespace: &str,
+    request: SingRequest,
+) -> Result<EncodedAudio> {
+    ensure!(
+        request.voice.namespace == namespace,
+        "Voice {} does not belong to engine {}",
+        request.voice,
+        namespace
+    );
+
+    // 音程や音素の長さは、種類が`sing`のスタイルに決めてもらう
+    let singers = client.singers().await?;
+    let teacher = singers
+        .iter()
+        .flat_map(|sp| &sp.styles)
+        .find(|st| st.kind.as_deref() == Some("sing"))
+        .ok_or_else(|| anyhow!("Engine {} has no style to build singing queries", namespace))?
+        .id;
+
+    let rest = serde_json::json!({ "key": null, "frame_length": EDGE_REST_FRAMES, "lyric": "" });
+    let lengths = request.score.frame_lengths()?;
+    let notes = std::iter::once(rest.clone())
+        .chain(
+            request
+                .score
+                .notes
+                .iter()
+                .zip(lengths)
+                .map(|(note, frame_length)| {
+                    serde_json::json!({
+                        "key": note.key,
+                        "frame_length": frame_length,
+                        "lyric": note.lyric,
+                    })
+                }),
+        )
+        .chain(std::iter::once(rest))
+        .collect::<Vec<_>>();
+
+    let query = client
+        .sing_frame_audio_query(&serde_json::json!({ "notes": notes }), teacher)
+        .await?;
+    Ok(client
+        .frame_synthesis(SynthesisParams {
+            style_id: request.voice.style_id,
+            query,
+        })
+        .await?)
+}
+
+/// VOICEVOX互換APIの`/morphable_targets`で`base`とモーフィングできるスタイルを返す
+pub(super) async fn morphable_targets_with(
+    client: &VoicevoxClient,
+    namespace: &str,
+    base: &VoiceId,
+) -> Result<Vec<VoiceId>> {
+    if base.namespace != namespace {
+        return Ok(Vec::new());
+    }
+
+    let ids = client.morphable_targets(base.style_id).await?;
+    Ok(ids
+        .into_iter()
+        .filter(|&id| id != base.style_id)
+        .map(|id| VoiceId::new(namespace, id))
+        .collect())
+}
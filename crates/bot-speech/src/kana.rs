@@ -0,0 +1,9 @@
+/// ひらがなをカタカナに変換する（それ以外の文字はそのまま）
+pub fn to_katakana(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            'ぁ'..='ゖ' => char::from_u32(c as u32 + 0x60).unwrap_or(c),
+            c => c,
+        })
+        .collect()
+}
@@ -1,2 +1,7 @@
+pub mod cache;
+pub mod endpoint;
+pub mod engine;
+pub mod kana;
+pub mod sing;
 pub mod speech;
 pub mod voicevox;
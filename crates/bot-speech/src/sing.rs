@@ -0,0 +1,432 @@
+use crate::kana::to_katakana;
+use anyhow::{anyhow, bail, ensure, Context as _, Result};
+use std::time::Duration;
+
+/// 歌唱合成のフレームレート（VOICEVOX ENGINEは24kHzを256サンプルごとに区切る）
+pub const FRAME_RATE: f64 = 24000.0 / 256.0;
+
+/// テンポを指定しなかった場合の既定値
+pub const DEFAULT_BPM: f64 = 120.0;
+
+/// 歌わせる楽譜
+#[derive(Debug, Clone, PartialEq)]
+pub struct Score {
+    /// 1分あたりの四分音符の数
+    pub bpm: f64,
+    pub notes: Vec<ScoreNote>,
+}
+
+/// 楽譜の1音
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreNote {
+    /// MIDIのノート番号（60が中央のド）。休符は`None`
+    pub key: Option<u8>,
+    /// 四分音符を1とした長さ
+    pub beats: f64,
+    /// 1モーラのカタカナ。休符は空
+    pub lyric: String,
+}
+
+impl Score {
+    /// テキスト形式の楽譜を読み込む
+    ///
+    /// 空白区切りで`音名:歌詞:長さ`を並べる（例: `bpm=120 C4:ど:4 D4:れ:8. R:4`）。
+    /// 音名は`C4`（中央のド）や`F#5`・`Bb3`、長さは`4`（四分音符）や`8.`（付点八分音符）、
+    /// `R:長さ`は休符を表す。
+    pub fn parse_text(text: &str) -> Result<Self> {
+        let mut bpm = DEFAULT_BPM;
+        let mut notes = Vec::new();
+
+        for token in text.split_whitespace() {
+            if let Some(value) = token
+                .strip_prefix("bpm=")
+                .or_else(|| token.strip_prefix("tempo="))
+            {
+                bpm = value
+                    .parse()
+                    .map_err(|_| anyhow!("テンポの形式が正しくありません: {}", token))?;
+                continue;
+            }
+
+            let note = parse_text_note(token)
+                .with_context(|| format!("音符の形式が正しくありません: {}", token))?;
+            notes.push(note);
+        }
+
+        let score = Self { bpm, notes };
+        score.validate()?;
+        Ok(score)
+    }
+
+    /// MusicXMLの楽譜を読み込む
+    ///
+    /// 最初のパートの最初の声部だけを使い、和音は一番上の音以外を無視する。
+    /// 歌詞のない音符はタイやメリスマとみなし、直前の音符を延ばす。
+    /// 圧縮された`.mxl`形式には対応しない。
+    pub fn parse_musicxml(xml: &str) -> Result<Self> {
+        let part = element(xml, "part").ok_or_else(|| anyhow!("パートが見つかりません。"))?;
+
+        let divisions = element(part, "divisions")
+            .map(|x| x.trim().parse::<f64>())
+            .transpose()
+            .map_err(|_| anyhow!("divisionsの形式が正しくありません。"))?
+            .unwrap_or(1.0);
+        ensure!(divisions > 0.0, "divisionsの形式が正しくありません。");
+        let bpm = attribute(part, "sound", "tempo")
+            .or_else(|| element(part, "per-minute"))
+            .and_then(|x| x.trim().parse::<f64>().ok())
+            .unwrap_or(DEFAULT_BPM);
+
+        let mut voice = None;
+        let mut notes: Vec<ScoreNote> = Vec::new();
+        for note in elements(part, "note") {
+            if has_element(note, "chord") || has_element(note, "grace") {
+                continue;
+            }
+            let note_voice = element(note, "voice").map(str::trim);
+            if voice.is_none() {
+                voice = note_voice;
+            }
+            if note_voice.is_some() && note_voice != voice {
+                continue;
+            }
+
+            let duration = element(note, "duration")
+                .and_then(|x| x.trim().parse::<f64>().ok())
+                .ok_or_else(|| anyhow!("長さのない音符があります。"))?;
+            let beats = duration / divisions;
+
+            if has_element(note, "rest") {
+                notes.push(ScoreNote {
+                    key: None,
+                    beats,
+                    lyric: String::new(),
+                });
+                continue;
+            }
+
+            let key = musicxml_key(note)?;
+            let lyric = element(note, "text").map(|x| to_katakana(&unescape(x.trim())));
+            match (lyric, notes.last_mut()) {
+                (Some(lyric), _) if !lyric.is_empty() => notes.push(ScoreNote {
+                    key: Some(key),
+                    beats,
+                    lyric,
+                }),
+                (_, Some(prev)) if prev.key.is_some() => prev.beats += beats,
+                _ => bail!("歌詞のない音符があります。"),
+            }
+        }
+
+        let score = Self { bpm, notes };
+        score.validate()?;
+        Ok(score)
+    }
+
+    /// 演奏にかかる時間
+    /// 表せないほど長い場合は[`Duration::MAX`]とする
+    pub fn duration(&self) -> Duration {
+        let beats: f64 = self.notes.iter().map(|x| x.beats).sum();
+        Duration::try_from_secs_f64(beats * 60.0 / self.bpm).unwrap_or(Duration::MAX)
+    }
+
+    /// 各音符の長さをフレーム数で返す
+    /// 丸め誤差が積み重ならないよう、曲の先頭からの経過時間をもとに区切る
+    pub fn frame_lengths(&self) -> Result<Vec<u32>> {
+        let mut beats = 0.0;
+        let mut prev_frame = 0;
+        let mut lengths = Vec::with_capacity(self.notes.len());
+        for note in &self.notes {
+            beats += note.beats;
+            let frame = (beats * 60.0 / self.bpm * FRAME_RATE).round() as u32;
+            ensure!(
+                frame > prev_frame,
+                "短すぎる音符があります。テンポを下げるか音符を長くしてください。"
+            );
+            lengths.push(frame - prev_frame);
+            prev_frame = frame;
+        }
+        Ok(lengths)
+    }
+
+    fn validate(&self) -> Result<()> {
+        ensure!(
+            self.bpm.is_finite() && (20.0..=400.0).contains(&self.bpm),
+            "テンポは20〜400の範囲で指定してください。"
+        );
+        ensure!(
+            self.notes.iter().any(|x| x.key.is_some()),
+            "音符がありません。"
+        );
+        ensure!(
+            self.notes
+                .iter()
+                .all(|x| x.beats.is_finite() && x.beats > 0.0),
+            "長さが正しくない音符があります。"
+        );
+        Ok(())
+    }
+}
+
+fn parse_text_note(token: &str) -> Result<ScoreNote> {
+    let parts = token.split(':').collect::<Vec<_>>();
+    let (key, lyric, length) = match parts.as_slice() {
+        [rest, length] if rest.eq_ignore_ascii_case("r") || *rest == "休" => {
+            (None, String::new(), *length)
+        }
+        [pitch, lyric, length] if !lyric.is_empty() => {
+            (Some(parse_pitch(pitch)?), to_katakana(lyric), *length)
+        }
+        _ => bail!("`音名:歌詞:長さ`または`R:長さ`の形式で指定してください。"),
+    };
+
+    Ok(ScoreNote {
+        key,
+        beats: parse_length(length)?,
+        lyric,
+    })
+}
+
+/// `C4`や`F#5`をMIDIのノート番号にする
+fn parse_pitch(pitch: &str) -> Result<u8> {
+    let mut chars = pitch.chars();
+    let step = chars
+        .next()
+        .and_then(|x| step_semitone(&x.to_ascii_uppercase().to_string()))
+        .ok_or_else(|| anyhow!("音名はC〜Bで指定してください。"))?;
+    let rest = chars.as_str();
+    let (alter, octave) = if let Some(octave) = rest.strip_prefix('#') {
+        (1, octave)
+    } else if let Some(octave) = rest.strip_prefix('b') {
+        (-1, octave)
+    } else {
+        (0, rest)
+    };
+    let octave = octave
+        .parse::<i32>()
+        .map_err(|_| anyhow!("オクターブを数字で指定してください。"))?;
+
+    to_key(step, alter, octave)
+}
+
+/// `4`（四分音符）や`8.`（付点八分音符）を四分音符を1とした長さにする
+fn parse_length(length: &str) -> Result<f64> {
+    let (value, dotted) = match length.strip_suffix('.') {
+        Some(value) => (value, true),
+        None => (length, false),
+    };
+    let value = value
+        .parse::<u32>()
+        .ok()
+        .filter(|x| [1, 2, 4, 8, 16, 32].contains(x))
+        .ok_or_else(|| anyhow!("長さは1・2・4・8・16・32のいずれかで指定してください。"))?;
+
+    let beats = 4.0 / value as f64;
+    Ok(if dotted { beats * 1.5 } else { beats })
+}
+
+fn musicxml_key(note: &str) -> Result<u8> {
+    let pitch = element(note, "pitch").ok_or_else(|| anyhow!("音の高さのない音符があります。"))?;
+    let step = element(pitch, "step")
+        .and_then(|x| step_semitone(x.trim()))
+        .ok_or_else(|| anyhow!("音名の形式が正しくありません。"))?;
+    let alter = element(pitch, "alter")
+        .and_then(|x| x.trim().parse::<f64>().ok())
+        .unwrap_or(0.0)
+        .round() as i32;
+    let octave = element(pitch, "octave")
+        .and_then(|x| x.trim().parse::<i32>().ok())
+        .ok_or_else(|| anyhow!("オクターブの形式が正しくありません。"))?;
+
+    to_key(step, alter, octave)
+}
+
+fn step_semitone(step: &str) -> Option<i32> {
+    match step {
+        "C" => Some(0),
+        "D" => Some(2),
+        "E" => Some(4),
+        "F" => Some(5),
+        "G" => Some(7),
+        "A" => Some(9),
+        "B" => Some(11),
+        _ => None,
+    }
+}
+
+fn to_key(step: i32, alter: i32, octave: i32) -> Result<u8> {
+    let key = (octave + 1) * 12 + step + alter;
+    u8::try_from(key)
+        .ok()
+        .filter(|x| *x <= 127)
+        .ok_or_else(|| anyhow!("音が高すぎるか低すぎます。"))
+}
+
+/// 最初の`<tag>...</tag>`の中身を返す
+fn element<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    elements(xml, tag).next()
+}
+
+/// `<tag>...</tag>`の中身を順に返す
+/// 中身のない`<tag/>`は空文字列となる
+fn elements<'a>(xml: &'a str, tag: &str) -> impl Iterator<Item = &'a str> + 'a {
+    let tag = tag.to_string();
+    let close = format!("</{}>", tag);
+    let mut rest = xml;
+    std::iter::from_fn(move || loop {
+        let start = find_start_tag(rest, &tag)?;
+        let after = &rest[start..];
+        let end_of_open = after.find('>')?;
+        if after[..end_of_open].ends_with('/') {
+            rest = &after[end_of_open + 1..];
+            return Some("");
+        }
+
+        let body = &after[end_of_open + 1..];
+        let Some(end) = body.find(&close) else {
+            rest = body;
+            continue;
+        };
+        rest = &body[end + close.len()..];
+        return Some(&body[..end]);
+    })
+}
+
+fn has_element(xml: &str, tag: &str) -> bool {
+    find_start_tag(xml, tag).is_some()
+}
+
+/// `<tag>`・`<tag/>`・`<tag attr="...">`の開始位置を返す（`<tagname>`のような別の要素は除く）
+fn find_start_tag(xml: &str, tag: &str) -> Option<usize> {
+    let open = format!("<{}", tag);
+    let mut offset = 0;
+    while let Some(pos) = xml[offset..].find(&open) {
+        let start = offset + pos;
+        let next = xml[start + open.len()..].chars().next();
+        if matches!(next, Some('>' | '/' | ' ' | '\t' | '\r' | '\n')) {
+            return Some(start);
+        }
+        offset = start + open.len();
+    }
+    None
+}
+
+/// 最初の`<tag ... name="value">`の属性値を返す
+fn attribute<'a>(xml: &'a str, tag: &str, name: &str) -> Option<&'a str> {
+    let start = find_start_tag(xml, tag)?;
+    let open = &xml[start..start + xml[start..].find('>')?];
+    let pattern = format!(" {}=", name);
+    let value = &open[open.find(&pattern)? + pattern.len()..];
+    let quote = value.chars().next().filter(|x| *x == '"' || *x == '\'')?;
+    let value = &value[1..];
+    Some(&value[..value.find(quote)?])
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_text_score() {
+        let score = Score::parse_text("bpm=90 C4:ど:4 F#4:れ:8. R:8\nBb3:ミ:2").unwrap();
+
+        assert_eq!(score.bpm, 90.0);
+        assert_eq!(
+            score.notes,
+            vec![
+                ScoreNote {
+                    key: Some(60),
+                    beats: 1.0,
+                    lyric: "ド".to_string()
+                },
+                ScoreNote {
+                    key: Some(66),
+                    beats: 0.75,
+                    lyric: "レ".to_string()
+                },
+                ScoreNote {
+                    key: None,
+                    beats: 0.5,
+                    lyric: String::new()
+                },
+                ScoreNote {
+                    key: Some(58),
+                    beats: 2.0,
+                    lyric: "ミ".to_string()
+                },
+            ]
+        );
+        assert!(Score::parse_text("C4:ど").is_err());
+        assert!(Score::parse_text("H4:ど:4").is_err());
+        assert!(Score::parse_text("R:4").is_err());
+    }
+
+    #[test]
+    fn parses_musicxml_score() {
+        let xml = r#"<?xml version="1.0"?>
+<score-partwise>
+  <part-list><score-part id="P1"><part-name>Voice</part-name></score-part></part-list>
+  <part id="P1">
+    <measure number="1">
+      <attributes><divisions>2</divisions></attributes>
+      <direction><sound tempo="100"/></direction>
+      <note><pitch><step>A</step><octave>4</octave></pitch><duration>2</duration><voice>1</voice><lyric><text>ら</text></lyric></note>
+      <note><chord/><pitch><step>C</step><octave>5</octave></pitch><duration>2</duration><voice>1</voice></note>
+      <note><pitch><step>B</step><alter>-1</alter><octave>4</octave></pitch><duration>1</duration><voice>1</voice></note>
+      <note><rest/><duration>1</duration><voice>1</voice></note>
+      <note><pitch><step>G</step><octave>3</octave></pitch><duration>4</duration><voice>2</voice><lyric><text>あ</text></lyric></note>
+    </measure>
+  </part>
+</score-partwise>"#;
+        let score = Score::parse_musicxml(xml).unwrap();
+
+        assert_eq!(score.bpm, 100.0);
+        assert_eq!(
+            score.notes,
+            vec![
+                ScoreNote {
+                    key: Some(69),
+                    beats: 1.5,
+                    lyric: "ラ".to_string()
+                },
+                ScoreNote {
+                    key: None,
+                    beats: 0.5,
+                    lyric: String::new()
+                },
+            ]
+        );
+
+        // 負・無限大・非数の長さは受け付けない
+        for duration in ["-1", "inf", "NaN"] {
+            let xml = format!(
+                "<score-partwise><part><measure><note><pitch><step>C</step><octave>4</octave></pitch><duration>{}</duration><lyric><text>ら</text></lyric></note></measure></part></score-partwise>",
+                duration
+            );
+            assert!(
+                Score::parse_musicxml(&xml).is_err(),
+                "duration: {}",
+                duration
+            );
+        }
+    }
+
+    #[test]
+    fn frame_lengths_do_not_drift() {
+        let score = Score::parse_text("bpm=130 C4:ら:8 C4:ら:8 C4:ら:8 C4:ら:8").unwrap();
+        let lengths = score.frame_lengths().unwrap();
+
+        let total: u32 = lengths.iter().sum();
+        let expected = (score.duration().as_secs_f64() * FRAME_RATE).round() as u32;
+        assert_eq!(total, expected);
+        assert!(lengths.iter().all(|x| (21..=22).contains(x)));
+    }
+}
@@ -1,52 +1,94 @@
-use crate::voicevox::{GenerateQueryFromPresetParams, Preset, SynthesisParams, VoicevoxClient};
+use crate::cache::{AudioCache, CacheKey};
+use crate::engine::{Morph, SpeechEngine, SynthesisRequest, TextFormat, VoiceId, VoicePreset};
 use anyhow::{anyhow, Result};
-use bot_audio::EncodedAudio;
+use bot_audio::DecodedAudio;
+use std::sync::Arc;
 
-pub async fn initialize_speakers(client: &VoicevoxClient) -> Result<()> {
-    let preset_list = client.presets().await?;
-    for preset in preset_list {
-        client.initialize_speaker(preset.style_id).await?;
-    }
-    Ok(())
+pub async fn initialize_speakers(engine: &dyn SpeechEngine) -> Result<()> {
+    engine.initialize().await
 }
 
-pub async fn make_speech(client: &VoicevoxClient, option: SpeechRequest) -> Result<EncodedAudio> {
-    let preset = get_preset(client, option.preset_id).await?;
-
-    let query = client
-        .generate_query_from_preset(GenerateQueryFromPresetParams {
-            preset_id: preset.id,
+pub async fn make_speech(
+    engine: &dyn SpeechEngine,
+    cache: &AudioCache,
+    option: SpeechRequest,
+) -> Result<DecodedAudio> {
+    let preset = get_preset(engine, option.preset_id).await?;
+
+    make_speech_from_request(
+        engine,
+        cache,
+        SynthesisRequest {
             text: option.text,
-        })
-        .await?;
-    // override speed to 1.3
-    let mut v: serde_json::Value = serde_json::from_str(&query)?;
-    v["speedScale"] = serde_json::json!(1.3);
-    let query = serde_json::to_string(&v)?;
-
-    let audio = client
-        .synthesis(SynthesisParams {
-            style_id: preset.style_id,
-            query,
-        })
-        .await?;
-
-    Ok(audio)
+            voice: preset.voice,
+            params: option.params.or(preset.params),
+            morph: None,
+            format: option.format,
+        },
+    )
+    .await
+}
+
+/// キャッシュにあればそれを返し、なければ合成してデコードした音声をキャッシュに加える
+pub async fn make_speech_from_request(
+    engine: &dyn SpeechEngine,
+    cache: &AudioCache,
+    request: SynthesisRequest,
+) -> Result<DecodedAudio> {
+    let key = CacheKey::from_request(&request).map(|key| {
+        key.with_engine_state(&engine_version(engine, &request), cache.user_dict_hash())
+    });
+    if let Some(key) = &key {
+        if let Some(pcm) = cache.get(key).await {
+            return Ok(DecodedAudio::from((*pcm).clone()));
+        }
+    }
+
+    let pcm: Vec<u8> = engine.synthesize(request).await?.decode().await?.into();
+    if let Some(key) = key {
+        cache.insert(key, Arc::new(pcm.clone())).await;
+    }
+
+    Ok(DecodedAudio::from(pcm))
 }
 
-pub async fn list_preset_ids(client: &VoicevoxClient) -> Result<Vec<PresetId>> {
-    let preset_list = client.presets().await?;
+/// 合成に使うエンジン（モーフィングの場合は相手のエンジンも）のバージョン
+/// まだ確認できていないエンジンは空文字列とする
+fn engine_version(engine: &dyn SpeechEngine, request: &SynthesisRequest) -> String {
+    let status = engine.status();
+    let version = |namespace: &str| {
+        status
+            .iter()
+            .find(|x| x.namespace == namespace)
+            .and_then(|x| x.capabilities.as_ref())
+            .map_or("", |x| x.version.as_str())
+    };
+
+    let mut versions = version(&request.voice.namespace).to_string();
+    if let Some(morph) = &request.morph {
+        versions.push('>');
+        versions.push_str(version(&morph.target.namespace));
+    }
+    versions
+}
+
+pub async fn list_preset_ids(engine: &dyn SpeechEngine) -> Result<Vec<PresetId>> {
+    let preset_list = engine.presets().await?;
     let ids = preset_list.into_iter().map(|p| PresetId(p.id)).collect();
     Ok(ids)
 }
 
-async fn get_preset(client: &VoicevoxClient, id: PresetId) -> Result<Preset> {
-    let preset_list = client.presets().await?;
+async fn get_preset(engine: &dyn SpeechEngine, id: PresetId) -> Result<VoicePreset> {
+    let find = |presets: Vec<VoicePreset>| presets.into_iter().find(|p| PresetId(p.id) == id);
+
+    if let Some(preset) = find(engine.presets().await?) {
+        return Ok(preset);
+    }
 
-    let preset = preset_list
-        .into_iter()
-        .find(|p| PresetId(p.id) == id)
-        .ok_or_else(|| anyhow!("Preset {} is not available", id.0))?;
+    // キャッシュが古い可能性があるため、取得し直してから再度探す
+    engine.invalidate_catalog().await;
+    let preset =
+        find(engine.presets().await?).ok_or_else(|| anyhow!("Preset {} is not available", id.0))?;
 
     Ok(preset)
 }
@@ -55,6 +97,58 @@ async fn get_preset(client: &VoicevoxClient, id: PresetId) -> Result<Preset> {
 pub struct SpeechRequest {
     pub text: String,
     pub preset_id: PresetId,
+    pub params: VoiceParams,
+    pub format: TextFormat,
+}
+
+/// 話速の既定値
+pub const DEFAULT_SPEED_SCALE: f64 = 1.3;
+
+/// 音声クエリに上書きするパラメータ
+/// `None`の項目はクエリ（プリセット）の値をそのまま使う。ただし話速は未指定でも[`DEFAULT_SPEED_SCALE`]で上書きする
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VoiceParams {
+    pub speed_scale: Option<f64>,
+    pub pitch_scale: Option<f64>,
+    pub intonation_scale: Option<f64>,
+    pub volume_scale: Option<f64>,
+    pub pre_phoneme_length: Option<f64>,
+    pub post_phoneme_length: Option<f64>,
+}
+
+impl VoiceParams {
+    /// 未設定の項目を`fallback`の値で埋める
+    pub fn or(self, fallback: VoiceParams) -> VoiceParams {
+        VoiceParams {
+            speed_scale: self.speed_scale.or(fallback.speed_scale),
+            pitch_scale: self.pitch_scale.or(fallback.pitch_scale),
+            intonation_scale: self.intonation_scale.or(fallback.intonation_scale),
+            volume_scale: self.volume_scale.or(fallback.volume_scale),
+            pre_phoneme_length: self.pre_phoneme_length.or(fallback.pre_phoneme_length),
+            post_phoneme_length: self.post_phoneme_length.or(fallback.post_phoneme_length),
+        }
+    }
+
+    /// 音声クエリ（JSON）にパラメータを適用する
+    pub fn apply(&self, query: &str) -> Result<String> {
+        let mut v: serde_json::Value = serde_json::from_str(query)?;
+
+        v["speedScale"] = serde_json::json!(self.speed_scale.unwrap_or(DEFAULT_SPEED_SCALE));
+        let overrides = [
+            ("pitchScale", self.pitch_scale),
+            ("intonationScale", self.intonation_scale),
+            ("volumeScale", self.volume_scale),
+            ("prePhonemeLength", self.pre_phoneme_length),
+            ("postPhonemeLength", self.post_phoneme_length),
+        ];
+        for (key, value) in overrides {
+            if let Some(x) = value {
+                v[key] = serde_json::json!(x);
+            }
+        }
+
+        Ok(serde_json::to_string(&v)?)
+    }
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
@@ -84,29 +178,30 @@ impl From<&PresetId> for i64 {
     }
 }
 
-pub async fn list_style_ids(client: &VoicevoxClient) -> Result<Vec<i64>> {
-    let speakers = client.speakers().await?;
-    let mut ids = Vec::new();
-    for sp in speakers {
-        for st in sp.styles {
-            ids.push(st.id);
-        }
-    }
-    Ok(ids)
+pub async fn list_voice_ids(engine: &dyn SpeechEngine) -> Result<Vec<VoiceId>> {
+    let voices = engine.voices().await?;
+    Ok(voices.into_iter().map(|v| v.id).collect())
 }
 
-pub async fn make_speech_by_style(
-    client: &VoicevoxClient,
+/// `morph`を指定した場合は`voice`と混ぜ合わせた声で合成する
+pub async fn make_speech_by_voice(
+    engine: &dyn SpeechEngine,
+    cache: &AudioCache,
     text: String,
-    style_id: i64,
-) -> Result<EncodedAudio> {
-    let query = client.generate_query(text, style_id).await?;
-    // override speed to 1.3
-    let mut v: serde_json::Value = serde_json::from_str(&query)?;
-    v["speedScale"] = serde_json::json!(1.3);
-    let query = serde_json::to_string(&v)?;
-    let audio = client
-        .synthesis(SynthesisParams { style_id, query })
-        .await?;
-    Ok(audio)
+    voice: VoiceId,
+    morph: Option<Morph>,
+    params: &VoiceParams,
+) -> Result<DecodedAudio> {
+    make_speech_from_request(
+        engine,
+        cache,
+        SynthesisRequest {
+            text,
+            voice,
+            params: params.clone(),
+            morph,
+            format: TextFormat::Plain,
+        },
+    )
+    .await
 }
@@ -1,126 +1,1006 @@
-use anyhow::Result;
+use crate::endpoint::{Endpoint, EndpointOptions, EndpointPool, EndpointStatus, LoadBalancing};
+use crate::engine::{EngineCapabilities, UserDictSync, WordType};
 use bot_audio::EncodedAudio;
-use reqwest::Url;
+use log::{debug, info, warn};
+use reqwest::{RequestBuilder, Response, StatusCode, Url};
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
 
+/// `/presets`と`/speakers`の結果を保持する期間の既定値
+pub const DEFAULT_CATALOG_TTL: Duration = Duration::from_secs(300);
+
+/// マニフェストにサンプリングレートがないエンジンで使う、VOICEVOX ENGINEの既定値
+const DEFAULT_SAMPLING_RATE: u32 = 24000;
+
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+/// 接続の確立にかかる時間の上限
+/// 停止しているエンドポイントを早く見限るため、リクエスト全体のタイムアウトより短くする
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+pub type Result<T, E = VoicevoxError> = std::result::Result<T, E>;
+
+/// VOICEVOX ENGINEとの通信で起きるエラー
+#[derive(Debug, thiserror::Error)]
+pub enum VoicevoxError {
+    /// エンジンに接続できない（停止している・接続が切れた）
+    #[error("Failed to connect to VOICEVOX engine: {0}")]
+    Connect(reqwest::Error),
+    /// 時間内に応答がない
+    #[error("VOICEVOX engine timed out: {0}")]
+    Timeout(reqwest::Error),
+    /// エンジンがリクエストを受け付けなかった（読めない文章など）
+    #[error("VOICEVOX engine rejected the request ({status}): {detail}")]
+    Rejected { status: StatusCode, detail: String },
+    /// エンジン内部のエラー
+    #[error("VOICEVOX engine returned {status}: {body}")]
+    Server { status: StatusCode, body: String },
+    /// 存在しないスタイルIDを指定した
+    #[error("Unknown VOICEVOX style: {0}")]
+    UnknownSpeaker(i64),
+    /// エンジンの応答を解釈できない
+    #[error("Invalid response from VOICEVOX engine: {0}")]
+    InvalidResponse(reqwest::Error),
+    #[error("Invalid VOICEVOX request: {0}")]
+    InvalidRequest(String),
+    #[error("No VOICEVOX endpoint is configured")]
+    NoEndpoint,
+    /// エンジンが対応していない機能を使おうとした
+    #[error("VOICEVOX engine does not support {0}")]
+    Unsupported(&'static str),
+}
+
+impl VoicevoxError {
+    /// エンジン側の障害によるエラーか
+    /// `false`の場合はリクエストの内容（文章やスタイル）に問題がある
+    /// 500などのエラーは特定のリクエストで起きることがあるため、エンジンが応答できない状態を表す502・503・504だけを障害とみなす
+    pub fn is_unavailable(&self) -> bool {
+        match self {
+            VoicevoxError::Connect(_) | VoicevoxError::Timeout(_) | VoicevoxError::NoEndpoint => {
+                true
+            }
+            VoicevoxError::Server { status, .. } => matches!(
+                *status,
+                StatusCode::BAD_GATEWAY
+                    | StatusCode::SERVICE_UNAVAILABLE
+                    | StatusCode::GATEWAY_TIMEOUT
+            ),
+            _ => false,
+        }
+    }
+}
+
+/// 接続とタイムアウトのエラーだけをエンジンの障害とし、それ以外は再試行しないエラーにする
+impl From<reqwest::Error> for VoicevoxError {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_timeout() {
+            VoicevoxError::Timeout(err)
+        } else if err.is_connect() {
+            VoicevoxError::Connect(err)
+        } else if err.is_builder() {
+            VoicevoxError::InvalidRequest(err.to_string())
+        } else {
+            VoicevoxError::InvalidResponse(err)
+        }
+    }
+}
+
+impl From<url::ParseError> for VoicevoxError {
+    fn from(err: url::ParseError) -> Self {
+        VoicevoxError::InvalidRequest(err.to_string())
+    }
+}
+
+/// 冪等なリクエスト（`/presets`・`/speakers`・`/initialize_speaker`）の再試行の方針
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// 初回を除いた再試行の回数
+    pub max_retries: u32,
+    /// 1回目の再試行までの待ち時間。以降は2倍ずつ延ばす
+    pub initial_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(200),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct VoicevoxClient {
     client: reqwest::Client,
-    api_base: String,
+    pool: Arc<EndpointPool>,
+    endpoints: Vec<EndpointOptions>,
+    retry: RetryPolicy,
+    catalog: Arc<Catalog>,
+    capabilities: Arc<Mutex<Option<EngineCapabilities>>>,
 }
 
 impl VoicevoxClient {
-    pub fn new(api_base: String) -> Self {
+    /// `endpoints`に複数のエンドポイントを渡すと、負荷分散と障害時の切り替えを行う
+    pub fn new(endpoints: Vec<EndpointOptions>) -> Self {
         Self {
-            client: reqwest::Client::new(),
-            api_base,
+            client: reqwest::Client::builder()
+                .connect_timeout(CONNECT_TIMEOUT)
+                .build()
+                .expect("Failed to build HTTP client"),
+            pool: Arc::new(EndpointPool::new(
+                endpoints.clone(),
+                LoadBalancing::default(),
+            )),
+            endpoints,
+            retry: RetryPolicy::default(),
+            catalog: Arc::new(Catalog::new(DEFAULT_CATALOG_TTL)),
+            capabilities: Arc::new(Mutex::new(None)),
         }
     }
 
+    /// カタログ（プリセット・話者一覧）のキャッシュ期間を変更する
+    pub fn with_catalog_ttl(mut self, ttl: Duration) -> Self {
+        self.catalog = Arc::new(Catalog::new(ttl));
+        self
+    }
+
+    /// エンドポイントへの振り分け方を変更する
+    pub fn with_load_balancing(mut self, strategy: LoadBalancing) -> Self {
+        self.pool = Arc::new(EndpointPool::new(self.endpoints.clone(), strategy));
+        self
+    }
+
+    /// 冪等なリクエストの再試行の方針を変更する
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
     pub async fn generate_query_from_preset(
         &self,
         params: GenerateQueryFromPresetParams,
     ) -> Result<String> {
-        let url = Url::parse_with_params(
-            &self.get_endpoint("/audio_query_from_preset"),
-            &[
-                ("text", params.text),
-                ("preset_id", params.preset_id.to_string()),
-            ],
-        )?;
-
-        let resp = self
-            .client
-            .post(url)
-            .send()
-            .await?
-            .error_for_status()?
-            .text()
-            .await?;
+        if !self.supports(|x| x.presets) {
+            return Err(VoicevoxError::Unsupported("presets"));
+        }
 
-        Ok(resp)
+        let params = &params;
+        self.send(|api_base, timeout| async move {
+            let url = Url::parse_with_params(
+                &format!("{}/audio_query_from_preset", api_base),
+                &[
+                    ("text", params.text.clone()),
+                    ("preset_id", params.preset_id.to_string()),
+                ],
+            )?;
+
+            let resp = execute(self.client.post(url).timeout(timeout))
+                .await?
+                .text()
+                .await?;
+
+            Ok(resp)
+        })
+        .await
     }
 
     pub async fn synthesis(&self, params: SynthesisParams) -> Result<EncodedAudio> {
-        let url = Url::parse_with_params(
-            &self.get_endpoint("/synthesis"),
-            &[("speaker", params.style_id.to_string())],
-        )?;
-
-        let resp = self
-            .client
-            .post(url)
-            .header("content-type", "application/json")
-            .body(params.query)
-            .send()
-            .await?
-            .error_for_status()?
-            .bytes()
-            .await?;
+        let params = &params;
+        let result = self
+            .send(|api_base, timeout| async move {
+                let url = Url::parse_with_params(
+                    &format!("{}/synthesis", api_base),
+                    &[("speaker", params.style_id.to_string())],
+                )?;
 
-        Ok(EncodedAudio::from(resp.to_vec()))
+                let request = self
+                    .client
+                    .post(url)
+                    .timeout(timeout)
+                    .header("content-type", "application/json")
+                    .body(params.query.clone());
+                let resp = execute(request).await?.bytes().await?;
+
+                Ok(EncodedAudio::from(resp.to_vec()))
+            })
+            .await;
+
+        self.check_speaker(result, params.style_id).await
     }
 
-    pub async fn presets(&self) -> Result<Vec<Preset>> {
-        let url = Url::parse(&self.get_endpoint("/presets"))?;
+    /// 2つのスタイルを`morph_rate`の割合で混ぜ合わせた音声を合成する
+    /// 音声クエリは`base_style_id`で作ったものを使う
+    pub async fn synthesis_morphing(&self, params: MorphingParams) -> Result<EncodedAudio> {
+        if !self.supports(|x| x.morphing) {
+            return Err(VoicevoxError::Unsupported("synthesis morphing"));
+        }
 
-        let resp = self
-            .client
-            .get(url)
-            .send()
-            .await?
-            .error_for_status()?
-            .json()
-            .await?;
+        let params = &params;
+        let result = self
+            .send(|api_base, timeout| async move {
+                let url = Url::parse_with_params(
+                    &format!("{}/synthesis_morphing", api_base),
+                    &[
+                        ("base_speaker", params.base_style_id.to_string()),
+                        ("target_speaker", params.target_style_id.to_string()),
+                        ("morph_rate", params.morph_rate.to_string()),
+                    ],
+                )?;
 
-        Ok(resp)
+                let request = self
+                    .client
+                    .post(url)
+                    .timeout(timeout)
+                    .header("content-type", "application/json")
+                    .body(params.query.clone());
+                let resp = execute(request).await?.bytes().await?;
+
+                Ok(EncodedAudio::from(resp.to_vec()))
+            })
+            .await;
+
+        let result = self.check_speaker(result, params.base_style_id).await;
+        self.check_speaker(result, params.target_style_id).await
+    }
+
+    /// `base_style_id`とモーフィングできるスタイルIDの一覧を返す
+    /// モーフィングに対応していないエンジンでは空の[`Vec`]を返す
+    pub async fn morphable_targets(&self, base_style_id: i64) -> Result<Vec<i64>> {
+        if !self.supports(|x| x.morphing) {
+            return Ok(Vec::new());
+        }
+
+        let result = self
+            .send_with_retry(|api_base, timeout| async move {
+                let url = Url::parse(&format!("{}/morphable_targets", api_base))?;
+
+                let request = self
+                    .client
+                    .post(url)
+                    .timeout(timeout)
+                    .json(&[base_style_id]);
+                let resp: Vec<HashMap<String, MorphableTargetInfo>> =
+                    execute(request).await?.json().await?;
+
+                Ok(resp)
+            })
+            .await;
+        let targets = self.check_speaker(result, base_style_id).await?;
+
+        let mut ids = targets
+            .into_iter()
+            .flatten()
+            .filter(|(_, info)| info.is_morphable)
+            .filter_map(|(id, _)| id.parse::<i64>().ok())
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        Ok(ids)
+    }
+
+    /// プリセット一覧を返す
+    /// キャッシュが有効期間内であればエンジンへの問い合わせは行わない
+    /// プリセットに対応していないエンジンでは空の[`Vec`]を返す
+    pub async fn presets(&self) -> Result<Vec<Preset>> {
+        if !self.supports(|x| x.presets) {
+            return Ok(Vec::new());
+        }
+
+        self.catalog
+            .presets
+            .get_or_fetch(self.catalog.ttl, || self.fetch_presets())
+            .await
     }
 
+    /// 話者一覧を返す
+    /// キャッシュが有効期間内であればエンジンへの問い合わせは行わない
     pub async fn speakers(&self) -> Result<Vec<Speaker>> {
-        let url = Url::parse(&self.get_endpoint("/speakers"))?;
+        self.catalog
+            .speakers
+            .get_or_fetch(self.catalog.ttl, || self.fetch_speakers())
+            .await
+    }
 
-        let resp = self
-            .client
-            .get(url)
-            .send()
-            .await?
-            .error_for_status()?
-            .json()
+    /// 歌唱に使える話者の一覧を返す
+    /// キャッシュが有効期間内であればエンジンへの問い合わせは行わない
+    /// 歌唱に対応していないエンジンでは空の[`Vec`]を返す
+    pub async fn singers(&self) -> Result<Vec<Speaker>> {
+        if !self.supports(|x| x.sing) {
+            return Ok(Vec::new());
+        }
+
+        self.catalog
+            .singers
+            .get_or_fetch(self.catalog.ttl, || self.fetch_singers())
+            .await
+    }
+
+    /// 楽譜（`/sing_frame_audio_query`の`Score`）から歌唱用の音声クエリを作る
+    /// `teacher_style_id`には音程や音素の長さを決めるスタイル（種類が`sing`のもの）を指定する
+    pub async fn sing_frame_audio_query(
+        &self,
+        score: &serde_json::Value,
+        teacher_style_id: i64,
+    ) -> Result<String> {
+        if !self.supports(|x| x.sing) {
+            return Err(VoicevoxError::Unsupported("singing"));
+        }
+
+        let result = self
+            .send(|api_base, timeout| async move {
+                let url = Url::parse_with_params(
+                    &format!("{}/sing_frame_audio_query", api_base),
+                    &[("speaker", teacher_style_id.to_string())],
+                )?;
+
+                let request = self.client.post(url).timeout(timeout).json(score);
+                let resp = execute(request).await?.text().await?;
+
+                Ok(resp)
+            })
+            .await;
+
+        self.check_singer(result, teacher_style_id).await
+    }
+
+    /// 歌唱用の音声クエリから音声を合成する
+    pub async fn frame_synthesis(&self, params: SynthesisParams) -> Result<EncodedAudio> {
+        if !self.supports(|x| x.sing) {
+            return Err(VoicevoxError::Unsupported("singing"));
+        }
+
+        let params = &params;
+        let result = self
+            .send(|api_base, timeout| async move {
+                let url = Url::parse_with_params(
+                    &format!("{}/frame_synthesis", api_base),
+                    &[("speaker", params.style_id.to_string())],
+                )?;
+
+                let request = self
+                    .client
+                    .post(url)
+                    .timeout(timeout)
+                    .header("content-type", "application/json")
+                    .body(params.query.clone());
+                let resp = execute(request).await?.bytes().await?;
+
+                Ok(EncodedAudio::from(resp.to_vec()))
+            })
+            .await;
+
+        self.check_singer(result, params.style_id).await
+    }
+
+    /// キャッシュ済みのカタログを破棄する
+    /// エンジン側でプリセットや話者が変更されたときに呼び出す
+    pub async fn invalidate_catalog(&self) {
+        self.catalog.presets.clear().await;
+        self.catalog.speakers.clear().await;
+        self.catalog.singers.clear().await;
+        debug!("VOICEVOX catalog cache invalidated");
+    }
+
+    /// カタログをエンジンから取得し直してキャッシュを更新する
+    pub async fn refresh_catalog(&self) -> Result<()> {
+        if self.supports(|x| x.presets) {
+            let presets = self.fetch_presets().await?;
+            self.catalog.presets.store(presets).await;
+        }
+
+        let speakers = self.fetch_speakers().await?;
+        self.catalog.speakers.store(speakers).await;
+
+        if self.supports(|x| x.sing) {
+            let singers = self.fetch_singers().await?;
+            self.catalog.singers.store(singers).await;
+        }
+
+        debug!("VOICEVOX catalog cache refreshed");
+        Ok(())
+    }
+
+    /// `period`ごとにカタログを更新するタスクを起動する
+    pub fn spawn_catalog_refresh(&self, period: Duration) -> JoinHandle<()> {
+        let client = self.clone();
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(period);
+            loop {
+                ticker.tick().await;
+                if let Err(err) = client.refresh_catalog().await {
+                    warn!("Failed to refresh VOICEVOX catalog: {}", err);
+                }
+            }
+        })
+    }
+
+    /// `period`ごとに各エンドポイントの`/version`を確認するタスクを起動する
+    /// 応答しないエンドポイントは切り離され、応答が戻ると再び使われる
+    pub fn spawn_health_check(&self, period: Duration) -> JoinHandle<()> {
+        let client = self.clone();
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(period);
+            loop {
+                ticker.tick().await;
+                for endpoint in client.pool.endpoints() {
+                    let version = client.fetch_version(&endpoint.api_base).await;
+                    endpoint.record_health(version);
+                }
+
+                // 起動時にエンジンが停止していた場合は、応答が戻ったところで機能を判定する
+                if client.capabilities().is_none() {
+                    match client.detect_capabilities().await {
+                        Ok(capabilities) => {
+                            info!("VOICEVOX engine capabilities detected: {:?}", capabilities)
+                        }
+                        Err(err) => debug!("Failed to detect VOICEVOX capabilities: {}", err),
+                    }
+                }
+            }
+        })
+    }
+
+    /// 各エンドポイントの状態を返す
+    pub fn endpoint_status(&self) -> Vec<EndpointStatus> {
+        self.pool.status()
+    }
+
+    /// エンジンのバージョンと対応している機能を調べ、以降のリクエストで使う
+    /// すべてのエンドポイントで同じエンジンが動いているものとみなし、最初に応答したものの結果を使う
+    pub async fn detect_capabilities(&self) -> Result<EngineCapabilities> {
+        let capabilities = self
+            .send_with_retry(|api_base, timeout| async move {
+                self.fetch_capabilities(&api_base, timeout).await
+            })
             .await?;
 
-        Ok(resp)
+        *self.capabilities.lock().unwrap() = Some(capabilities.clone());
+        Ok(capabilities)
+    }
+
+    /// 判定済みの機能を返す
+    /// まだ判定できていない場合は`None`
+    pub fn capabilities(&self) -> Option<EngineCapabilities> {
+        self.capabilities.lock().unwrap().clone()
+    }
+
+    /// 機能に対応しているか
+    /// まだ判定できていない場合は対応しているものとして扱い、エンジンの応答に任せる
+    fn supports(&self, feature: impl FnOnce(&EngineCapabilities) -> bool) -> bool {
+        self.capabilities
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_none_or(feature)
     }
 
     pub async fn generate_query(&self, text: String, speaker: i64) -> Result<String> {
-        let url = Url::parse_with_params(
-            &self.get_endpoint("/audio_query"),
-            &[("text", text), ("speaker", speaker.to_string())],
-        )?;
-
-        let resp = self
-            .client
-            .post(url)
-            .send()
+        let text = &text;
+        let result = self
+            .send(|api_base, timeout| async move {
+                let url = Url::parse_with_params(
+                    &format!("{}/audio_query", api_base),
+                    &[("text", text.clone()), ("speaker", speaker.to_string())],
+                )?;
+
+                let resp = execute(self.client.post(url).timeout(timeout))
+                    .await?
+                    .text()
+                    .await?;
+
+                Ok(resp)
+            })
+            .await;
+
+        self.check_speaker(result, speaker).await
+    }
+
+    /// AquesTalk風記法のカナから音声クエリを作る
+    /// 記法の誤りはエンジンが[`VoicevoxError::Rejected`]として返す
+    pub async fn generate_query_from_kana(&self, kana: String, speaker: i64) -> Result<String> {
+        let kana = &kana;
+        let result = self
+            .send(|api_base, timeout| async move {
+                let url = Url::parse_with_params(
+                    &format!("{}/accent_phrases", api_base),
+                    &[
+                        ("text", kana.clone()),
+                        ("speaker", speaker.to_string()),
+                        ("is_kana", "true".to_string()),
+                    ],
+                )?;
+
+                let resp: serde_json::Value = execute(self.client.post(url).timeout(timeout))
+                    .await?
+                    .json()
+                    .await?;
+
+                Ok(resp)
+            })
+            .await;
+        let accent_phrases = self.check_speaker(result, speaker).await?;
+
+        // `/audio_query`の既定値に合わせる
+        // 出力のサンプリングレートは`/audio_query`と同じくエンジンの既定値を使う
+        let sampling_rate = self
+            .capabilities()
+            .and_then(|x| x.sampling_rate)
+            .unwrap_or(DEFAULT_SAMPLING_RATE);
+        let query = serde_json::json!({
+            "accent_phrases": accent_phrases,
+            "speedScale": 1.0,
+            "pitchScale": 0.0,
+            "intonationScale": 1.0,
+            "volumeScale": 1.0,
+            "prePhonemeLength": 0.1,
+            "postPhonemeLength": 0.1,
+            "outputSamplingRate": sampling_rate,
+            "outputStereo": false,
+            "kana": kana,
+        });
+        Ok(query.to_string())
+    }
+
+    pub async fn initialize_speaker(&self, speaker_id: i64) -> Result<()> {
+        let result = self
+            .send_with_retry(|api_base, timeout| async move {
+                let url = Url::parse_with_params(
+                    &format!("{}/initialize_speaker", api_base),
+                    &[("speaker", speaker_id.to_string())],
+                )?;
+
+                execute(self.client.post(url).timeout(timeout)).await?;
+
+                Ok(())
+            })
+            .await;
+
+        self.check_speaker(result, speaker_id).await
+    }
+
+    async fn fetch_presets(&self) -> Result<Vec<Preset>> {
+        self.send_with_retry(|api_base, timeout| async move {
+            let url = Url::parse(&format!("{}/presets", api_base))?;
+
+            let resp = execute(self.client.get(url).timeout(timeout))
+                .await?
+                .json()
+                .await?;
+
+            Ok(resp)
+        })
+        .await
+    }
+
+    async fn fetch_speakers(&self) -> Result<Vec<Speaker>> {
+        self.send_with_retry(|api_base, timeout| async move {
+            let url = Url::parse(&format!("{}/speakers", api_base))?;
+
+            let resp = execute(self.client.get(url).timeout(timeout))
+                .await?
+                .json()
+                .await?;
+
+            Ok(resp)
+        })
+        .await
+    }
+
+    async fn fetch_singers(&self) -> Result<Vec<Speaker>> {
+        self.send_with_retry(|api_base, timeout| async move {
+            let url = Url::parse(&format!("{}/singers", api_base))?;
+
+            let resp = execute(self.client.get(url).timeout(timeout))
+                .await?
+                .json()
+                .await?;
+
+            Ok(resp)
+        })
+        .await
+    }
+
+    async fn fetch_capabilities(
+        &self,
+        api_base: &str,
+        timeout: Duration,
+    ) -> Result<EngineCapabilities> {
+        let version = self.fetch_version(api_base).await?;
+        let manifest: Option<EngineManifest> = self
+            .fetch_optional(api_base, "engine_manifest", timeout)
+            .await?;
+        let devices: Option<HashMap<String, bool>> = self
+            .fetch_optional(api_base, "supported_devices", timeout)
+            .await?;
+        // マニフェストに項目のない機能は、APIが存在するかどうかで判定する
+        let presets = self
+            .fetch_optional::<serde_json::Value>(api_base, "presets", timeout)
+            .await?
+            .is_some();
+        let user_dict = self
+            .fetch_optional::<serde_json::Value>(api_base, "user_dict", timeout)
+            .await?
+            .is_some();
+
+        let feature = |name: &str| {
+            manifest
+                .as_ref()
+                .and_then(|x| x.supported_features.get(name))
+                .and_then(|x| x.as_bool())
+                .unwrap_or(false)
+        };
+        let mut devices = devices
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(_, available)| *available)
+            .map(|(device, _)| device)
+            .collect::<Vec<_>>();
+        devices.sort();
+
+        Ok(EngineCapabilities {
+            version,
+            engine_name: manifest.as_ref().and_then(|x| x.name.clone()),
+            devices,
+            presets,
+            user_dict,
+            morphing: feature("synthesis_morphing"),
+            sing: feature("sing"),
+            sampling_rate: manifest.as_ref().and_then(|x| x.default_sampling_rate),
+        })
+    }
+
+    /// 古いエンジンには存在しないことがあるAPIを呼び出す
+    /// 存在しない場合は`None`を返す
+    async fn fetch_optional<T: DeserializeOwned>(
+        &self,
+        api_base: &str,
+        path: &str,
+        timeout: Duration,
+    ) -> Result<Option<T>> {
+        let url = Url::parse(&format!("{}/{}", api_base, path))?;
+
+        match execute(self.client.get(url).timeout(timeout)).await {
+            Ok(resp) => Ok(Some(resp.json().await?)),
+            Err(VoicevoxError::Rejected { status, .. })
+                if status == StatusCode::NOT_FOUND || status == StatusCode::METHOD_NOT_ALLOWED =>
+            {
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    async fn fetch_version(&self, api_base: &str) -> Result<String> {
+        let url = Url::parse(&format!("{}/version", api_base))?;
+
+        let resp = execute(self.client.get(url).timeout(HEALTH_CHECK_TIMEOUT))
             .await?
-            .error_for_status()?
-            .text()
+            .json::<String>()
             .await?;
 
         Ok(resp)
     }
 
-    pub async fn initialize_speaker(&self, speaker_id: i64) -> Result<()> {
-        let url = Url::parse_with_params(
-            &self.get_endpoint("/initialize_speaker"),
-            &[("speaker", speaker_id.to_string())],
-        )?;
+    /// すべてのエンドポイントのユーザー辞書を`dict`の内容に合わせる
+    /// ユーザー辞書はエンドポイントごとに独立しているため、負荷分散せずに1台ずつ更新する
+    /// ユーザー辞書に対応していないエンジンでは何もしない
+    pub async fn sync_user_dict(&self, dict: &UserDictSync) -> Result<()> {
+        if !self.supports(|x| x.user_dict) {
+            debug!("VOICEVOX engine does not support user dictionary, skipping sync");
+            return Ok(());
+        }
+
+        let mut result = Ok(());
+        for endpoint in self.pool.endpoints() {
+            if let Err(err) = self.sync_user_dict_on(endpoint, dict).await {
+                warn!(
+                    "Failed to sync user dictionary of VOICEVOX endpoint {}: {}",
+                    endpoint.api_base, err
+                );
+                result = Err(err);
+            }
+        }
+        result
+    }
+
+    async fn sync_user_dict_on(&self, endpoint: &Endpoint, dict: &UserDictSync) -> Result<()> {
+        let api_base = &endpoint.api_base;
+
+        // エンジンは表記を全角に変換して保存するため、比較する前にそろえる
+        let managed = dict
+            .managed_surfaces
+            .iter()
+            .map(|x| to_zenkaku(x))
+            .collect::<HashSet<_>>();
+        let desired = dict
+            .words
+            .iter()
+            .map(|word| (to_zenkaku(&word.surface), word))
+            .collect::<HashMap<_, _>>();
+
+        let url = Url::parse(&format!("{}/user_dict", api_base))?;
+        let current: HashMap<String, EngineUserDictWord> =
+            execute(self.client.get(url).timeout(endpoint.timeout))
+                .await?
+                .json()
+                .await?;
 
-        self.client.post(url).send().await?.error_for_status()?;
+        let mut up_to_date = HashSet::new();
+        for (uuid, word) in current {
+            if !managed.contains(&word.surface) && !desired.contains_key(&word.surface) {
+                continue;
+            }
+
+            // 優先度は送っておらずエンジンの既定値のままなので比較しない
+            let keep = desired.get(&word.surface).is_some_and(|x| {
+                x.pronunciation == word.pronunciation
+                    && x.accent_type == word.accent_type
+                    && word.has_word_type(x.word_type)
+            });
+            if keep && up_to_date.insert(word.surface.clone()) {
+                continue;
+            }
+
+            let url = Url::parse(&format!("{}/user_dict_word/{}", api_base, uuid))?;
+            execute(self.client.delete(url).timeout(endpoint.timeout)).await?;
+            debug!(
+                "Removed {} from user dictionary of {}",
+                word.surface, api_base
+            );
+        }
+
+        for (surface, word) in desired {
+            if up_to_date.contains(&surface) {
+                continue;
+            }
+
+            let url = Url::parse_with_params(
+                &format!("{}/user_dict_word", api_base),
+                &[
+                    ("surface", word.surface.clone()),
+                    ("pronunciation", word.pronunciation.clone()),
+                    ("accent_type", word.accent_type.to_string()),
+                    ("word_type", word.word_type.api_name().to_string()),
+                ],
+            )?;
+            execute(self.client.post(url).timeout(endpoint.timeout)).await?;
+            debug!("Added {} to user dictionary of {}", word.surface, api_base);
+        }
 
         Ok(())
     }
 
-    fn get_endpoint(&self, path: impl AsRef<str>) -> String {
-        self.api_base.clone() + path.as_ref()
+    /// エンジンに拒否されたリクエストが、存在しないスタイルIDによるものかを確かめる
+    async fn check_speaker<T>(&self, result: Result<T>, style_id: i64) -> Result<T> {
+        if !matches!(result, Err(VoicevoxError::Rejected { .. })) {
+            return result;
+        }
+        check_known_style(result, style_id, self.speakers().await)
+    }
+
+    /// [`Self::check_speaker`]の歌唱用。`/singers`のスタイルと照らし合わせる
+    async fn check_singer<T>(&self, result: Result<T>, style_id: i64) -> Result<T> {
+        if !matches!(result, Err(VoicevoxError::Rejected { .. })) {
+            return result;
+        }
+        check_known_style(result, style_id, self.singers().await)
+    }
+
+    /// [`Self::send`]がすべてのエンドポイントで失敗した場合に、間隔を空けて再試行する
+    /// 同じリクエストを何度送っても結果が変わらないものにだけ使う
+    async fn send_with_retry<T, F, Fut>(&self, request: F) -> Result<T>
+    where
+        F: Fn(String, Duration) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut backoff = self.retry.initial_backoff;
+        let mut retries = 0;
+
+        loop {
+            match self.send(&request).await {
+                Err(err) if err.is_unavailable() && retries < self.retry.max_retries => {
+                    debug!(
+                        "VOICEVOX request failed, retrying in {:?}: {}",
+                        backoff, err
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// 利用可能なエンドポイントに順にリクエストを送る
+    /// 接続できない・応答がない・502・503・504を返したエンドポイントは飛ばして次を試す
+    async fn send<T, F, Fut>(&self, request: F) -> Result<T>
+    where
+        F: Fn(String, Duration) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut last_err = None;
+
+        for endpoint in self.pool.candidates() {
+            let _guard = endpoint.begin();
+            match request(endpoint.api_base.clone(), endpoint.timeout).await {
+                Ok(resp) => {
+                    endpoint.record_success();
+                    return Ok(resp);
+                }
+                Err(err) if err.is_unavailable() => {
+                    warn!("VOICEVOX endpoint {} failed: {}", endpoint.api_base, err);
+                    endpoint.record_failure(&err);
+                    last_err = Some(err);
+                }
+                Err(err) => {
+                    // エンジンは応答しているので、エンドポイントの障害とはみなさない
+                    endpoint.record_success();
+                    return Err(err);
+                }
+            }
+        }
+
+        Err(last_err.unwrap_or(VoicevoxError::NoEndpoint))
+    }
+}
+
+/// 拒否されたリクエストのスタイルIDが`speakers`に含まれなければ[`VoicevoxError::UnknownSpeaker`]にする
+fn check_known_style<T>(
+    result: Result<T>,
+    style_id: i64,
+    speakers: Result<Vec<Speaker>>,
+) -> Result<T> {
+    let known = match speakers {
+        Ok(speakers) => speakers
+            .iter()
+            .any(|sp| sp.styles.iter().any(|st| st.id == style_id)),
+        // 確かめられない場合は元のエラーを返す
+        Err(_) => true,
+    };
+    match result {
+        Err(VoicevoxError::Rejected { .. }) if !known => {
+            Err(VoicevoxError::UnknownSpeaker(style_id))
+        }
+        result => result,
+    }
+}
+
+/// リクエストを送り、エラーを表すステータスコードを[`VoicevoxError`]に変換する
+async fn execute(request: RequestBuilder) -> Result<Response> {
+    let resp = request.send().await?;
+    let status = resp.status();
+    if status.is_success() {
+        return Ok(resp);
+    }
+
+    let body = resp.text().await.unwrap_or_default();
+    if status.is_server_error() {
+        Err(VoicevoxError::Server { status, body })
+    } else {
+        Err(VoicevoxError::Rejected {
+            status,
+            detail: validation_detail(&body),
+        })
+    }
+}
+
+/// 半角の英数字・記号を全角にする（VOICEVOX ENGINEのユーザー辞書と同じ変換）
+fn to_zenkaku(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '!'..='~' => char::from_u32(c as u32 + 0xFEE0).unwrap_or(c),
+            ' ' => '\u{3000}',
+            c => c,
+        })
+        .collect()
+}
+
+/// エラー応答の`detail`を読みやすい形にする
+/// 検証エラー（422）の場合は`[{"loc": [...], "msg": "..."}]`の形式で返ってくる
+fn validation_detail(body: &str) -> String {
+    #[derive(Deserialize)]
+    struct ErrorBody {
+        detail: serde_json::Value,
+    }
+
+    let Ok(ErrorBody { detail }) = serde_json::from_str::<ErrorBody>(body) else {
+        return body.to_string();
+    };
+    match detail {
+        serde_json::Value::String(msg) => msg,
+        // カナの解析エラーなど`{"text": ..., "error_name": ...}`の形式
+        serde_json::Value::Object(map) if map.get("text").is_some_and(|x| x.is_string()) => {
+            map["text"].as_str().unwrap_or_default().to_string()
+        }
+        serde_json::Value::Array(items) => items
+            .iter()
+            .map(|item| {
+                let loc = item["loc"]
+                    .as_array()
+                    .map(|loc| {
+                        loc.iter()
+                            .map(|x| x.to_string().trim_matches('"').to_string())
+                            .collect::<Vec<_>>()
+                            .join(".")
+                    })
+                    .unwrap_or_default();
+                let msg = item["msg"].as_str().unwrap_or_default();
+                format!("{}: {}", loc, msg)
+            })
+            .collect::<Vec<_>>()
+            .join(", "),
+        other => other.to_string(),
+    }
+}
+
+struct Catalog {
+    ttl: Duration,
+    presets: CatalogEntry<Vec<Preset>>,
+    speakers: CatalogEntry<Vec<Speaker>>,
+    singers: CatalogEntry<Vec<Speaker>>,
+}
+
+impl Catalog {
+    fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            presets: CatalogEntry::default(),
+            speakers: CatalogEntry::default(),
+            singers: CatalogEntry::default(),
+        }
+    }
+}
+
+struct CatalogEntry<T> {
+    slot: RwLock<Option<(T, Instant)>>,
+}
+
+impl<T> Default for CatalogEntry<T> {
+    fn default() -> Self {
+        Self {
+            slot: RwLock::new(None),
+        }
+    }
+}
+
+impl<T: Clone> CatalogEntry<T> {
+    async fn get_or_fetch<F, Fut>(&self, ttl: Duration, fetch: F) -> Result<T>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        if let Some((value, fetched_at)) = &*self.slot.read().await {
+            if fetched_at.elapsed() < ttl {
+                return Ok(value.clone());
+            }
+        }
+
+        // 同時に期限切れを検出したリクエストがエンジンへ殺到しないよう、取得中は書き込みロックを保持する
+        let mut slot = self.slot.write().await;
+        if let Some((value, fetched_at)) = &*slot {
+            if fetched_at.elapsed() < ttl {
+                return Ok(value.clone());
+            }
+        }
+
+        let value = fetch().await?;
+        *slot = Some((value.clone(), Instant::now()));
+        Ok(value)
+    }
+
+    async fn store(&self, value: T) {
+        *self.slot.write().await = Some((value, Instant::now()));
+    }
+
+    async fn clear(&self) {
+        *self.slot.write().await = None;
     }
 }
 
@@ -136,6 +1016,30 @@ pub struct SynthesisParams {
     pub query: String,
 }
 
+#[derive(Debug, Clone)]
+pub struct MorphingParams {
+    pub base_style_id: i64,
+    pub target_style_id: i64,
+    /// 0.0で`base_style_id`、1.0で`target_style_id`の声となる
+    pub morph_rate: f64,
+    pub query: String,
+}
+
+/// `/engine_manifest`の応答のうち、機能の判定に使う項目
+/// 互換エンジンでは項目が欠けていることがあるため、すべて省略可能とする
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct EngineManifest {
+    name: Option<String>,
+    supported_features: HashMap<String, serde_json::Value>,
+    default_sampling_rate: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MorphableTargetInfo {
+    is_morphable: bool,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct Preset {
     pub id: i64,
@@ -156,6 +1060,41 @@ pub struct Preset {
     pub post_phoneme_length: f64,
 }
 
+/// `/user_dict`の応答のうち、同期に使う項目
+#[derive(Debug, Clone, Deserialize)]
+struct EngineUserDictWord {
+    surface: String,
+    pronunciation: String,
+    accent_type: u32,
+    /// 品詞。返さないエンジンでは比較しない
+    part_of_speech: Option<String>,
+    part_of_speech_detail_1: Option<String>,
+}
+
+impl EngineUserDictWord {
+    fn has_word_type(&self, word_type: WordType) -> bool {
+        let (part_of_speech, detail) = part_of_speech(word_type);
+        self.part_of_speech
+            .as_deref()
+            .is_none_or(|x| x == part_of_speech)
+            && self
+                .part_of_speech_detail_1
+                .as_deref()
+                .is_none_or(|x| x == detail)
+    }
+}
+
+/// エンジンが`word_type`から決める品詞と品詞細分類1
+fn part_of_speech(word_type: WordType) -> (&'static str, &'static str) {
+    match word_type {
+        WordType::ProperNoun => ("名詞", "固有名詞"),
+        WordType::CommonNoun => ("名詞", "一般"),
+        WordType::Verb => ("動詞", "自立"),
+        WordType::Adjective => ("形容詞", "自立"),
+        WordType::Suffix => ("名詞", "接尾"),
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct Speaker {
     pub name: String,
@@ -167,4 +1106,7 @@ pub struct Speaker {
 pub struct SpeakerStyle {
     pub id: i64,
     pub name: String,
+    /// `talk`・`sing`・`frame_decode`など。古いエンジンでは省略される
+    #[serde(default, rename = "type")]
+    pub kind: Option<String>,
 }
@@ -1,25 +1,38 @@
 use crate::time_signal::TimeSignalConfig;
 use anyhow::{anyhow, Result};
 use bot_db::redis;
-use bot_speech::voicevox::VoicevoxClient;
+use bot_speech::cache::AudioCache;
+use bot_speech::engine::SpeechEngine;
 use dashmap::DashMap;
 use serenity::{
     client::{Client, Context},
     model::{
         channel::Message,
+        gateway::GatewayIntents,
         id::{ChannelId, GuildId},
     },
     prelude::TypeMapKey,
 };
+use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::RwLock;
 
 pub struct AppState {
     pub redis_client: redis::Client,
-    pub voicevox_client: VoicevoxClient,
+    pub speech_engine: Arc<dyn SpeechEngine>,
+    pub audio_cache: Arc<AudioCache>,
+    /// エンジンのユーザー辞書への同期が有効か
+    pub user_dict_sync: bool,
+    /// アクセント付きの辞書登録を許可するサーバー
+    pub user_dict_guilds: HashSet<GuildId>,
+    /// 接続時に指定したゲートウェイのインテント
+    pub intents: GatewayIntents,
     pub connected_guild_states: DashMap<GuildId, ConnectedGuildState>,
     pub preferred_style_id: RwLock<Option<i64>>,
     pub time_signal_settings: DashMap<GuildId, TimeSignalConfig>,
+    /// 音声合成の失敗を最後にサーバーへ知らせた時刻
+    pub speech_error_notified_at: DashMap<GuildId, Instant>,
 }
 
 pub struct ConnectedGuildState {
@@ -1,13 +1,25 @@
-﻿use crate::app_state::{AppState, ConnectedGuildState};
-use crate::{autojoin, time_signal};
+use crate::app_state::{AppState, ConnectedGuildState};
+use crate::message::read_setting::{self, ReadOption};
+use crate::message::text_rule::{self, Rule, RuleSet};
+use crate::voice::{self, VoiceParam};
+use crate::{autojoin, engine_dict, time_signal};
 use anyhow::Context as _;
-use anyhow::Result;
+use anyhow::{anyhow, bail, ensure, Result};
+use bot_db::engine_dict::{Accent, ClaimOption, ClaimResponse, ReleaseOption, ReleaseResponse};
+use bot_db::read_settings::{self, ReadSettings};
+use bot_db::user_name::{self, UserNameSettings};
+use bot_db::voice_profile::{self, Scope, VoiceProfile};
 use bot_db::{dict, redis};
-use serenity::builder::CreateEmbed;
+use bot_speech::engine::{EngineCapabilities, SingRequest, VoiceId, WordType};
+use bot_speech::sing::Score;
+use bot_speech::voicevox::VoicevoxError;
+use serenity::builder::{CreateEmbed, CreateInteractionResponseFollowup};
 use serenity::client::Context as SerenityContext;
+use serenity::model::channel::{Attachment, AttachmentType};
 use serenity::model::id::{ChannelId, GuildId, UserId};
 use std::collections::BTreeMap;
 use std::path::Path;
+use std::time::Duration;
 use tokio::fs;
 
 const DICT_JSON_PATH: &str = "deployment/dict.json";
@@ -80,12 +92,68 @@ pub fn clear_time_signal_audio(state: &AppState, guild_id: GuildId) {
     time_signal::clear_audio(state, guild_id);
 }
 
+/// エンジンのユーザー辞書に登録するときの発音の情報
+#[derive(Debug, Clone, Copy)]
+pub struct EngineWordOption {
+    pub accent_type: u32,
+    pub word_type: WordType,
+}
+
+#[derive(Debug, Clone)]
+pub enum DictAddOutcome {
+    Added,
+    /// エンジンのユーザー辞書にも登録した
+    AddedToEngine,
+    /// 他のサーバーが同じ表記をエンジンに登録しているため、通常の読み替えとして登録した
+    EngineOwnedByOtherGuild,
+    WordAlreadyExists,
+}
+
+/// 辞書の項目に指定できる優先度の絶対値の上限
+pub const MAX_DICT_PRIORITY: i64 = 1000;
+
+/// 辞書に登録する項目の種類と優先度
+#[derive(Debug, Clone, Copy)]
+pub struct DictEntryOption {
+    pub kind: dict::EntryKind,
+    pub priority: i64,
+}
+
+impl Default for DictEntryOption {
+    fn default() -> Self {
+        Self {
+            kind: dict::EntryKind::Literal,
+            priority: 0,
+        }
+    }
+}
+
 pub async fn dict_add(
     state: &AppState,
     guild_id: GuildId,
     word: &str,
     read_as: &str,
-) -> Result<dict::InsertResponse> {
+    entry: DictEntryOption,
+    engine_word: Option<EngineWordOption>,
+) -> Result<DictAddOutcome> {
+    validate_dict_priority(entry.priority)?;
+    if entry.kind == dict::EntryKind::Regex {
+        if engine_word.is_some() {
+            bail!("正規表現の項目にはアクセントを指定できません。");
+        }
+        validate_dict_pattern(word, read_as)?;
+    }
+    if let Some(option) = engine_word {
+        if !state.user_dict_sync {
+            bail!("このBotではアクセントの登録は無効になっています。");
+        }
+        if !state.user_dict_guilds.contains(&guild_id) {
+            bail!("このサーバーではアクセントの登録は許可されていません。");
+        }
+        let pronunciation = engine_dict::to_pronunciation(read_as)?;
+        engine_dict::validate_accent(&pronunciation, option.accent_type)?;
+    }
+
     let mut conn = get_redis_connection(state).await?;
     let result = dict::insert(
         &mut conn,
@@ -93,16 +161,134 @@ pub async fn dict_add(
             guild_id: guild_id.into(),
             word: word.to_string(),
             read_as: read_as.to_string(),
+            kind: entry.kind,
+            priority: entry.priority,
         },
     )
     .await
     .context("Failed to insert dictionary entry")?;
 
-    if let dict::InsertResponse::Success = result {
-        sync_dict_json(state, guild_id).await?;
+    if let dict::InsertResponse::WordAlreadyExists = result {
+        return Ok(DictAddOutcome::WordAlreadyExists);
     }
+    sync_dict_json(state, guild_id).await?;
 
-    Ok(result)
+    let Some(option) = engine_word else {
+        return Ok(DictAddOutcome::Added);
+    };
+    let claim = bot_db::engine_dict::claim(
+        &mut conn,
+        ClaimOption {
+            guild_id: guild_id.into(),
+            word: word.to_string(),
+            accent: Accent {
+                accent_type: option.accent_type,
+                word_type: option.word_type.api_name().to_string(),
+            },
+        },
+    )
+    .await
+    .context("Failed to register engine dictionary entry")?;
+
+    Ok(match claim {
+        ClaimResponse::Success => {
+            engine_dict::spawn_sync(
+                state.redis_client.clone(),
+                state.speech_engine.clone(),
+                state.audio_cache.clone(),
+            );
+            DictAddOutcome::AddedToEngine
+        }
+        ClaimResponse::OwnedByOtherGuild => DictAddOutcome::EngineOwnedByOtherGuild,
+    })
+}
+
+/// 辞書への登録結果を利用者向けの文章にする
+pub fn describe_dict_add(outcome: &DictAddOutcome, word: &str, read_as: &str) -> String {
+    match outcome {
+        DictAddOutcome::Added => format!("辞書に登録しました: {} → {}", word, read_as),
+        DictAddOutcome::AddedToEngine => format!(
+            "辞書に登録しました: {} → {}（アクセント付きで音声合成エンジンに登録します）",
+            word, read_as
+        ),
+        DictAddOutcome::EngineOwnedByOtherGuild => format!(
+            "辞書に登録しました: {} → {}\n他のサーバーが同じ単語を音声合成エンジンに登録しているため、アクセントは反映されず通常の読み替えとして扱います。",
+            word, read_as
+        ),
+        DictAddOutcome::WordAlreadyExists => {
+            "すでに登録済みです。上書きする場合はいったん削除してください。".to_string()
+        }
+    }
+}
+
+/// 正規表現と置き換え後の文字列を検証する
+/// 置き換え後の文字列で参照しているキャプチャ（`$1`・`${name}`）が正規表現にあるかも確かめる
+fn validate_dict_pattern(pattern: &str, replacement: &str) -> Result<()> {
+    let regex = crate::regex::dict_pattern(pattern)
+        .map_err(|err| anyhow!("正規表現の形式が正しくありません: {}", err))?;
+
+    let mut rest = replacement;
+    while let Some(pos) = rest.find('$') {
+        rest = &rest[pos + 1..];
+        let (name, next) = if let Some(braced) = rest.strip_prefix('{') {
+            let end = braced
+                .find('}')
+                .ok_or_else(|| anyhow!("`${{`に対応する`}}`がありません。"))?;
+            (&braced[..end], &braced[end + 1..])
+        } else if let Some(escaped) = rest.strip_prefix('$') {
+            ("", escaped)
+        } else {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            (&rest[..end], &rest[end..])
+        };
+        rest = next;
+        if name.is_empty() {
+            continue;
+        }
+
+        let exists = match name.parse::<usize>() {
+            Ok(index) => index < regex.captures_len(),
+            Err(_) => regex.capture_names().flatten().any(|x| x == name),
+        };
+        if !exists {
+            bail!("正規表現にキャプチャ`{}`がありません。", name);
+        }
+    }
+    Ok(())
+}
+
+fn validate_dict_priority(priority: i64) -> Result<()> {
+    ensure!(
+        (-MAX_DICT_PRIORITY..=MAX_DICT_PRIORITY).contains(&priority),
+        "優先度は{}〜{}で指定してください。",
+        -MAX_DICT_PRIORITY,
+        MAX_DICT_PRIORITY
+    );
+    Ok(())
+}
+
+/// 登録済みの項目の優先度を変更する
+pub async fn dict_set_priority(
+    state: &AppState,
+    guild_id: GuildId,
+    word: &str,
+    priority: i64,
+) -> Result<dict::SetPriorityResponse> {
+    validate_dict_priority(priority)?;
+
+    let mut conn = get_redis_connection(state).await?;
+    dict::set_priority(
+        &mut conn,
+        dict::SetPriorityOption {
+            guild_id: guild_id.into(),
+            word: word.to_string(),
+            priority,
+        },
+    )
+    .await
+    .context("Failed to set dictionary priority")
 }
 
 pub async fn dict_remove(
@@ -123,14 +309,33 @@ pub async fn dict_remove(
 
     if let dict::RemoveResponse::Success = result {
         sync_dict_json(state, guild_id).await?;
+
+        let released = bot_db::engine_dict::release(
+            &mut conn,
+            ReleaseOption {
+                guild_id: guild_id.into(),
+                word: word.to_string(),
+            },
+        )
+        .await
+        .context("Failed to release engine dictionary entry")?;
+        if let ReleaseResponse::Success = released {
+            engine_dict::spawn_sync(
+                state.redis_client.clone(),
+                state.speech_engine.clone(),
+                state.audio_cache.clone(),
+            );
+        }
     }
 
     Ok(result)
 }
 
+/// 辞書の一覧をJSONで返す
+/// 優先度が既定の語句は`"語句": "読み"`、それ以外は`"語句": {"read_as": ..., "regex": ..., "priority": ...}`と表す
 pub async fn dict_list(state: &AppState, guild_id: GuildId) -> Result<String> {
     let mut conn = get_redis_connection(state).await?;
-    let entries = dict::get_all(
+    let entries = dict::get_entries(
         &mut conn,
         dict::GetAllOption {
             guild_id: guild_id.into(),
@@ -139,15 +344,35 @@ pub async fn dict_list(state: &AppState, guild_id: GuildId) -> Result<String> {
     .await
     .context("Failed to list dictionary entries")?;
 
-    let map: BTreeMap<String, String> = entries.into_iter().collect();
-    write_dict_json(&map).await?;
+    let literals: BTreeMap<String, String> = entries
+        .iter()
+        .filter(|entry| entry.kind == dict::EntryKind::Literal)
+        .map(|entry| (entry.word.clone(), entry.read_as.clone()))
+        .collect();
+    write_dict_json(&literals).await?;
+
+    let map: BTreeMap<String, serde_json::Value> = entries
+        .into_iter()
+        .map(|entry| {
+            let value = if entry.kind == dict::EntryKind::Literal && entry.priority == 0 {
+                serde_json::Value::String(entry.read_as)
+            } else {
+                serde_json::json!({
+                    "read_as": entry.read_as,
+                    "regex": entry.kind == dict::EntryKind::Regex,
+                    "priority": entry.priority,
+                })
+            };
+            (entry.word, value)
+        })
+        .collect();
 
     Ok(serde_json::to_string_pretty(&map)?)
 }
 
 pub async fn dict_words(state: &AppState, guild_id: GuildId) -> Result<Vec<String>> {
     let mut conn = get_redis_connection(state).await?;
-    let entries = dict::get_all(
+    let entries = dict::get_entries(
         &mut conn,
         dict::GetAllOption {
             guild_id: guild_id.into(),
@@ -155,7 +380,742 @@ pub async fn dict_words(state: &AppState, guild_id: GuildId) -> Result<Vec<Strin
     )
     .await?;
 
-    Ok(entries.into_iter().map(|(word, _)| word).collect())
+    Ok(entries.into_iter().map(|entry| entry.word).collect())
+}
+
+pub async fn voice_profile_describe(
+    state: &AppState,
+    guild_id: GuildId,
+    user_id: Option<UserId>,
+) -> Result<String> {
+    let mut conn = get_redis_connection(state).await?;
+    let guild = voice_profile::get(
+        &mut conn,
+        voice_profile::GetOption {
+            scope: voice::guild_scope(guild_id),
+        },
+    )
+    .await?;
+
+    let Some(user_id) = user_id else {
+        return Ok(voice::describe_profile(&guild, &VoiceProfile::default()));
+    };
+
+    let own = voice_profile::get(
+        &mut conn,
+        voice_profile::GetOption {
+            scope: voice::user_scope(guild_id, user_id),
+        },
+    )
+    .await?;
+
+    Ok(voice::describe_profile(&own, &guild))
+}
+
+/// 声の設定を更新する
+/// 値が範囲外の場合や、スタイルがエンジンに存在しない場合はエラーを返す
+pub async fn voice_profile_update(
+    state: &AppState,
+    scope: Scope,
+    voice_id: Option<&str>,
+    params: &[(VoiceParam, f64)],
+) -> Result<()> {
+    let mut profile = VoiceProfile::default();
+    for (param, value) in params {
+        param.set(&mut profile, *value)?;
+    }
+
+    if let Some(voice_id) = voice_id {
+        profile.voice_id = Some(validate_voice_id(state, voice_id).await?.to_string());
+    }
+
+    if profile == VoiceProfile::default() {
+        bail!("変更する項目を指定してください。");
+    }
+
+    let mut conn = get_redis_connection(state).await?;
+    if profile.voice_id.is_some() {
+        // 元のスタイルが変わるとモーフィングできるとは限らないため、モーフィングの指定は解除する
+        voice_profile::reset_morph(&mut conn, voice_profile::ResetOption { scope })
+            .await
+            .context("Failed to reset morphing")?;
+    }
+    voice_profile::set(&mut conn, voice_profile::SetOption { scope, profile })
+        .await
+        .context("Failed to update voice profile")
+}
+
+pub async fn voice_profile_reset(state: &AppState, scope: Scope) -> Result<()> {
+    let mut conn = get_redis_connection(state).await?;
+    voice_profile::reset(&mut conn, voice_profile::ResetOption { scope })
+        .await
+        .context("Failed to reset voice profile")
+}
+
+/// サーバーの既定の声を設定する。`None`の場合は設定を削除する
+/// ユーザーとチャンネルの声が未設定の場合に使われる
+pub async fn voice_default_set(
+    state: &AppState,
+    guild_id: GuildId,
+    voice_id: Option<&str>,
+) -> Result<()> {
+    let scope = voice::guild_scope(guild_id);
+    let Some(voice_id) = voice_id else {
+        let mut conn = get_redis_connection(state).await?;
+        return voice_profile::reset_voice(&mut conn, voice_profile::ResetOption { scope })
+            .await
+            .context("Failed to reset guild default voice");
+    };
+
+    voice_profile_update(state, scope, Some(voice_id), &[]).await
+}
+
+/// 読み上げルールの変更内容
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextRuleChange {
+    Enable(Rule),
+    Disable(Rule),
+    /// ルールを指定した順番（1始まり）に移動する
+    Move(Rule, usize),
+    /// すべてのルールを既定に戻す
+    Reset,
+}
+
+/// ルール名を解釈する
+pub fn parse_text_rule(name: &str) -> Result<Rule> {
+    let Some(rule) = Rule::from_name(name) else {
+        bail!(
+            "不明なルールです: {}（`/rules show`で一覧を確認できます）",
+            name
+        );
+    };
+    Ok(rule)
+}
+
+/// サーバーの読み上げルールを返す
+pub async fn text_rules(state: &AppState, guild_id: GuildId) -> Result<RuleSet> {
+    let mut conn = get_redis_connection(state).await?;
+    text_rule::load(&mut conn, guild_id).await
+}
+
+/// サーバーの読み上げルールを変更し、変更後のルールを返す
+pub async fn text_rules_update(
+    state: &AppState,
+    guild_id: GuildId,
+    change: TextRuleChange,
+) -> Result<RuleSet> {
+    let mut conn = get_redis_connection(state).await?;
+
+    let mut rules = text_rule::load(&mut conn, guild_id).await?;
+    match change {
+        TextRuleChange::Enable(rule) => rules.set_enabled(rule, true),
+        TextRuleChange::Disable(rule) => rules.set_enabled(rule, false),
+        TextRuleChange::Move(rule, position) => {
+            ensure!(
+                (1..=rules.rules.len()).contains(&position),
+                "順番は1〜{}で指定してください。",
+                rules.rules.len()
+            );
+            rules.move_to(rule, position - 1);
+        }
+        TextRuleChange::Reset => {
+            bot_db::text_rule::reset(
+                &mut conn,
+                bot_db::text_rule::ResetOption {
+                    guild_id: guild_id.into(),
+                },
+            )
+            .await
+            .context("Failed to reset text rules")?;
+            return Ok(RuleSet::default());
+        }
+    }
+
+    text_rule::save(&mut conn, guild_id, &rules)
+        .await
+        .context("Failed to save text rules")?;
+    Ok(rules)
+}
+
+/// 読み上げルールの一覧を表示する埋め込みを作る
+pub fn build_text_rules_embed(rules: &RuleSet) -> CreateEmbed {
+    let lines = rules
+        .rules
+        .iter()
+        .enumerate()
+        .map(|(i, (rule, enabled))| {
+            format!(
+                "{}. {} `{}` {}",
+                i + 1,
+                if *enabled { "✅" } else { "❌" },
+                rule.name(),
+                rule.description()
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let mut embed = CreateEmbed::default();
+    embed
+        .title("読み上げルール")
+        .description(format!("上から順に適用します。\n{}", lines.join("\n")))
+        .color(0x3498db);
+    embed
+}
+
+/// 読み上げの設定の項目名を解釈する
+pub fn parse_read_option(name: &str) -> Result<ReadOption> {
+    let Some(option) = ReadOption::from_name(name) else {
+        bail!(
+            "不明な項目です: {}（`/read show`で一覧を確認できます）",
+            name
+        );
+    };
+    Ok(option)
+}
+
+/// サーバーの読み上げの設定を返す
+pub async fn read_settings(state: &AppState, guild_id: GuildId) -> Result<ReadSettings> {
+    let mut conn = get_redis_connection(state).await?;
+    read_setting::load(&mut conn, guild_id).await
+}
+
+/// 読み上げの設定の変更内容
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReadSettingsChange {
+    Set(ReadOption, String),
+    /// 項目を既定に戻す。`None`の場合はすべての項目を戻す
+    Reset(Option<ReadOption>),
+}
+
+/// サーバーの読み上げの設定を変更し、変更後の設定を返す
+pub async fn read_settings_update(
+    state: &AppState,
+    guild_id: GuildId,
+    change: ReadSettingsChange,
+) -> Result<ReadSettings> {
+    let mut conn = get_redis_connection(state).await?;
+    match change {
+        ReadSettingsChange::Set(option, value) => {
+            let mut settings = ReadSettings::default();
+            option.set(&mut settings, &value)?;
+            read_settings::set(
+                &mut conn,
+                read_settings::SetOption {
+                    guild_id: guild_id.into(),
+                    settings,
+                },
+            )
+            .await
+            .context("Failed to update read settings")?;
+        }
+        ReadSettingsChange::Reset(option) => {
+            read_settings::reset(
+                &mut conn,
+                read_settings::ResetOption {
+                    guild_id: guild_id.into(),
+                    field: option.map(|x| x.name().to_string()),
+                },
+            )
+            .await
+            .context("Failed to reset read settings")?;
+        }
+    }
+    read_setting::load(&mut conn, guild_id).await
+}
+
+/// 名前の読み方の最大の文字数
+pub const MAX_NAME_READING_LENGTH: usize = 32;
+
+/// 名前の読み上げの設定の変更内容
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserNameChange {
+    /// 名前の読み方を登録する
+    Reading(String),
+    /// 名前を読み上げるかどうか。`None`の場合はサーバーの設定に従う
+    Announce(Option<bool>),
+    /// すべての設定を削除する
+    Reset,
+}
+
+/// ユーザーの名前の読み上げの設定を表示用の文字列にする
+pub async fn user_name_describe(
+    state: &AppState,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> Result<String> {
+    let mut conn = get_redis_connection(state).await?;
+    let own = user_name::get(
+        &mut conn,
+        user_name::GetOption {
+            guild_id: guild_id.into(),
+            user_id: user_id.into(),
+        },
+    )
+    .await?;
+    let guild =
+        read_setting::ReadConfig::from_settings(&read_setting::load(&mut conn, guild_id).await?);
+
+    let reading = own.reading.map_or_else(
+        || "未設定（表示名を読みます）".to_string(),
+        |x| format!("「{}」", x),
+    );
+    let announce = match own.announce {
+        Some(true) => "読む".to_string(),
+        Some(false) => "読まない".to_string(),
+        None => format!(
+            "{} (サーバー既定)",
+            if guild.author_name {
+                "読む"
+            } else {
+                "読まない"
+            }
+        ),
+    };
+    Ok(format!(
+        "名前の読み方: {}\nメッセージの前に名前を読む: {}",
+        reading, announce
+    ))
+}
+
+/// ユーザーの名前の読み上げの設定を変更する
+pub async fn user_name_update(
+    state: &AppState,
+    guild_id: GuildId,
+    user_id: UserId,
+    change: UserNameChange,
+) -> Result<()> {
+    let mut conn = get_redis_connection(state).await?;
+    let (guild_id, user_id) = (guild_id.into(), user_id.into());
+    let settings = match change {
+        UserNameChange::Reading(reading) => {
+            let reading = reading.trim();
+            ensure!(!reading.is_empty(), "読み方を指定してください。");
+            ensure!(
+                reading.chars().count() <= MAX_NAME_READING_LENGTH,
+                "読み方は{}文字以内で指定してください。",
+                MAX_NAME_READING_LENGTH
+            );
+            UserNameSettings {
+                reading: Some(reading.to_string()),
+                ..Default::default()
+            }
+        }
+        UserNameChange::Announce(Some(announce)) => UserNameSettings {
+            announce: Some(announce),
+            ..Default::default()
+        },
+        UserNameChange::Announce(None) | UserNameChange::Reset => {
+            let field =
+                (change == UserNameChange::Announce(None)).then_some(user_name::FIELD_ANNOUNCE);
+            return user_name::reset(
+                &mut conn,
+                user_name::ResetOption {
+                    guild_id,
+                    user_id,
+                    field,
+                },
+            )
+            .await
+            .context("Failed to reset user name settings");
+        }
+    };
+
+    user_name::set(
+        &mut conn,
+        user_name::SetOption {
+            guild_id,
+            user_id,
+            settings,
+        },
+    )
+    .await
+    .context("Failed to update user name settings")
+}
+
+/// 読み上げの設定を表示する埋め込みを作る
+pub fn build_read_settings_embed(settings: &ReadSettings) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.title("読み上げの設定").color(0x3498db);
+    for option in ReadOption::ALL {
+        embed.field(
+            format!("{} (`{}`)", option.label(), option.name()),
+            format!(
+                "{}\n指定できる値: {}",
+                option.describe(settings),
+                option.values()
+            ),
+            false,
+        );
+    }
+    embed
+}
+
+/// チャンネルで読み上げるメッセージの声を設定する。`None`の場合は設定を削除する
+/// ユーザーの声が未設定の場合に、サーバーの既定の声より優先して使われる
+pub async fn voice_channel_set(
+    state: &AppState,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    voice_id: Option<&str>,
+) -> Result<()> {
+    let mut conn = get_redis_connection(state).await?;
+    let Some(voice_id) = voice_id else {
+        return bot_db::voice::remove_channel(
+            &mut conn,
+            bot_db::voice::RemoveChannelOption {
+                guild_id: guild_id.into(),
+                channel_id: channel_id.into(),
+            },
+        )
+        .await
+        .context("Failed to remove channel voice");
+    };
+
+    let voice_id = validate_voice_id(state, voice_id).await?;
+    bot_db::voice::set_channel(
+        &mut conn,
+        bot_db::voice::SetChannelOption {
+            guild_id: guild_id.into(),
+            channel_id: channel_id.into(),
+            voice_id: voice_id.to_string(),
+        },
+    )
+    .await
+    .context("Failed to set channel voice")
+}
+
+/// モーフィングの割合を省略したときの値
+pub const DEFAULT_MORPH_RATE: f64 = 0.5;
+
+/// 自分の声に混ぜ合わせる（モーフィングする）スタイルと割合を設定する。`None`の場合は設定を削除する
+/// 自分の声（スタイル）を設定していて、そのスタイルとモーフィングできる場合のみ設定できる
+pub async fn voice_morph_set(
+    state: &AppState,
+    guild_id: GuildId,
+    user_id: UserId,
+    morph: Option<(&str, f64)>,
+) -> Result<()> {
+    let scope = voice::user_scope(guild_id, user_id);
+    let mut conn = get_redis_connection(state).await?;
+    let Some((target, rate)) = morph else {
+        return voice_profile::reset_morph(&mut conn, voice_profile::ResetOption { scope })
+            .await
+            .context("Failed to reset morphing");
+    };
+
+    if !(0.0..=1.0).contains(&rate) {
+        bail!("割合は0.0から1.0の範囲で指定してください。");
+    }
+    let profile = voice_profile::get(&mut conn, voice_profile::GetOption { scope }).await?;
+    let Some(base) = profile.voice_id else {
+        bail!("モーフィングを設定する前に、`/voice select`などで自分の声を選んでください。");
+    };
+    let base = base.parse::<VoiceId>()?;
+    let target = validate_voice_id(state, target).await?;
+
+    let targets = state.speech_engine.morphable_targets(&base).await?;
+    if targets.is_empty() {
+        // モーフィングに対応していないエンジンも空になる
+        bail!("スタイルID {} の声はモーフィングに対応していません。", base);
+    }
+    if !targets.contains(&target) {
+        bail!(
+            "スタイルID {} と {} はモーフィングできません。",
+            base,
+            target
+        );
+    }
+
+    voice_profile::set(
+        &mut conn,
+        voice_profile::SetOption {
+            scope,
+            profile: VoiceProfile {
+                morph_target: Some(target.to_string()),
+                morph_rate: Some(rate),
+                ..Default::default()
+            },
+        },
+    )
+    .await
+    .context("Failed to set morphing")
+}
+
+/// スタイルIDの形式と、エンジンに存在するかを確かめる
+async fn validate_voice_id(state: &AppState, voice_id: &str) -> Result<VoiceId> {
+    let Ok(voice_id) = voice_id.parse::<VoiceId>() else {
+        bail!("スタイルIDの形式が正しくありません: {}", voice_id);
+    };
+    let voice_ids = bot_speech::speech::list_voice_ids(state.speech_engine.as_ref()).await?;
+    if !voice_ids.contains(&voice_id) {
+        bail!("スタイルID {} は存在しません。", voice_id);
+    }
+    Ok(voice_id)
+}
+
+/// 試聴で読み上げる文章
+const PREVIEW_TEXT: &str = "こんにちは。この声で読み上げます。";
+pub const PREVIEW_FILE_NAME: &str = "preview.wav";
+
+pub enum VoicePreviewOutcome {
+    /// ボイスチャンネルで再生した
+    Played { name: String },
+    /// ボイスチャンネルに接続していないため、音声ファイル（WAV）として返す
+    Audio { name: String, wav: Vec<u8> },
+}
+
+/// 指定したスタイルで試聴用の文章を合成する
+/// 話速などはユーザーの設定を使い、声の設定は変更しない
+pub async fn preview_voice(
+    ctx: &SerenityContext,
+    state: &AppState,
+    guild_id: GuildId,
+    user_id: UserId,
+    voice_id: &str,
+) -> Result<VoicePreviewOutcome> {
+    let Ok(voice_id) = voice_id.parse::<VoiceId>() else {
+        bail!("スタイルIDの形式が正しくありません: {}", voice_id);
+    };
+    let voices = state.speech_engine.voices().await?;
+    let Some(voice) = voices.iter().find(|v| v.id == voice_id) else {
+        bail!("スタイルID {} は存在しません。", voice_id);
+    };
+    let name = voice::voice_name(voice);
+
+    let mut conn = get_redis_connection(state).await?;
+    let profile = voice::get_effective_profile(&mut conn, guild_id, user_id).await?;
+    let audio = bot_speech::speech::make_speech_by_voice(
+        state.speech_engine.as_ref(),
+        &state.audio_cache,
+        PREVIEW_TEXT.to_string(),
+        voice_id,
+        None,
+        &voice::to_speech_params(&profile),
+    )
+    .await?;
+
+    if bot_call::is_connected(ctx, guild_id).await? {
+        bot_call::enqueue(ctx, guild_id, audio.into()).await?;
+        Ok(VoicePreviewOutcome::Played { name })
+    } else {
+        Ok(VoicePreviewOutcome::Audio {
+            name,
+            wav: audio.to_wav(),
+        })
+    }
+}
+
+/// 試聴の結果をインタラクションのフォローアップメッセージにする
+pub fn build_preview_followup<'a, 'b>(
+    message: &'b mut CreateInteractionResponseFollowup<'a>,
+    outcome: Result<VoicePreviewOutcome>,
+) -> &'b mut CreateInteractionResponseFollowup<'a> {
+    message.ephemeral(true);
+    match outcome {
+        Ok(VoicePreviewOutcome::Played { name }) => {
+            message.content(format!("`{}`の試聴をボイスチャンネルで再生します。", name))
+        }
+        Ok(VoicePreviewOutcome::Audio { name, wav }) => message
+            .content(format!("`{}`の試聴です。", name))
+            .add_file(AttachmentType::Bytes {
+                data: wav.into(),
+                filename: PREVIEW_FILE_NAME.to_string(),
+            }),
+        Err(err) => message.content(format!("試聴できませんでした: {}", err)),
+    }
+}
+
+/// `/sing`で歌わせられる楽譜の長さの上限
+const MAX_SING_DURATION: Duration = Duration::from_secs(90);
+
+/// 楽譜として添付できるファイルの大きさの上限
+const MAX_SCORE_FILE_BYTES: u64 = 1024 * 1024;
+
+/// 入力された文字列か添付ファイルから楽譜を読み込む
+/// 拡張子が`.musicxml`・`.xml`のファイルはMusicXML、それ以外はテキスト形式として扱う
+pub async fn load_score(text: Option<&str>, file: Option<&Attachment>) -> Result<Score> {
+    let Some(file) = file else {
+        let Some(text) = text.filter(|x| !x.trim().is_empty()) else {
+            bail!("楽譜を入力するか、楽譜のファイルを添付してください。");
+        };
+        return Score::parse_text(text);
+    };
+
+    let name = file.filename.to_lowercase();
+    if name.ends_with(".mxl") {
+        bail!("圧縮されたMusicXML（.mxl）には対応していません。非圧縮の.musicxmlで書き出してください。");
+    }
+    ensure!(
+        file.size <= MAX_SCORE_FILE_BYTES,
+        "楽譜のファイルが大きすぎます（{}KBまで）。",
+        MAX_SCORE_FILE_BYTES / 1024
+    );
+
+    let bytes = file
+        .download()
+        .await
+        .context("Failed to download score attachment")?;
+    let content = String::from_utf8(bytes)
+        .map_err(|_| anyhow!("楽譜のファイルをUTF-8で保存してください。"))?;
+    if name.ends_with(".musicxml") || name.ends_with(".xml") {
+        Score::parse_musicxml(&content)
+    } else {
+        Score::parse_text(&content)
+    }
+}
+
+/// 楽譜を歌わせ、ボイスチャンネルで再生する
+/// `voice_id`を省略した場合は歌唱に使える最初の声で歌う。再生した声の名前を返す
+pub async fn sing(
+    ctx: &SerenityContext,
+    state: &AppState,
+    guild_id: GuildId,
+    score: Score,
+    voice_id: Option<&str>,
+) -> Result<String> {
+    if !bot_call::is_connected(ctx, guild_id).await? {
+        bail!("ボイスチャンネルに参加していません。`/v`で参加させてから実行してください。");
+    }
+    ensure!(
+        score.duration() <= MAX_SING_DURATION,
+        "楽譜が長すぎます（{}秒まで）。",
+        MAX_SING_DURATION.as_secs()
+    );
+
+    let singers = state.speech_engine.singers().await?;
+    let singer = match voice_id {
+        Some(voice_id) => {
+            let Ok(voice_id) = voice_id.parse::<VoiceId>() else {
+                bail!("スタイルIDの形式が正しくありません: {}", voice_id);
+            };
+            singers
+                .iter()
+                .find(|v| v.id == voice_id)
+                .ok_or_else(|| anyhow!("スタイルID {} は歌唱に対応していません。", voice_id))?
+        }
+        None => singers
+            .first()
+            .ok_or_else(|| anyhow!("歌唱に対応した声がありません。"))?,
+    };
+
+    let request = SingRequest {
+        score,
+        voice: singer.id.clone(),
+    };
+    let audio = match state.speech_engine.sing(request).await {
+        Ok(audio) => audio.decode().await?,
+        Err(err) => match err.downcast_ref::<VoicevoxError>() {
+            Some(VoicevoxError::Rejected { detail, .. }) => {
+                bail!("歌詞や音の高さを確認してください: {}", detail)
+            }
+            _ => return Err(err),
+        },
+    };
+    bot_call::enqueue(ctx, guild_id, audio.into()).await?;
+
+    Ok(voice::voice_name(singer))
+}
+
+/// 音声合成エンジンの状態を表示する埋め込みを作る
+pub fn build_status_embed(state: &AppState) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.title("音声合成エンジンの状態").color(0x1abc9c);
+
+    for engine in state.speech_engine.status() {
+        let lines = engine
+            .endpoints
+            .iter()
+            .map(|endpoint| {
+                let mark = if endpoint.healthy { "🟢" } else { "🔴" };
+                let mut line = format!(
+                    "{} `{}` バージョン: {} / 処理中: {}",
+                    mark,
+                    endpoint.api_base,
+                    endpoint.version.as_deref().unwrap_or("不明"),
+                    endpoint.in_flight
+                );
+                if endpoint.circuit_open {
+                    line += &format!(
+                        "\n　切り離し中（連続失敗: {}回）",
+                        endpoint.consecutive_failures
+                    );
+                }
+                if let (false, Some(err)) = (endpoint.healthy, &endpoint.last_error) {
+                    line += &format!("\n　直近のエラー: {}", truncate(err, 200));
+                }
+                line
+            })
+            .collect::<Vec<_>>();
+
+        let mut value = if lines.is_empty() {
+            "エンドポイントなし".to_string()
+        } else {
+            lines.join("\n")
+        };
+        value += &format!(
+            "\n{}",
+            engine
+                .capabilities
+                .as_ref()
+                .map_or_else(|| "機能: 未確認".to_string(), describe_capabilities)
+        );
+        embed.field(format!("`{}`", engine.namespace), value, false);
+    }
+
+    let stats = state.audio_cache.stats();
+    let mut cache = format!(
+        "ヒット率: {}（メモリ: {}回 / ディスク: {}回 / ミス: {}回）\n保持: {}件 / {:.1}MB（上限 {:.1}MB）",
+        stats
+            .hit_rate()
+            .map_or_else(|| "-".to_string(), |rate| format!("{:.1}%", rate * 100.0)),
+        stats.hits,
+        stats.disk_hits,
+        stats.misses,
+        stats.entries,
+        to_mb(stats.bytes as u64),
+        to_mb(stats.capacity_bytes as u64),
+    );
+    if let Some(bytes) = stats.disk_bytes {
+        cache += &format!("\nディスク: {:.1}MB", to_mb(bytes));
+    }
+    embed.field("音声キャッシュ", cache, false);
+
+    embed
+}
+
+/// エンジンが対応している機能を1行で表す
+fn describe_capabilities(capabilities: &EngineCapabilities) -> String {
+    let features = [
+        ("プリセット", capabilities.presets),
+        ("ユーザー辞書", capabilities.user_dict),
+        ("モーフィング", capabilities.morphing),
+        ("歌唱", capabilities.sing),
+    ]
+    .into_iter()
+    .map(|(name, supported)| format!("{}{}", if supported { "✅" } else { "❌" }, name))
+    .collect::<Vec<_>>()
+    .join(" ");
+
+    let mut line = format!("機能: {}", features);
+    if let Some(name) = &capabilities.engine_name {
+        line += &format!("\n　エンジン: {} {}", name, capabilities.version);
+    }
+    if !capabilities.devices.is_empty() {
+        line += &format!("\n　デバイス: {}", capabilities.devices.join(", "));
+    }
+    line
+}
+
+fn to_mb(bytes: u64) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0)
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() > max_chars {
+        text.chars().take(max_chars).collect::<String>() + "…"
+    } else {
+        text.to_string()
+    }
 }
 
 pub fn build_help_embed() -> CreateEmbed {
@@ -187,7 +1147,17 @@ pub fn build_help_embed() -> CreateEmbed {
     );
     embed.field(
         "`/dict add`, `!dict add`",
-        "読み替えを辞書に追加します。",
+        "読み替えを辞書に追加します。アクセントを指定すると、音声合成エンジンの辞書にアクセント付きで登録します。",
+        false,
+    );
+    embed.field(
+        "`/dict add regex:True`, `!dict regex`",
+        "正規表現で読み替えを追加します。読みでは`$1`などでキャプチャを参照できます。`priority`で適用する優先度（大きいものから先）を指定できます。",
+        false,
+    );
+    embed.field(
+        "`/dict priority`, `!dict priority`",
+        "登録済みの読み替えを適用する優先度を変更します。",
         false,
     );
     embed.field(
@@ -200,6 +1170,61 @@ pub fn build_help_embed() -> CreateEmbed {
         "登録済みの読み替え一覧をJSON形式で表示します。",
         false,
     );
+    embed.field(
+        "`/profile`, `!profile`",
+        "自分の声の設定（スタイル・話速・音高・抑揚・音量・無音長）を表示・変更します。`guild_set` などでサーバーの既定値を変更できます（サーバー管理権限が必要）。",
+        false,
+    );
+    embed.field(
+        "`/voice select`, `!voice`",
+        "話者とスタイルを一覧から選んで自分の声を変更します。選んだスタイルは決定する前に試聴できます。",
+        false,
+    );
+    embed.field(
+        "`/voice preview`, `!voice preview`",
+        "指定したスタイルの声を試聴します。ボイスチャンネルに接続中はそこで再生し、それ以外は音声ファイルを送信します。",
+        false,
+    );
+    embed.field(
+        "`/voice morph`, `!voice morph`",
+        "自分の声に別のスタイルを指定した割合で混ぜ合わせます（モーフィング）。同じキャラクターを選んだ人同士でも声を聞き分けられます。",
+        false,
+    );
+    embed.field(
+        "`/voice default`, `/voice channel`, `!voice default`, `!voice channel`",
+        "声を設定していないユーザーに使うサーバーやチャンネルの既定の声を設定します（サーバー管理権限が必要）。声はユーザー、チャンネル、サーバーの既定の順に優先されます。",
+        false,
+    );
+    embed.field(
+        "`/rules`, `!rules`",
+        "URLの省略や絵文字の除去など、読み上げる文章の変換ルールを表示します。`enable`・`disable`・`move`・`reset`で有効・無効や適用順を変更できます（サーバー管理権限が必要）。",
+        false,
+    );
+    embed.field(
+        "`/read`, `!read`",
+        "読み上げる最大の文字数と、長い文章の省略のしかた（文字数・文の区切り・最初の行だけ・省略しない）、省略時に末尾に付ける文字列、送信者の名前を読むかどうか、絵文字の読み方、画像やスタンプなどの説明、返信先やスレッドのメッセージを読むかどうか、コードブロックの読み方、同じ文字の繰り返しを読む回数を表示・変更します（変更にはサーバー管理権限が必要）。",
+        false,
+    );
+    embed.field(
+        "`/name`, `!name`",
+        "メッセージの前に読み上げる自分の名前の読み方を登録します。`announce`で名前を読むかどうかを自分だけ変更できます。サーバー全体では`/read`の`author_name`で設定します。",
+        false,
+    );
+    embed.field(
+        "`/sing`, `!sing`",
+        "楽譜を歌わせ、ボイスチャンネルで再生します。`C4:ど:4 D4:れ:4 R:4`のように`音名:歌詞:長さ`を並べるか、MusicXMLファイルを添付してください。",
+        false,
+    );
+    embed.field(
+        "`/status`, `!status`",
+        "音声合成エンジンの各エンドポイントの状態と対応している機能を表示します。",
+        false,
+    );
+    embed.field(
+        "`%` + カナ",
+        "メッセージの先頭に`%`を付けると、AquesTalk風記法のカナ（例: `%コンニチワ'`）として読み方とアクセントを指定して読み上げます。",
+        false,
+    );
     embed.field("`/help`, `!help`", "このヘルプを表示します。", false);
 
     embed
@@ -1,13 +1,19 @@
 use crate::app_state::AppState;
 use crate::command::actions;
-use crate::command::actions::VoiceToggleOutcome;
+use crate::command::actions::{
+    ReadSettingsChange, TextRuleChange, UserNameChange, VoiceToggleOutcome,
+};
+use crate::component_interaction::voice_picker;
+use crate::engine_dict;
+use crate::voice::{self, VoiceParam};
 use anyhow::{anyhow, Result};
 use bot_db::dict;
+use bot_speech::engine::WordType;
 use serde_json::Value;
 use serenity::builder::CreateEmbed;
 use serenity::client::Context as SerenityContext;
 use serenity::model::application::interaction::application_command::{
-    ApplicationCommandInteraction, CommandDataOption,
+    ApplicationCommandInteraction, CommandDataOption, CommandDataOptionValue,
 };
 use serenity::model::application::interaction::autocomplete::AutocompleteInteraction;
 use serenity::model::application::interaction::InteractionResponseType;
@@ -22,6 +28,13 @@ pub async fn handle(
         "s" => handle_skip(ctx, interaction, state).await?,
         "time" => handle_time(ctx, interaction, state).await?,
         "dict" => handle_dict(ctx, interaction, state).await?,
+        "profile" => handle_profile(ctx, interaction, state).await?,
+        "voice" => handle_voice_command(ctx, interaction, state).await?,
+        "rules" => handle_rules(ctx, interaction, state).await?,
+        "read" => handle_read(ctx, interaction, state).await?,
+        "name" => handle_name(ctx, interaction, state).await?,
+        "sing" => handle_sing(ctx, interaction, state).await?,
+        "status" => handle_status(ctx, interaction, state).await?,
         "help" => handle_help(ctx, interaction).await?,
         _ => respond_text(ctx, interaction, "未対応のコマンドです。").await?,
     }
@@ -34,10 +47,6 @@ pub async fn handle_autocomplete(
     interaction: &AutocompleteInteraction,
     state: &AppState,
 ) -> Result<()> {
-    if interaction.data.name != "dict" {
-        return Ok(());
-    }
-
     let Some(guild_id) = interaction.guild_id else {
         return Ok(());
     };
@@ -47,24 +56,59 @@ pub async fn handle_autocomplete(
     };
 
     let query = option.value.as_ref().and_then(Value::as_str).unwrap_or("");
+    let lower_query = query.to_lowercase();
 
-    let mut words = match actions::dict_words(state, guild_id).await {
-        Ok(words) => words,
-        Err(_) => Vec::new(),
+    // (表示名, 値)
+    let suggestions: Vec<(String, String)> = match interaction.data.name.as_str() {
+        "dict" => {
+            let mut words = match actions::dict_words(state, guild_id).await {
+                Ok(words) => words,
+                Err(_) => Vec::new(),
+            };
+            words.sort();
+
+            words
+                .into_iter()
+                .filter(|word| lower_query.is_empty() || word.to_lowercase().contains(&lower_query))
+                .take(25)
+                .map(|word| (word.clone(), word))
+                .collect()
+        }
+        "voice" => {
+            let voices = state.speech_engine.voices().await.unwrap_or_default();
+
+            voices
+                .iter()
+                .map(|v| (voice::voice_name(v), v.id.to_string()))
+                .filter(|(name, id)| {
+                    lower_query.is_empty()
+                        || name.to_lowercase().contains(&lower_query)
+                        || id.contains(&lower_query)
+                })
+                .take(25)
+                .collect()
+        }
+        "sing" => {
+            let singers = state.speech_engine.singers().await.unwrap_or_default();
+
+            singers
+                .iter()
+                .map(|v| (voice::voice_name(v), v.id.to_string()))
+                .filter(|(name, id)| {
+                    lower_query.is_empty()
+                        || name.to_lowercase().contains(&lower_query)
+                        || id.contains(&lower_query)
+                })
+                .take(25)
+                .collect()
+        }
+        _ => return Ok(()),
     };
-    words.sort();
-
-    let lower_query = query.to_lowercase();
-    let suggestions = words
-        .into_iter()
-        .filter(|word| lower_query.is_empty() || word.to_lowercase().contains(&lower_query))
-        .take(25)
-        .collect::<Vec<_>>();
 
     interaction
         .create_autocomplete_response(&ctx.http, |response| {
-            for word in &suggestions {
-                response.add_string_choice(word, word.clone());
+            for (name, value) in &suggestions {
+                response.add_string_choice(name, value.clone());
             }
             response
         })
@@ -265,21 +309,60 @@ async fn handle_dict(
         "add" => {
             let word = extract_string_option(subcommand, "word")?;
             let read_as = extract_string_option(subcommand, "read_as")?;
+            let accent_type = subcommand
+                .options
+                .iter()
+                .find(|opt| opt.name == "accent")
+                .and_then(|opt| opt.value.as_ref())
+                .and_then(Value::as_u64);
+            let word_type = subcommand
+                .options
+                .iter()
+                .find(|opt| opt.name == "word_type")
+                .and_then(|opt| opt.value.as_ref())
+                .and_then(Value::as_str)
+                .and_then(engine_dict::parse_word_type)
+                .unwrap_or(WordType::ProperNoun);
+            let engine_word = accent_type.map(|accent_type| actions::EngineWordOption {
+                accent_type: accent_type as u32,
+                word_type,
+            });
+            let regex = subcommand
+                .options
+                .iter()
+                .find(|opt| opt.name == "regex")
+                .and_then(|opt| opt.value.as_ref())
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
+            let entry = actions::DictEntryOption {
+                kind: if regex {
+                    dict::EntryKind::Regex
+                } else {
+                    dict::EntryKind::Literal
+                },
+                priority: subcommand
+                    .options
+                    .iter()
+                    .find(|opt| opt.name == "priority")
+                    .and_then(|opt| opt.value.as_ref())
+                    .and_then(Value::as_i64)
+                    .unwrap_or(0),
+            };
 
-            match actions::dict_add(state, guild_id, &word, &read_as).await? {
-                dict::InsertResponse::Success => {
+            match actions::dict_add(state, guild_id, &word, &read_as, entry, engine_word).await {
+                Ok(outcome) => {
                     respond_text(
                         ctx,
                         interaction,
-                        &format!("辞書に登録しました: {} → {}", word, read_as),
+                        &actions::describe_dict_add(&outcome, &word, &read_as),
                     )
                     .await?
                 }
-                dict::InsertResponse::WordAlreadyExists => {
+                Err(err) => {
                     respond_text(
                         ctx,
                         interaction,
-                        "すでに登録済みです。上書きする場合はいったん削除してください。",
+                        &format!("辞書に登録できませんでした: {}", err),
                     )
                     .await?
                 }
@@ -297,6 +380,27 @@ async fn handle_dict(
                 }
             }
         }
+        "priority" => {
+            let word = extract_string_option(subcommand, "word")?;
+            let priority = subcommand
+                .options
+                .iter()
+                .find(|opt| opt.name == "priority")
+                .and_then(|opt| opt.value.as_ref())
+                .and_then(Value::as_i64)
+                .ok_or_else(|| anyhow!("Option priority not found"))?;
+
+            let content = match actions::dict_set_priority(state, guild_id, &word, priority).await {
+                Ok(dict::SetPriorityResponse::Success) => {
+                    format!("{} の優先度を{}にしました。", word, priority)
+                }
+                Ok(dict::SetPriorityResponse::WordDoesNotExist) => {
+                    "指定された単語は登録されていません。".to_string()
+                }
+                Err(err) => format!("優先度を変更できませんでした: {}", err),
+            };
+            respond_text(ctx, interaction, &content).await?
+        }
         "list" => {
             let json = actions::dict_list(state, guild_id).await?;
             if json.len() <= 1900 {
@@ -316,6 +420,472 @@ async fn handle_dict(
     Ok(())
 }
 
+async fn handle_profile(
+    ctx: &SerenityContext,
+    interaction: &ApplicationCommandInteraction,
+    state: &AppState,
+) -> Result<()> {
+    let Some(guild_id) = interaction.guild_id else {
+        respond_text(
+            ctx,
+            interaction,
+            "このコマンドはサーバー内で使用してください。",
+        )
+        .await?;
+        return Ok(());
+    };
+
+    let Some(subcommand) = interaction.data.options.first() else {
+        respond_text(ctx, interaction, "サブコマンドを指定してください。").await?;
+        return Ok(());
+    };
+
+    let user_id = interaction.user.id;
+    let is_guild_subcommand = subcommand.name.starts_with("guild_");
+    if is_guild_subcommand && subcommand.name != "guild_show" && !can_manage_guild(interaction) {
+        respond_text(
+            ctx,
+            interaction,
+            "サーバーの既定値を変更するにはサーバー管理権限が必要です。",
+        )
+        .await?;
+        return Ok(());
+    }
+    let scope = if is_guild_subcommand {
+        voice::guild_scope(guild_id)
+    } else {
+        voice::user_scope(guild_id, user_id)
+    };
+
+    match subcommand.name.as_str() {
+        "show" => {
+            let text = actions::voice_profile_describe(state, guild_id, Some(user_id)).await?;
+            respond_text(ctx, interaction, &text).await?
+        }
+        "guild_show" => {
+            let text = actions::voice_profile_describe(state, guild_id, None).await?;
+            respond_text(ctx, interaction, &text).await?
+        }
+        "set" | "guild_set" => {
+            let voice_id = subcommand
+                .options
+                .iter()
+                .find(|opt| opt.name == "style")
+                .and_then(|opt| opt.value.as_ref())
+                .and_then(Value::as_str);
+            let params = VoiceParam::ALL
+                .into_iter()
+                .filter_map(|param| {
+                    let value = subcommand
+                        .options
+                        .iter()
+                        .find(|opt| opt.name == param.name())?
+                        .value
+                        .as_ref()?
+                        .as_f64()?;
+                    Some((param, value))
+                })
+                .collect::<Vec<_>>();
+
+            match actions::voice_profile_update(state, scope, voice_id, &params).await {
+                Ok(()) => respond_text(ctx, interaction, "声の設定を更新しました。").await?,
+                Err(err) => {
+                    respond_text(
+                        ctx,
+                        interaction,
+                        &format!("声の設定を更新できませんでした: {}", err),
+                    )
+                    .await?
+                }
+            }
+        }
+        "reset" | "guild_reset" => {
+            actions::voice_profile_reset(state, scope).await?;
+            respond_text(ctx, interaction, "声の設定を削除しました。").await?
+        }
+        _ => respond_text(ctx, interaction, "未対応のサブコマンドです。").await?,
+    }
+
+    Ok(())
+}
+
+async fn handle_voice_command(
+    ctx: &SerenityContext,
+    interaction: &ApplicationCommandInteraction,
+    state: &AppState,
+) -> Result<()> {
+    let Some(guild_id) = interaction.guild_id else {
+        respond_text(
+            ctx,
+            interaction,
+            "このコマンドはサーバー内で使用してください。",
+        )
+        .await?;
+        return Ok(());
+    };
+
+    let Some(subcommand) = interaction.data.options.first() else {
+        respond_text(ctx, interaction, "サブコマンドを指定してください。").await?;
+        return Ok(());
+    };
+
+    match subcommand.name.as_str() {
+        "select" => {
+            let picker =
+                voice_picker::speaker_picker(state, guild_id, interaction.user.id, 0).await?;
+
+            interaction
+                .create_interaction_response(&ctx.http, |response| {
+                    response
+                        .kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|message| {
+                            message
+                                .ephemeral(true)
+                                .set_embed(picker.embed)
+                                .set_components(picker.components)
+                        })
+                })
+                .await?;
+        }
+        "preview" => {
+            let voice_id = extract_string_option(subcommand, "style")?;
+
+            // 合成に時間がかかることがあるため、先に応答しておく
+            interaction.defer_ephemeral(&ctx.http).await?;
+
+            let outcome =
+                actions::preview_voice(ctx, state, guild_id, interaction.user.id, &voice_id).await;
+            interaction
+                .create_followup_message(&ctx.http, |message| {
+                    actions::build_preview_followup(message, outcome)
+                })
+                .await?;
+        }
+        "morph" => {
+            let target = subcommand
+                .options
+                .iter()
+                .find(|opt| opt.name == "style")
+                .and_then(|opt| opt.value.as_ref())
+                .and_then(Value::as_str);
+            let rate = subcommand
+                .options
+                .iter()
+                .find(|opt| opt.name == "rate")
+                .and_then(|opt| opt.value.as_ref())
+                .and_then(Value::as_f64)
+                .unwrap_or(actions::DEFAULT_MORPH_RATE);
+
+            let result = actions::voice_morph_set(
+                state,
+                guild_id,
+                interaction.user.id,
+                target.map(|target| (target, rate)),
+            )
+            .await;
+            let content = match (result, target) {
+                (Ok(()), Some(_)) => "モーフィングを設定しました。".to_string(),
+                (Ok(()), None) => "モーフィングの設定を削除しました。".to_string(),
+                (Err(err), _) => format!("モーフィングを設定できませんでした: {}", err),
+            };
+            respond_text(ctx, interaction, content).await?;
+        }
+        "default" | "channel" => {
+            if !can_manage_guild(interaction) {
+                respond_text(
+                    ctx,
+                    interaction,
+                    "既定の声を変更するにはサーバー管理権限が必要です。",
+                )
+                .await?;
+                return Ok(());
+            }
+
+            let voice_id = subcommand
+                .options
+                .iter()
+                .find(|opt| opt.name == "style")
+                .and_then(|opt| opt.value.as_ref())
+                .and_then(Value::as_str);
+            let result = if subcommand.name == "default" {
+                actions::voice_default_set(state, guild_id, voice_id).await
+            } else {
+                actions::voice_channel_set(state, guild_id, interaction.channel_id, voice_id).await
+            };
+
+            let content = match (result, voice_id) {
+                (Ok(()), Some(_)) => "既定の声を設定しました。".to_string(),
+                (Ok(()), None) => "既定の声の設定を削除しました。".to_string(),
+                (Err(err), _) => format!("既定の声を変更できませんでした: {}", err),
+            };
+            respond_text(ctx, interaction, content).await?;
+        }
+        _ => respond_text(ctx, interaction, "未対応のサブコマンドです。").await?,
+    }
+
+    Ok(())
+}
+
+async fn handle_rules(
+    ctx: &SerenityContext,
+    interaction: &ApplicationCommandInteraction,
+    state: &AppState,
+) -> Result<()> {
+    let Some(guild_id) = interaction.guild_id else {
+        respond_text(
+            ctx,
+            interaction,
+            "このコマンドはサーバー内で使用してください。",
+        )
+        .await?;
+        return Ok(());
+    };
+
+    let subcommand = interaction
+        .data
+        .options
+        .first()
+        .ok_or_else(|| anyhow!("Subcommand not found"))?;
+
+    if subcommand.name == "show" {
+        let rules = actions::text_rules(state, guild_id).await?;
+        respond_embed(ctx, interaction, actions::build_text_rules_embed(&rules)).await?;
+        return Ok(());
+    }
+
+    if !can_manage_guild(interaction) {
+        respond_text(
+            ctx,
+            interaction,
+            "読み上げルールを変更するにはサーバー管理権限が必要です。",
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let change = match subcommand.name.as_str() {
+        "reset" => Ok(TextRuleChange::Reset),
+        name => {
+            let rule = actions::parse_text_rule(&extract_string_option(subcommand, "rule")?)?;
+            match name {
+                "enable" => Ok(TextRuleChange::Enable(rule)),
+                "disable" => Ok(TextRuleChange::Disable(rule)),
+                "move" => subcommand
+                    .options
+                    .iter()
+                    .find(|opt| opt.name == "position")
+                    .and_then(|opt| opt.value.as_ref())
+                    .and_then(Value::as_u64)
+                    .map(|position| TextRuleChange::Move(rule, position as usize))
+                    .ok_or_else(|| anyhow!("Option position not found")),
+                _ => Err(anyhow!("Unknown subcommand: {}", name)),
+            }
+        }
+    }?;
+
+    match actions::text_rules_update(state, guild_id, change).await {
+        Ok(rules) => {
+            respond_embed(ctx, interaction, actions::build_text_rules_embed(&rules)).await?
+        }
+        Err(err) => {
+            respond_text(
+                ctx,
+                interaction,
+                format!("読み上げルールを変更できませんでした: {}", err),
+            )
+            .await?
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle_read(
+    ctx: &SerenityContext,
+    interaction: &ApplicationCommandInteraction,
+    state: &AppState,
+) -> Result<()> {
+    let Some(guild_id) = interaction.guild_id else {
+        respond_text(
+            ctx,
+            interaction,
+            "このコマンドはサーバー内で使用してください。",
+        )
+        .await?;
+        return Ok(());
+    };
+
+    let subcommand = interaction
+        .data
+        .options
+        .first()
+        .ok_or_else(|| anyhow!("Subcommand not found"))?;
+
+    if subcommand.name == "show" {
+        let settings = actions::read_settings(state, guild_id).await?;
+        respond_embed(
+            ctx,
+            interaction,
+            actions::build_read_settings_embed(&settings),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    if !can_manage_guild(interaction) {
+        respond_text(
+            ctx,
+            interaction,
+            "読み上げの設定を変更するにはサーバー管理権限が必要です。",
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let option = |name: &str| {
+        subcommand
+            .options
+            .iter()
+            .find(|opt| opt.name == name)
+            .and_then(|opt| opt.value.as_ref())
+            .and_then(Value::as_str)
+    };
+    let change = match subcommand.name.as_str() {
+        "set" => {
+            let read_option = actions::parse_read_option(option("setting").unwrap_or_default())?;
+            let value = option("value").ok_or_else(|| anyhow!("Option value not found"))?;
+            ReadSettingsChange::Set(read_option, value.to_string())
+        }
+        "reset" => ReadSettingsChange::Reset(
+            option("setting")
+                .map(actions::parse_read_option)
+                .transpose()?,
+        ),
+        name => return Err(anyhow!("Unknown subcommand: {}", name)),
+    };
+
+    let result = actions::read_settings_update(state, guild_id, change).await;
+    match result {
+        Ok(settings) => {
+            respond_embed(
+                ctx,
+                interaction,
+                actions::build_read_settings_embed(&settings),
+            )
+            .await?
+        }
+        Err(err) => {
+            respond_text(
+                ctx,
+                interaction,
+                format!("読み上げの設定を変更できませんでした: {}", err),
+            )
+            .await?
+        }
+    }
+
+    Ok(())
+}
+
+async fn handle_name(
+    ctx: &SerenityContext,
+    interaction: &ApplicationCommandInteraction,
+    state: &AppState,
+) -> Result<()> {
+    let Some(guild_id) = interaction.guild_id else {
+        respond_text(
+            ctx,
+            interaction,
+            "このコマンドはサーバー内で使用してください。",
+        )
+        .await?;
+        return Ok(());
+    };
+
+    let subcommand = interaction
+        .data
+        .options
+        .first()
+        .ok_or_else(|| anyhow!("Subcommand not found"))?;
+    let user_id = interaction.user.id;
+
+    let change = match subcommand.name.as_str() {
+        "show" => {
+            let text = actions::user_name_describe(state, guild_id, user_id).await?;
+            respond_text(ctx, interaction, text).await?;
+            return Ok(());
+        }
+        "reading" => UserNameChange::Reading(extract_string_option(subcommand, "value")?),
+        "announce" => UserNameChange::Announce(crate::message::read_setting::parse_switch(
+            &extract_string_option(subcommand, "value")?,
+        )),
+        "reset" => UserNameChange::Reset,
+        name => return Err(anyhow!("Unknown subcommand: {}", name)),
+    };
+
+    let content = match actions::user_name_update(state, guild_id, user_id, change).await {
+        Ok(()) => "名前の設定を更新しました。".to_string(),
+        Err(err) => format!("名前の設定を更新できませんでした: {}", err),
+    };
+    respond_text(ctx, interaction, content).await?;
+
+    Ok(())
+}
+
+async fn handle_sing(
+    ctx: &SerenityContext,
+    interaction: &ApplicationCommandInteraction,
+    state: &AppState,
+) -> Result<()> {
+    let Some(guild_id) = interaction.guild_id else {
+        respond_text(
+            ctx,
+            interaction,
+            "このコマンドはサーバー内で使用してください。",
+        )
+        .await?;
+        return Ok(());
+    };
+
+    let option = |name: &str| interaction.data.options.iter().find(|opt| opt.name == name);
+    let text = option("score")
+        .and_then(|opt| opt.value.as_ref())
+        .and_then(Value::as_str);
+    let voice_id = option("style")
+        .and_then(|opt| opt.value.as_ref())
+        .and_then(Value::as_str);
+    let file = match option("file").and_then(|opt| opt.resolved.as_ref()) {
+        Some(CommandDataOptionValue::Attachment(file)) => Some(file),
+        _ => None,
+    };
+
+    // 合成に時間がかかるため、先に応答しておく
+    interaction.defer(&ctx.http).await?;
+
+    let result = match actions::load_score(text, file).await {
+        Ok(score) => actions::sing(ctx, state, guild_id, score, voice_id).await,
+        Err(err) => Err(err),
+    };
+    let content = match result {
+        Ok(name) => format!("`{}`が歌います。", name),
+        Err(err) => format!("歌えませんでした: {}", err),
+    };
+    interaction
+        .create_followup_message(&ctx.http, |message| message.content(content))
+        .await?;
+
+    Ok(())
+}
+
+async fn handle_status(
+    ctx: &SerenityContext,
+    interaction: &ApplicationCommandInteraction,
+    state: &AppState,
+) -> Result<()> {
+    let embed = actions::build_status_embed(state);
+    respond_embed(ctx, interaction, embed).await
+}
+
 async fn handle_help(
     ctx: &SerenityContext,
     interaction: &ApplicationCommandInteraction,
@@ -335,6 +905,14 @@ fn extract_string_option(option: &CommandDataOption, name: &str) -> Result<Strin
         .ok_or_else(|| anyhow!("Missing required option: {}", name))
 }
 
+fn can_manage_guild(interaction: &ApplicationCommandInteraction) -> bool {
+    interaction
+        .member
+        .as_ref()
+        .and_then(|member| member.permissions)
+        .is_some_and(|permissions| permissions.manage_guild())
+}
+
 fn find_focused_option<'a>(options: &'a [CommandDataOption]) -> Option<&'a CommandDataOption> {
     for option in options {
         if option.focused {
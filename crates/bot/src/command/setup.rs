@@ -1,7 +1,17 @@
+use crate::command::actions::{DEFAULT_MORPH_RATE, MAX_DICT_PRIORITY, MAX_NAME_READING_LENGTH};
+use crate::engine_dict;
+use crate::message::read_setting::ReadOption;
+use crate::message::text_rule::Rule;
+use crate::voice::VoiceParam;
 use anyhow::Result;
+use bot_speech::engine::WordType;
+use serenity::builder::CreateApplicationCommandOption;
 use serenity::client::Context;
 use serenity::model::application::command::{Command, CommandOptionType};
 
+/// `/dict add`で指定できるアクセント核の位置の上限
+const MAX_ACCENT_TYPE: u64 = 30;
+
 pub async fn setup_commands(ctx: &Context) -> Result<()> {
     Command::set_global_application_commands(&ctx.http, |commands| {
         commands
@@ -73,6 +83,53 @@ pub async fn setup_commands(ctx: &Context) -> Result<()> {
                                     .kind(CommandOptionType::String)
                                     .required(true)
                             })
+                            .create_sub_option(|sub_option| {
+                                sub_option
+                                    .name("accent")
+                                    .description(
+                                        "音が下がる位置（何音目か、0は平板型）。指定するとエンジンの辞書に登録します",
+                                    )
+                                    .kind(CommandOptionType::Integer)
+                                    .min_int_value(0)
+                                    .max_int_value(MAX_ACCENT_TYPE)
+                            })
+                            .create_sub_option(|sub_option| {
+                                sub_option
+                                    .name("word_type")
+                                    .description("品詞（アクセントを指定した場合のみ。既定は固有名詞）")
+                                    .kind(CommandOptionType::String);
+                                for word_type in WordType::ALL {
+                                    sub_option.add_string_choice(
+                                        engine_dict::word_type_label(word_type),
+                                        engine_dict::word_type_name(word_type),
+                                    );
+                                }
+                                sub_option
+                            })
+                            .create_sub_option(|sub_option| {
+                                sub_option
+                                    .name("regex")
+                                    .description(
+                                        "wordを正規表現として扱い、read_asで$1などのキャプチャを参照します",
+                                    )
+                                    .kind(CommandOptionType::Boolean)
+                            })
+                            .create_sub_option(|sub_option| add_priority_option(sub_option, false))
+                    })
+                    .create_option(|option| {
+                        option
+                            .name("priority")
+                            .description("登録済みの読み替えを適用する優先度を変更します。")
+                            .kind(CommandOptionType::SubCommand)
+                            .create_sub_option(|sub_option| {
+                                sub_option
+                                    .name("word")
+                                    .description("優先度を変更する単語")
+                                    .kind(CommandOptionType::String)
+                                    .required(true)
+                                    .set_autocomplete(true)
+                            })
+                            .create_sub_option(|sub_option| add_priority_option(sub_option, true))
                     })
                     .create_option(|option| {
                         option
@@ -95,6 +152,283 @@ pub async fn setup_commands(ctx: &Context) -> Result<()> {
                             .kind(CommandOptionType::SubCommand)
                     })
             })
+            .create_application_command(|command| {
+                command
+                    .name("profile")
+                    .description("声の設定を表示・変更します。")
+                    .create_option(|option| {
+                        option
+                            .name("show")
+                            .description("自分の声の設定を表示します。")
+                            .kind(CommandOptionType::SubCommand)
+                    })
+                    .create_option(|option| {
+                        option
+                            .name("set")
+                            .description("自分の声の設定を変更します。")
+                            .kind(CommandOptionType::SubCommand);
+                        add_profile_options(option)
+                    })
+                    .create_option(|option| {
+                        option
+                            .name("reset")
+                            .description("自分の声の設定を削除し、サーバーの既定値に戻します。")
+                            .kind(CommandOptionType::SubCommand)
+                    })
+                    .create_option(|option| {
+                        option
+                            .name("guild_show")
+                            .description("サーバーの既定の声の設定を表示します。")
+                            .kind(CommandOptionType::SubCommand)
+                    })
+                    .create_option(|option| {
+                        option
+                            .name("guild_set")
+                            .description("サーバーの既定の声の設定を変更します。")
+                            .kind(CommandOptionType::SubCommand);
+                        add_profile_options(option)
+                    })
+                    .create_option(|option| {
+                        option
+                            .name("guild_reset")
+                            .description("サーバーの既定の声の設定を削除します。")
+                            .kind(CommandOptionType::SubCommand)
+                    })
+            })
+            .create_application_command(|command| {
+                command
+                    .name("voice")
+                    .description("読み上げの声を選択・試聴します。")
+                    .create_option(|option| {
+                        option
+                            .name("select")
+                            .description("読み上げの声を一覧から選択します。")
+                            .kind(CommandOptionType::SubCommand)
+                    })
+                    .create_option(|option| {
+                        option
+                            .name("preview")
+                            .description("指定したスタイルの声を試聴します。")
+                            .kind(CommandOptionType::SubCommand)
+                            .create_sub_option(|sub_option| {
+                                sub_option
+                                    .name("style")
+                                    .description("試聴するスタイル")
+                                    .kind(CommandOptionType::String)
+                                    .required(true)
+                                    .set_autocomplete(true)
+                            })
+                    })
+                    .create_option(|option| {
+                        option
+                            .name("morph")
+                            .description("自分の声に別のスタイルを混ぜ合わせます。")
+                            .kind(CommandOptionType::SubCommand)
+                            .create_sub_option(|sub_option| {
+                                sub_option
+                                    .name("style")
+                                    .description("混ぜ合わせるスタイル（省略すると設定を削除）")
+                                    .kind(CommandOptionType::String)
+                                    .set_autocomplete(true)
+                            })
+                            .create_sub_option(|sub_option| {
+                                sub_option
+                                    .name("rate")
+                                    .description(format!(
+                                        "混ぜ合わせる割合（0.0〜1.0、既定は{}）",
+                                        DEFAULT_MORPH_RATE
+                                    ))
+                                    .kind(CommandOptionType::Number)
+                                    .min_number_value(0.0)
+                                    .max_number_value(1.0)
+                            })
+                    })
+                    .create_option(|option| {
+                        option
+                            .name("default")
+                            .description(
+                                "声を設定していないユーザーに使うサーバーの既定の声を設定します。",
+                            )
+                            .kind(CommandOptionType::SubCommand)
+                            .create_sub_option(|sub_option| {
+                                sub_option
+                                    .name("style")
+                                    .description("既定にするスタイル（省略すると設定を削除）")
+                                    .kind(CommandOptionType::String)
+                                    .set_autocomplete(true)
+                            })
+                    })
+                    .create_option(|option| {
+                        option
+                            .name("channel")
+                            .description(
+                                "このチャンネルで声を設定していないユーザーに使う声を設定します。",
+                            )
+                            .kind(CommandOptionType::SubCommand)
+                            .create_sub_option(|sub_option| {
+                                sub_option
+                                    .name("style")
+                                    .description("このチャンネルで使うスタイル（省略すると設定を削除）")
+                                    .kind(CommandOptionType::String)
+                                    .set_autocomplete(true)
+                            })
+                    })
+            })
+            .create_application_command(|command| {
+                command
+                    .name("rules")
+                    .description("読み上げる文章の変換ルールを設定します。")
+                    .create_option(|option| {
+                        option
+                            .name("show")
+                            .description("ルールの一覧と適用順を表示します。")
+                            .kind(CommandOptionType::SubCommand)
+                    })
+                    .create_option(|option| {
+                        option
+                            .name("enable")
+                            .description("ルールを有効にします。")
+                            .kind(CommandOptionType::SubCommand)
+                            .create_sub_option(|sub_option| add_rule_choices(sub_option))
+                    })
+                    .create_option(|option| {
+                        option
+                            .name("disable")
+                            .description("ルールを無効にします。")
+                            .kind(CommandOptionType::SubCommand)
+                            .create_sub_option(|sub_option| add_rule_choices(sub_option))
+                    })
+                    .create_option(|option| {
+                        option
+                            .name("move")
+                            .description("ルールを適用する順番を変更します。")
+                            .kind(CommandOptionType::SubCommand)
+                            .create_sub_option(|sub_option| add_rule_choices(sub_option))
+                            .create_sub_option(|sub_option| {
+                                sub_option
+                                    .name("position")
+                                    .description("移動先の順番（1が最初）")
+                                    .kind(CommandOptionType::Integer)
+                                    .min_int_value(1)
+                                    .max_int_value(Rule::ALL.len())
+                                    .required(true)
+                            })
+                    })
+                    .create_option(|option| {
+                        option
+                            .name("reset")
+                            .description("すべてのルールを既定に戻します。")
+                            .kind(CommandOptionType::SubCommand)
+                    })
+            })
+            .create_application_command(|command| {
+                command
+                    .name("read")
+                    .description("読み上げる文章の長さと省略のしかたを設定します。")
+                    .create_option(|option| {
+                        option
+                            .name("show")
+                            .description("現在の設定を表示します。")
+                            .kind(CommandOptionType::SubCommand)
+                    })
+                    .create_option(|option| {
+                        option
+                            .name("set")
+                            .description("設定を変更します。")
+                            .kind(CommandOptionType::SubCommand)
+                            .create_sub_option(|sub_option| add_read_option_choices(sub_option, true))
+                            .create_sub_option(|sub_option| {
+                                sub_option
+                                    .name("value")
+                                    .description("設定する値（`/read show`で指定できる値を確認できます）")
+                                    .kind(CommandOptionType::String)
+                                    .required(true)
+                            })
+                    })
+                    .create_option(|option| {
+                        option
+                            .name("reset")
+                            .description("設定を既定に戻します。")
+                            .kind(CommandOptionType::SubCommand)
+                            .create_sub_option(|sub_option| add_read_option_choices(sub_option, false))
+                    })
+            })
+            .create_application_command(|command| {
+                command
+                    .name("name")
+                    .description("メッセージの前に読み上げる自分の名前を設定します。")
+                    .create_option(|option| {
+                        option
+                            .name("show")
+                            .description("現在の設定を表示します。")
+                            .kind(CommandOptionType::SubCommand)
+                    })
+                    .create_option(|option| {
+                        option
+                            .name("reading")
+                            .description("名前の読み方を登録します。")
+                            .kind(CommandOptionType::SubCommand)
+                            .create_sub_option(|sub_option| {
+                                sub_option
+                                    .name("value")
+                                    .description("読み方（例: やまだ）")
+                                    .kind(CommandOptionType::String)
+                                    .max_length(MAX_NAME_READING_LENGTH as u16)
+                                    .required(true)
+                            })
+                    })
+                    .create_option(|option| {
+                        option
+                            .name("announce")
+                            .description("自分のメッセージの前に名前を読むかどうかを設定します。")
+                            .kind(CommandOptionType::SubCommand)
+                            .create_sub_option(|sub_option| {
+                                sub_option
+                                    .name("value")
+                                    .description("名前を読むかどうか")
+                                    .kind(CommandOptionType::String)
+                                    .add_string_choice("読む", "on")
+                                    .add_string_choice("読まない", "off")
+                                    .add_string_choice("サーバーの設定に従う", "default")
+                                    .required(true)
+                            })
+                    })
+                    .create_option(|option| {
+                        option
+                            .name("reset")
+                            .description("名前の設定をすべて削除します。")
+                            .kind(CommandOptionType::SubCommand)
+                    })
+            })
+            .create_application_command(|command| {
+                command
+                    .name("sing")
+                    .description("楽譜を歌わせ、ボイスチャンネルで再生します。")
+                    .create_option(|option| {
+                        option
+                            .name("score")
+                            .description("楽譜（例: bpm=120 C4:ど:4 D4:れ:4 E4:み:2 R:4）")
+                            .kind(CommandOptionType::String)
+                    })
+                    .create_option(|option| {
+                        option
+                            .name("file")
+                            .description("楽譜のファイル（テキスト形式または.musicxml）")
+                            .kind(CommandOptionType::Attachment)
+                    })
+                    .create_option(|option| {
+                        option
+                            .name("style")
+                            .description("歌わせるスタイル（省略すると最初の声）")
+                            .kind(CommandOptionType::String)
+                            .set_autocomplete(true)
+                    })
+            })
+            .create_application_command(|command| {
+                command
+                    .name("status")
+                    .description("音声合成エンジンの状態を表示します。")
+            })
             .create_application_command(|command| {
                 command
                     .name("help")
@@ -105,3 +439,80 @@ pub async fn setup_commands(ctx: &Context) -> Result<()> {
 
     Ok(())
 }
+
+fn add_priority_option(
+    option: &mut CreateApplicationCommandOption,
+    required: bool,
+) -> &mut CreateApplicationCommandOption {
+    option
+        .name("priority")
+        .description("優先度（大きいものから先に適用、既定は0）")
+        .kind(CommandOptionType::Integer)
+        .min_int_value(-MAX_DICT_PRIORITY)
+        .max_int_value(MAX_DICT_PRIORITY)
+        .required(required)
+}
+
+fn add_rule_choices(
+    option: &mut CreateApplicationCommandOption,
+) -> &mut CreateApplicationCommandOption {
+    option
+        .name("rule")
+        .description("ルール")
+        .kind(CommandOptionType::String)
+        .required(true);
+    for rule in Rule::ALL {
+        option.add_string_choice(
+            format!("{}（{}）", rule.name(), rule.description()),
+            rule.name(),
+        );
+    }
+    option
+}
+
+fn add_read_option_choices(
+    option: &mut CreateApplicationCommandOption,
+    required: bool,
+) -> &mut CreateApplicationCommandOption {
+    option
+        .name("setting")
+        .description(if required {
+            "変更する項目"
+        } else {
+            "既定に戻す項目（省略するとすべての項目）"
+        })
+        .kind(CommandOptionType::String)
+        .required(required);
+    for read_option in ReadOption::ALL {
+        option.add_string_choice(
+            format!("{}（{}）", read_option.name(), read_option.label()),
+            read_option.name(),
+        );
+    }
+    option
+}
+
+fn add_profile_options(
+    option: &mut CreateApplicationCommandOption,
+) -> &mut CreateApplicationCommandOption {
+    option.create_sub_option(|sub_option| {
+        sub_option
+            .name("style")
+            .description("スタイルID (例: 3, aivis:888753760)")
+            .kind(CommandOptionType::String)
+    });
+
+    for param in VoiceParam::ALL {
+        let (min, max) = param.range();
+        option.create_sub_option(|sub_option| {
+            sub_option
+                .name(param.name())
+                .description(format!("{} ({}〜{})", param.label(), min, max))
+                .kind(CommandOptionType::Number)
+                .min_number_value(min)
+                .max_number_value(max)
+        });
+    }
+
+    option
+}
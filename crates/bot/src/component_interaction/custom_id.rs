@@ -1 +1,14 @@
+/// スタイルを選ぶセレクトメニュー（値は名前空間付きのスタイルID）
 pub const CUSTOM_ID_VOICE: &str = "voice";
+/// 選んだスタイルを試聴するボタン（`voice_preview:<スタイルID>`）
+pub const CUSTOM_ID_VOICE_PREVIEW: &str = "voice_preview:";
+/// 選んだスタイルを自分の声にするボタン（`voice_select:<スタイルID>`）
+pub const CUSTOM_ID_VOICE_SELECT: &str = "voice_select:";
+/// 話者を選ぶセレクトメニュー（値は`<名前空間>:<話者のUUID>`）
+pub const CUSTOM_ID_VOICE_SPEAKER: &str = "voice_speaker";
+/// 話者の選択画面のページを切り替えるボタン（`voice_speakers:<ページ>`）
+pub const CUSTOM_ID_VOICE_SPEAKERS_PAGE: &str = "voice_speakers:";
+/// スタイルの選択画面のページを切り替えるボタン（`voice_styles:<名前空間>:<話者のUUID>:<ページ>`）
+pub const CUSTOM_ID_VOICE_STYLES_PAGE: &str = "voice_styles:";
+/// サーバーの既定の声に戻すボタン
+pub const CUSTOM_ID_VOICE_RESET: &str = "voice_reset";
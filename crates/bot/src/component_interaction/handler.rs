@@ -1,19 +1,50 @@
 use super::custom_id;
+use super::voice_picker::{self, Picker};
 use crate::app_state;
+use crate::command::actions;
 use anyhow::{anyhow, bail, Context as _, Result};
-use bot_db::voice::SetOption;
 use serenity::{
+    builder::CreateComponents,
     client::Context,
-    model::application::interaction::{
-        message_component::MessageComponentInteraction, InteractionResponseType,
+    model::{
+        application::interaction::{
+            message_component::MessageComponentInteraction, InteractionResponseType,
+        },
+        channel::MessageFlags,
     },
 };
 
 pub async fn handle(ctx: &Context, interaction: &MessageComponentInteraction) -> Result<()> {
-    if interaction.data.custom_id == custom_id::CUSTOM_ID_VOICE {
+    let custom_id = interaction.data.custom_id.as_str();
+
+    if custom_id == custom_id::CUSTOM_ID_VOICE {
         handle_voice(ctx, interaction)
             .await
             .context(r#"Failed to handle "voice" message component interaction"#)?;
+    } else if let Some(voice_id) = custom_id.strip_prefix(custom_id::CUSTOM_ID_VOICE_SELECT) {
+        handle_voice_select(ctx, interaction, voice_id)
+            .await
+            .context(r#"Failed to handle "voice_select" message component interaction"#)?;
+    } else if let Some(voice_id) = custom_id.strip_prefix(custom_id::CUSTOM_ID_VOICE_PREVIEW) {
+        handle_voice_preview(ctx, interaction, voice_id)
+            .await
+            .context(r#"Failed to handle "voice_preview" message component interaction"#)?;
+    } else if custom_id == custom_id::CUSTOM_ID_VOICE_SPEAKER {
+        handle_voice_speaker(ctx, interaction)
+            .await
+            .context(r#"Failed to handle "voice_speaker" message component interaction"#)?;
+    } else if let Some(page) = custom_id.strip_prefix(custom_id::CUSTOM_ID_VOICE_SPEAKERS_PAGE) {
+        handle_voice_speakers_page(ctx, interaction, page)
+            .await
+            .context(r#"Failed to handle "voice_speakers" message component interaction"#)?;
+    } else if let Some(rest) = custom_id.strip_prefix(custom_id::CUSTOM_ID_VOICE_STYLES_PAGE) {
+        handle_voice_styles_page(ctx, interaction, rest)
+            .await
+            .context(r#"Failed to handle "voice_styles" message component interaction"#)?;
+    } else if custom_id == custom_id::CUSTOM_ID_VOICE_RESET {
+        handle_voice_reset(ctx, interaction)
+            .await
+            .context(r#"Failed to handle "voice_reset" message component interaction"#)?;
     } else {
         bail!(
             "Unknown message component interaction custom_id: {}",
@@ -29,58 +60,226 @@ async fn handle_voice(ctx: &Context, interaction: &MessageComponentInteraction)
         .guild_id
         .ok_or_else(|| anyhow!("Failed to get guild ID"))?;
 
-    let selected_preset_id = interaction
+    let selected_voice_id = interaction
         .data
         .values
-        .get(0)
-        .ok_or_else(|| anyhow!("Value not available in message component interaction"))?
-        .parse::<i64>()?;
+        .first()
+        .ok_or_else(|| anyhow!("Value not available in message component interaction"))?;
 
     let state = app_state::get(ctx).await?;
+    let picker =
+        voice_picker::style_selected(&state, guild_id, interaction.user.id, selected_voice_id)
+            .await?;
 
-    let available_presets = state.voicevox_client.presets().await?;
-    let selected_preset = available_presets
-        .into_iter()
-        .find(|p| p.id == selected_preset_id)
-        .ok_or_else(|| anyhow!("Preset {} not available", selected_preset_id))?;
-
-    let mut conn = state.redis_client.get_async_connection().await?;
-    bot_db::voice::set(
-        &mut conn,
-        SetOption {
-            guild_id: guild_id.into(),
-            user_id: interaction.user.id.into(),
-            value: selected_preset_id,
-        },
+    respond_picker(ctx, interaction, picker).await
+}
+
+async fn handle_voice_select(
+    ctx: &Context,
+    interaction: &MessageComponentInteraction,
+    voice_id: &str,
+) -> Result<()> {
+    let guild_id = interaction
+        .guild_id
+        .ok_or_else(|| anyhow!("Failed to get guild ID"))?;
+
+    let state = app_state::get(ctx).await?;
+    let name = voice_picker::select(&state, guild_id, interaction.user.id, voice_id).await?;
+
+    r(
+        ctx,
+        interaction,
+        format!(
+            "<@{}>さんの声を`{}`に変更しました。",
+            interaction.user.id, name
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+async fn handle_voice_preview(
+    ctx: &Context,
+    interaction: &MessageComponentInteraction,
+    voice_id: &str,
+) -> Result<()> {
+    let guild_id = interaction
+        .guild_id
+        .ok_or_else(|| anyhow!("Failed to get guild ID"))?;
+
+    // 合成に時間がかかることがあるため、先に応答しておく
+    interaction
+        .defer_ephemeral(&ctx.http)
+        .await
+        .context("Failed to defer interaction response")?;
+
+    let state = app_state::get(ctx).await?;
+    let outcome =
+        actions::preview_voice(ctx, &state, guild_id, interaction.user.id, voice_id).await;
+    interaction
+        .create_followup_message(&ctx.http, |message| {
+            actions::build_preview_followup(message, outcome)
+        })
+        .await
+        .context("Failed to create followup message")?;
+
+    Ok(())
+}
+
+async fn handle_voice_speaker(
+    ctx: &Context,
+    interaction: &MessageComponentInteraction,
+) -> Result<()> {
+    let guild_id = interaction
+        .guild_id
+        .ok_or_else(|| anyhow!("Failed to get guild ID"))?;
+
+    let speaker_key = interaction
+        .data
+        .values
+        .first()
+        .ok_or_else(|| anyhow!("Value not available in message component interaction"))?;
+
+    let state = app_state::get(ctx).await?;
+    let picker =
+        voice_picker::style_picker(&state, guild_id, interaction.user.id, speaker_key, 0, None)
+            .await?;
+
+    respond_picker(ctx, interaction, picker).await
+}
+
+async fn handle_voice_speakers_page(
+    ctx: &Context,
+    interaction: &MessageComponentInteraction,
+    page: &str,
+) -> Result<()> {
+    let guild_id = interaction
+        .guild_id
+        .ok_or_else(|| anyhow!("Failed to get guild ID"))?;
+    let page = page.parse::<usize>()?;
+
+    let state = app_state::get(ctx).await?;
+    let picker = voice_picker::speaker_picker(&state, guild_id, interaction.user.id, page).await?;
+
+    respond_picker(ctx, interaction, picker).await
+}
+
+async fn handle_voice_styles_page(
+    ctx: &Context,
+    interaction: &MessageComponentInteraction,
+    rest: &str,
+) -> Result<()> {
+    let guild_id = interaction
+        .guild_id
+        .ok_or_else(|| anyhow!("Failed to get guild ID"))?;
+    let (speaker_key, page) = voice_picker::parse_styles_page_id(rest)
+        .ok_or_else(|| anyhow!("Invalid custom_id: {}", interaction.data.custom_id))?;
+
+    let state = app_state::get(ctx).await?;
+    let picker = voice_picker::style_picker(
+        &state,
+        guild_id,
+        interaction.user.id,
+        speaker_key,
+        page,
+        None,
     )
     .await?;
 
+    respond_picker(ctx, interaction, picker).await
+}
+
+async fn handle_voice_reset(
+    ctx: &Context,
+    interaction: &MessageComponentInteraction,
+) -> Result<()> {
+    let guild_id = interaction
+        .guild_id
+        .ok_or_else(|| anyhow!("Failed to get guild ID"))?;
+
+    let state = app_state::get(ctx).await?;
+    voice_picker::reset(&state, guild_id, interaction.user.id).await?;
+
     r(
         ctx,
         interaction,
         format!(
-            "<@{}>さんの声を`{}`に変更しました。",
-            interaction.user.id, selected_preset.name
+            "<@{}>さんの声をサーバーの既定に戻しました。",
+            interaction.user.id
         ),
     )
     .await?;
     Ok(())
 }
 
-// Helper function to create text message response
-async fn r(
+/// 操作したユーザーだけに表示されているメッセージ（`/voice`の応答）かどうか
+/// `!voice`で送った全員向けのメッセージは書き換えず、操作したユーザーに別の画面を表示する
+fn is_ephemeral(interaction: &MessageComponentInteraction) -> bool {
+    interaction
+        .message
+        .flags
+        .is_some_and(|flags| flags.contains(MessageFlags::EPHEMERAL))
+}
+
+async fn respond_picker(
     ctx: &Context,
     interaction: &MessageComponentInteraction,
-    text: impl ToString,
+    picker: Picker,
 ) -> Result<()> {
+    let kind = if is_ephemeral(interaction) {
+        InteractionResponseType::UpdateMessage
+    } else {
+        InteractionResponseType::ChannelMessageWithSource
+    };
+
     interaction
         .create_interaction_response(&ctx.http, |create_response| {
             create_response
-                .kind(InteractionResponseType::ChannelMessageWithSource)
-                .interaction_response_data(|create_message| create_message.content(text))
+                .kind(kind)
+                .interaction_response_data(|create_message| {
+                    create_message
+                        .ephemeral(true)
+                        .set_embed(picker.embed)
+                        .set_components(picker.components)
+                })
         })
         .await
         .context("Failed to create interaction response")?;
 
     Ok(())
 }
+
+// Helper function to create text message response
+async fn r(
+    ctx: &Context,
+    interaction: &MessageComponentInteraction,
+    text: impl ToString,
+) -> Result<()> {
+    if is_ephemeral(interaction) {
+        // 選択画面を結果の表示に置き換える
+        interaction
+            .create_interaction_response(&ctx.http, |create_response| {
+                create_response
+                    .kind(InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|create_message| {
+                        create_message
+                            .content(text)
+                            .set_embeds(Vec::new())
+                            .set_components(CreateComponents::default())
+                    })
+            })
+            .await
+            .context("Failed to create interaction response")?;
+    } else {
+        interaction
+            .create_interaction_response(&ctx.http, |create_response| {
+                create_response
+                    .kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|create_message| create_message.content(text))
+            })
+            .await
+            .context("Failed to create interaction response")?;
+    }
+
+    Ok(())
+}
@@ -1,2 +1,3 @@
 pub mod custom_id;
 pub mod handler;
+pub mod voice_picker;
@@ -0,0 +1,347 @@
+use super::custom_id::{
+    CUSTOM_ID_VOICE, CUSTOM_ID_VOICE_PREVIEW, CUSTOM_ID_VOICE_RESET, CUSTOM_ID_VOICE_SELECT,
+    CUSTOM_ID_VOICE_SPEAKER, CUSTOM_ID_VOICE_SPEAKERS_PAGE, CUSTOM_ID_VOICE_STYLES_PAGE,
+};
+use crate::app_state::AppState;
+use crate::voice;
+use anyhow::{anyhow, Result};
+use bot_db::voice_profile::{self, VoiceProfile};
+use bot_speech::engine::{Voice, VoiceId};
+use serenity::builder::{CreateComponents, CreateEmbed, CreateSelectMenuOption};
+use serenity::model::application::component::ButtonStyle;
+use serenity::model::id::{GuildId, UserId};
+
+/// セレクトメニューに表示できる選択肢の上限（Discordの制限）
+const OPTIONS_PER_PAGE: usize = 25;
+
+/// 声の選択画面（埋め込みとコンポーネント）
+pub struct Picker {
+    pub embed: CreateEmbed,
+    pub components: CreateComponents,
+}
+
+/// 話者と、その話者のスタイル
+struct Speaker {
+    /// `<名前空間>:<話者のUUID>`
+    key: String,
+    name: String,
+    styles: Vec<Voice>,
+}
+
+/// エンジンの声の一覧を話者ごとにまとめる（順序はエンジンの一覧に従う）
+fn group_speakers(voices: Vec<Voice>) -> Vec<Speaker> {
+    let mut speakers: Vec<Speaker> = Vec::new();
+    for voice in voices {
+        let key = format!("{}:{}", voice.id.namespace, voice.speaker_uuid);
+        match speakers.iter_mut().find(|sp| sp.key == key) {
+            Some(speaker) => speaker.styles.push(voice),
+            None => speakers.push(Speaker {
+                key,
+                name: voice.speaker_name.clone(),
+                styles: vec![voice],
+            }),
+        }
+    }
+    speakers
+}
+
+/// 話者を選ぶ画面
+pub async fn speaker_picker(
+    state: &AppState,
+    guild_id: GuildId,
+    user_id: UserId,
+    page: usize,
+) -> Result<Picker> {
+    let voices = state.speech_engine.voices().await?;
+    let current = describe_current(state, guild_id, user_id, &voices).await?;
+    let speakers = group_speakers(voices);
+
+    let (page, last_page) = clamp_page(page, speakers.len());
+    let options = speakers
+        .iter()
+        .skip(page * OPTIONS_PER_PAGE)
+        .take(OPTIONS_PER_PAGE)
+        .map(|speaker| {
+            let mut option = CreateSelectMenuOption::new(truncate(&speaker.name), &speaker.key);
+            option.description(truncate(&format!("{}スタイル", speaker.styles.len())));
+            option
+        })
+        .collect::<Vec<_>>();
+
+    let mut embed = CreateEmbed::default();
+    embed
+        .title("声の選択")
+        .description(format!("{}\n\n話者を選んでください。", current))
+        .footer(|footer| footer.text(format!("{} / {} ページ", page + 1, last_page + 1)));
+
+    let mut components = CreateComponents::default();
+    if !options.is_empty() {
+        components.create_action_row(|row| {
+            row.create_select_menu(|menu| {
+                menu.custom_id(CUSTOM_ID_VOICE_SPEAKER)
+                    .placeholder("話者を選択")
+                    .options(|o| o.set_options(options))
+            })
+        });
+    }
+    components.create_action_row(|row| {
+        if last_page > 0 {
+            row.create_button(|button| {
+                button
+                    .custom_id(format!(
+                        "{}{}",
+                        CUSTOM_ID_VOICE_SPEAKERS_PAGE,
+                        page.max(1) - 1
+                    ))
+                    .label("前へ")
+                    .style(ButtonStyle::Secondary)
+                    .disabled(page == 0)
+            });
+            row.create_button(|button| {
+                button
+                    .custom_id(format!("{}{}", CUSTOM_ID_VOICE_SPEAKERS_PAGE, page + 1))
+                    .label("次へ")
+                    .style(ButtonStyle::Secondary)
+                    .disabled(page == last_page)
+            });
+        }
+        row.create_button(|button| {
+            button
+                .custom_id(CUSTOM_ID_VOICE_RESET)
+                .label("サーバーの既定に戻す")
+                .style(ButtonStyle::Danger)
+        })
+    });
+
+    Ok(Picker { embed, components })
+}
+
+/// 話者のスタイルを選ぶ画面
+/// `selected`を指定すると、そのスタイルを選択した状態で試聴と決定のボタンを表示する
+pub async fn style_picker(
+    state: &AppState,
+    guild_id: GuildId,
+    user_id: UserId,
+    speaker_key: &str,
+    page: usize,
+    selected: Option<&VoiceId>,
+) -> Result<Picker> {
+    let voices = state.speech_engine.voices().await?;
+    let current = describe_current(state, guild_id, user_id, &voices).await?;
+    let speaker = group_speakers(voices)
+        .into_iter()
+        .find(|sp| sp.key == speaker_key)
+        .ok_or_else(|| anyhow!("Speaker {} is not available", speaker_key))?;
+
+    let (page, last_page) = clamp_page(page, speaker.styles.len());
+    let options = speaker
+        .styles
+        .iter()
+        .skip(page * OPTIONS_PER_PAGE)
+        .take(OPTIONS_PER_PAGE)
+        .map(|voice| {
+            let mut option =
+                CreateSelectMenuOption::new(truncate(&voice.style_name), voice.id.to_string());
+            option
+                .description(format!("スタイルID: {}", voice.id))
+                .default_selection(Some(&voice.id) == selected);
+            option
+        })
+        .collect::<Vec<_>>();
+
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(format!("声の選択: {}", speaker.name))
+        .description(format!("{}\n\nスタイルを選んでください。", current))
+        .footer(|footer| footer.text(format!("{} / {} ページ", page + 1, last_page + 1)));
+
+    let mut components = CreateComponents::default();
+    components.create_action_row(|row| {
+        row.create_select_menu(|menu| {
+            menu.custom_id(CUSTOM_ID_VOICE)
+                .placeholder("スタイルを選択")
+                .options(|o| o.set_options(options))
+        })
+    });
+    if let Some(voice_id) = selected.filter(|id| speaker.styles.iter().any(|v| &v.id == *id)) {
+        components.create_action_row(|row| {
+            row.create_button(|button| {
+                button
+                    .custom_id(format!("{}{}", CUSTOM_ID_VOICE_PREVIEW, voice_id))
+                    .label("試聴")
+                    .style(ButtonStyle::Secondary)
+            });
+            row.create_button(|button| {
+                button
+                    .custom_id(format!("{}{}", CUSTOM_ID_VOICE_SELECT, voice_id))
+                    .label("この声にする")
+                    .style(ButtonStyle::Primary)
+            })
+        });
+    }
+    components.create_action_row(|row| {
+        if last_page > 0 {
+            row.create_button(|button| {
+                button
+                    .custom_id(styles_page_id(&speaker.key, page.max(1) - 1))
+                    .label("前へ")
+                    .style(ButtonStyle::Secondary)
+                    .disabled(page == 0)
+            });
+            row.create_button(|button| {
+                button
+                    .custom_id(styles_page_id(&speaker.key, page + 1))
+                    .label("次へ")
+                    .style(ButtonStyle::Secondary)
+                    .disabled(page == last_page)
+            });
+        }
+        row.create_button(|button| {
+            button
+                .custom_id(format!("{}0", CUSTOM_ID_VOICE_SPEAKERS_PAGE))
+                .label("話者の選択に戻る")
+                .style(ButtonStyle::Secondary)
+        })
+    });
+
+    Ok(Picker { embed, components })
+}
+
+/// `voice_id`を選択した状態のスタイルの選択画面
+pub async fn style_selected(
+    state: &AppState,
+    guild_id: GuildId,
+    user_id: UserId,
+    voice_id: &str,
+) -> Result<Picker> {
+    let voice_id = voice_id.parse::<VoiceId>()?;
+    let speakers = group_speakers(state.speech_engine.voices().await?);
+    let (speaker, index) = speakers
+        .iter()
+        .find_map(|sp| {
+            let index = sp.styles.iter().position(|v| v.id == voice_id)?;
+            Some((sp, index))
+        })
+        .ok_or_else(|| anyhow!("Voice {} is not available", voice_id))?;
+
+    style_picker(
+        state,
+        guild_id,
+        user_id,
+        &speaker.key,
+        index / OPTIONS_PER_PAGE,
+        Some(&voice_id),
+    )
+    .await
+}
+
+/// スタイルの選択画面のページを切り替えるボタンのcustom_id
+fn styles_page_id(speaker_key: &str, page: usize) -> String {
+    format!("{}{}:{}", CUSTOM_ID_VOICE_STYLES_PAGE, speaker_key, page)
+}
+
+/// [`styles_page_id`]から話者とページを取り出す
+pub fn parse_styles_page_id(rest: &str) -> Option<(&str, usize)> {
+    let (speaker_key, page) = rest.rsplit_once(':')?;
+    Some((speaker_key, page.parse().ok()?))
+}
+
+/// ユーザーの声を`voice_id`に変更し、変更後の声の名前を返す
+pub async fn select(
+    state: &AppState,
+    guild_id: GuildId,
+    user_id: UserId,
+    voice_id: &str,
+) -> Result<String> {
+    let voice_id = voice_id.parse::<VoiceId>()?;
+    let voices = state.speech_engine.voices().await?;
+    let voice = voices
+        .iter()
+        .find(|v| v.id == voice_id)
+        .ok_or_else(|| anyhow!("Voice {} is not available", voice_id))?;
+
+    let mut conn = state.redis_client.get_async_connection().await?;
+    // 元のスタイルが変わるとモーフィングできるとは限らないため、モーフィングの指定は解除する
+    voice_profile::reset_morph(
+        &mut conn,
+        voice_profile::ResetOption {
+            scope: voice::user_scope(guild_id, user_id),
+        },
+    )
+    .await?;
+    voice_profile::set(
+        &mut conn,
+        voice_profile::SetOption {
+            scope: voice::user_scope(guild_id, user_id),
+            profile: VoiceProfile {
+                voice_id: Some(voice_id.to_string()),
+                ..Default::default()
+            },
+        },
+    )
+    .await?;
+
+    Ok(voice::voice_name(voice))
+}
+
+/// ユーザーの声の指定を削除し、サーバーの既定の声に戻す
+/// 話速などの他の設定は残す
+pub async fn reset(state: &AppState, guild_id: GuildId, user_id: UserId) -> Result<()> {
+    let mut conn = state.redis_client.get_async_connection().await?;
+    voice_profile::reset_voice(
+        &mut conn,
+        voice_profile::ResetOption {
+            scope: voice::user_scope(guild_id, user_id),
+        },
+    )
+    .await
+}
+
+/// 現在の声を表示用の文字列にする
+async fn describe_current(
+    state: &AppState,
+    guild_id: GuildId,
+    user_id: UserId,
+    voices: &[Voice],
+) -> Result<String> {
+    let mut conn = state.redis_client.get_async_connection().await?;
+    let own = voice_profile::get(
+        &mut conn,
+        voice_profile::GetOption {
+            scope: voice::user_scope(guild_id, user_id),
+        },
+    )
+    .await?;
+    let guild = voice_profile::get(
+        &mut conn,
+        voice_profile::GetOption {
+            scope: voice::guild_scope(guild_id),
+        },
+    )
+    .await?;
+
+    let name = |voice_id: &str| {
+        voice_id
+            .parse::<VoiceId>()
+            .ok()
+            .and_then(|id| voices.iter().find(|v| v.id == id))
+            .map_or_else(|| format!("不明な声 ({})", voice_id), voice::voice_name)
+    };
+
+    Ok(match (&own.voice_id, &guild.voice_id) {
+        (Some(id), _) => format!("現在の声: {}", name(id)),
+        (None, Some(id)) => format!("現在の声: {}（サーバーの既定）", name(id)),
+        (None, None) => "現在の声: 未設定（エンジンの最初のスタイル）".to_string(),
+    })
+}
+
+/// ページ番号を範囲内に収め、最後のページの番号と合わせて返す
+fn clamp_page(page: usize, items: usize) -> (usize, usize) {
+    let last_page = items.saturating_sub(1) / OPTIONS_PER_PAGE;
+    (page.min(last_page), last_page)
+}
+
+/// 選択肢のラベルと説明は100文字まで
+fn truncate(text: &str) -> String {
+    text.chars().take(100).collect()
+}
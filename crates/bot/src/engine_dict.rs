@@ -0,0 +1,169 @@
+use crate::error::report_error;
+use anyhow::{bail, Result};
+use bot_db::{dict, engine_dict, redis};
+use bot_speech::cache::AudioCache;
+use bot_speech::engine::{SpeechEngine, UserDictSync, UserDictWord, WordType};
+use bot_speech::kana::to_katakana;
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// 同期が重なってエンジンに同じ単語が二重に登録されないようにする
+static SYNC_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// コマンドで指定する品詞の名前
+pub fn word_type_name(word_type: WordType) -> &'static str {
+    match word_type {
+        WordType::ProperNoun => "proper_noun",
+        WordType::CommonNoun => "common_noun",
+        WordType::Verb => "verb",
+        WordType::Adjective => "adjective",
+        WordType::Suffix => "suffix",
+    }
+}
+
+pub fn word_type_label(word_type: WordType) -> &'static str {
+    match word_type {
+        WordType::ProperNoun => "固有名詞",
+        WordType::CommonNoun => "普通名詞",
+        WordType::Verb => "動詞",
+        WordType::Adjective => "形容詞",
+        WordType::Suffix => "語尾",
+    }
+}
+
+/// 名前（`proper_noun`）と表示名（`固有名詞`）のどちらでも指定できる
+pub fn parse_word_type(text: &str) -> Option<WordType> {
+    WordType::ALL
+        .into_iter()
+        .find(|&x| word_type_name(x) == text || word_type_label(x) == text)
+}
+
+/// 読みをエンジンに登録できるカタカナにする
+/// ひらがなはカタカナに変換し、それ以外の文字が含まれる場合はエラーとする
+pub fn to_pronunciation(read_as: &str) -> Result<String> {
+    let pronunciation = to_katakana(read_as);
+
+    if pronunciation.is_empty()
+        || !pronunciation
+            .chars()
+            .all(|c| matches!(c, 'ァ'..='ヴ' | 'ー'))
+    {
+        bail!("アクセントを指定する場合、読みはひらがなかカタカナで入力してください。");
+    }
+    Ok(pronunciation)
+}
+
+/// カタカナの読みのモーラ数を返す（拗音の小書き文字は前の文字と合わせて1モーラ）
+pub fn mora_count(pronunciation: &str) -> usize {
+    pronunciation
+        .chars()
+        .filter(|c| {
+            !matches!(
+                c,
+                'ァ' | 'ィ' | 'ゥ' | 'ェ' | 'ォ' | 'ャ' | 'ュ' | 'ョ' | 'ヮ'
+            )
+        })
+        .count()
+}
+
+/// アクセント核の位置が読みに対して正しいか確かめる
+pub fn validate_accent(pronunciation: &str, accent_type: u32) -> Result<()> {
+    let moras = mora_count(pronunciation);
+    if accent_type as usize > moras {
+        bail!(
+            "アクセントは0（平板型）から{}（読みのモーラ数）の範囲で指定してください。",
+            moras
+        );
+    }
+    Ok(())
+}
+
+/// データベースに記録された単語をエンジンのユーザー辞書に反映する
+/// 反映した内容は`cache`にも記録し、古い辞書で合成した音声を使わないようにする
+pub async fn sync(
+    redis_client: &redis::Client,
+    engine: &dyn SpeechEngine,
+    cache: &AudioCache,
+) -> Result<()> {
+    let _lock = SYNC_LOCK.lock().await;
+    let mut conn = redis_client.get_async_connection().await?;
+
+    let mut words = Vec::new();
+    for owned in engine_dict::get_all(&mut conn).await? {
+        let read_as = dict::get(
+            &mut conn,
+            dict::GetOption {
+                guild_id: owned.guild_id,
+                word: owned.word.clone(),
+            },
+        )
+        .await?;
+        let Some(read_as) = read_as else {
+            continue;
+        };
+        let (Ok(pronunciation), Some(word_type)) = (
+            to_pronunciation(&read_as),
+            WordType::from_api_name(&owned.accent.word_type),
+        ) else {
+            warn!("Skipped invalid engine dictionary entry: {}", owned.word);
+            continue;
+        };
+
+        words.push(UserDictWord {
+            surface: owned.word,
+            pronunciation,
+            accent_type: owned.accent.accent_type,
+            word_type,
+        });
+    }
+
+    let dict = UserDictSync {
+        words,
+        managed_surfaces: engine_dict::managed_surfaces(&mut conn).await?,
+    };
+    let result = engine.sync_user_dict(&dict).await;
+    // 一部のエンジンへの反映に失敗した場合も、反映できたエンジンの古い音声は使わない
+    cache.set_user_dict(&dict);
+    result?;
+    debug!(
+        "Synced {} words to engine user dictionary",
+        dict.words.len()
+    );
+
+    Ok(())
+}
+
+/// コマンドへの応答を待たせないよう、同期を別のタスクで行う
+pub fn spawn_sync(
+    redis_client: redis::Client,
+    engine: Arc<dyn SpeechEngine>,
+    cache: Arc<AudioCache>,
+) {
+    tokio::spawn(async move {
+        if let Err(err) = sync(&redis_client, engine.as_ref(), &cache).await {
+            report_error(err.context("Failed to sync engine user dictionary"));
+        }
+    });
+}
+
+/// `period`ごとに同期する
+/// 停止していたエンドポイントが復帰したときや、エンジンが再起動して辞書が失われたときのため
+pub fn spawn_periodic_sync(
+    redis_client: redis::Client,
+    engine: Arc<dyn SpeechEngine>,
+    cache: Arc<AudioCache>,
+    period: Duration,
+) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(period);
+        loop {
+            ticker.tick().await;
+            if let Err(err) = sync(&redis_client, engine.as_ref(), &cache).await {
+                warn!("Failed to sync engine user dictionary: {:?}", err);
+            }
+        }
+    });
+}
@@ -1,25 +1,41 @@
 use crate::error::report_error;
 use anyhow::{Context, Result};
+use bot_config::EngineConfig;
 use bot_db::redis;
-use bot_speech::{speech::initialize_speakers, voicevox::VoicevoxClient};
+use bot_speech::cache::AudioCache;
+use bot_speech::endpoint::{EndpointOptions, LoadBalancing};
+use bot_speech::engine::{
+    CompatibleEngine, EngineRegistry, SpeechEngine, StubEngine, VoicevoxEngine,
+};
+use bot_speech::speech::initialize_speakers;
+use bot_speech::voicevox::{RetryPolicy, VoicevoxClient};
 use dashmap::DashMap;
 use log::info;
 use sentry::integrations::anyhow::capture_anyhow;
-use serenity::{model::gateway::GatewayIntents, Client};
+use serenity::{
+    model::{gateway::GatewayIntents, id::GuildId},
+    Client,
+};
 use songbird::SerenityInit;
+use std::sync::Arc;
 use tokio::time::Duration;
 
 mod app_state;
 mod autojoin;
 mod command;
 mod component_interaction;
+mod engine_dict;
 mod error;
 mod event_handler;
 mod message;
 mod regex;
 mod time_signal;
+mod voice;
 mod voice_state;
 
+/// エンジンのユーザー辞書をデータベースの内容に合わせ直す間隔
+const USER_DICT_SYNC_INTERVAL: Duration = Duration::from_secs(600);
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let _guard = sentry::init(());
@@ -49,14 +65,96 @@ async fn run() -> Result<()> {
         .await
         .context("Failed to build serenity client")?;
 
+    let catalog_ttl = Duration::from_secs(config.voicevox.catalog_ttl_secs);
+    let load_balancing = match config.voicevox.load_balancing {
+        bot_config::LoadBalancing::RoundRobin => LoadBalancing::RoundRobin,
+        bot_config::LoadBalancing::LeastBusy => LoadBalancing::LeastBusy,
+    };
+    let endpoints = config
+        .voicevox
+        .endpoint_list()
+        .into_iter()
+        .map(|(api_base, timeout_secs)| {
+            EndpointOptions::new(api_base).with_timeout(Duration::from_secs(timeout_secs))
+        })
+        .collect();
+    let retry = RetryPolicy {
+        max_retries: config.voicevox.max_retries,
+        ..Default::default()
+    };
+    let voicevox_client = VoicevoxClient::new(endpoints)
+        .with_catalog_ttl(catalog_ttl)
+        .with_load_balancing(load_balancing)
+        .with_retry_policy(retry);
+    // 期限切れでメッセージの読み上げが待たされないよう、期限の半分の間隔で更新しておく
+    let refresh_period = (catalog_ttl / 2).max(Duration::from_secs(1));
+    voicevox_client.spawn_catalog_refresh(refresh_period);
+    voicevox_client.spawn_health_check(Duration::from_secs(
+        config.voicevox.health_check_interval_secs.max(1),
+    ));
+
+    let mut speech_engine =
+        EngineRegistry::new(Arc::new(VoicevoxEngine::new(voicevox_client.clone())));
+    for engine in config.engines {
+        match engine {
+            EngineConfig::Compatible {
+                namespace,
+                api_base,
+            } => {
+                let endpoint = EndpointOptions::new(api_base)
+                    .with_timeout(Duration::from_secs(config.voicevox.timeout_secs));
+                let client = VoicevoxClient::new(vec![endpoint])
+                    .with_catalog_ttl(catalog_ttl)
+                    .with_retry_policy(retry);
+                client.spawn_catalog_refresh(refresh_period);
+                speech_engine.register(Arc::new(CompatibleEngine::new(namespace, client)))?;
+            }
+            EngineConfig::Stub { namespace } => {
+                speech_engine.register(Arc::new(StubEngine::new(namespace)))?;
+            }
+        }
+    }
+
+    const MB: u64 = 1024 * 1024;
+    let mut audio_cache = AudioCache::new((config.audio_cache.max_mb * MB) as usize);
+    if let Some(dir) = &config.audio_cache.dir {
+        audio_cache = audio_cache.with_disk(dir, config.audio_cache.disk_max_mb * MB)?;
+    }
+
+    if let Some(path) = &config.english_dict_path {
+        message::english::set_external_dictionary(path);
+    }
+
+    let redis_client = redis::Client::open(config.redis.url)?;
+    let speech_engine: Arc<dyn SpeechEngine> = Arc::new(speech_engine);
+    let audio_cache = Arc::new(audio_cache);
+    if config.voicevox.user_dict_sync {
+        engine_dict::spawn_periodic_sync(
+            redis_client.clone(),
+            speech_engine.clone(),
+            audio_cache.clone(),
+            USER_DICT_SYNC_INTERVAL,
+        );
+    }
+
     app_state::initialize(
         &client,
         app_state::AppState {
-            redis_client: redis::Client::open(config.redis.url)?,
-            voicevox_client: VoicevoxClient::new(config.voicevox.api_base),
+            redis_client,
+            speech_engine,
+            audio_cache,
+            user_dict_sync: config.voicevox.user_dict_sync,
+            user_dict_guilds: config
+                .voicevox
+                .user_dict_guilds
+                .iter()
+                .map(|&id| GuildId(id))
+                .collect(),
+            intents,
             connected_guild_states: DashMap::new(),
             preferred_style_id: tokio::sync::RwLock::new(None),
             time_signal_settings: DashMap::new(),
+            speech_error_notified_at: DashMap::new(),
         },
     )
     .await;
@@ -71,7 +169,7 @@ async fn run() -> Result<()> {
             let data = d.read().await;
             let state = data.get::<app_state::AppState>().unwrap();
 
-            if let Err(err) = initialize_speakers(&state.voicevox_client).await {
+            if let Err(err) = initialize_speakers(state.speech_engine.as_ref()).await {
                 report_error(err);
             }
         });
@@ -0,0 +1,98 @@
+//! コードブロックとインラインコードの読み方
+
+use discord_md::ast::{
+    BlockQuote, Bold, ItalicsStar, ItalicsUnderscore, MarkdownDocument, MarkdownElement,
+    MarkdownElementCollection, Plain, Spoiler, Strikethrough, Underline,
+};
+
+/// コードを省略したときに読む文字列
+const OMITTED: &str = "コード省略";
+
+/// コードの読み方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CodeMode {
+    /// コードを読まない
+    Skip,
+    /// 「コード省略」と言語名を読む
+    #[default]
+    Replace,
+    /// コードの最初の行だけを読む
+    FirstLine,
+}
+
+impl CodeMode {
+    pub const ALL: [CodeMode; 3] = [CodeMode::Skip, CodeMode::Replace, CodeMode::FirstLine];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CodeMode::Skip => "skip",
+            CodeMode::Replace => "replace",
+            CodeMode::FirstLine => "first_line",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|x| x.name() == name)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CodeMode::Skip => "読まない",
+            CodeMode::Replace => "「コード省略」と言語名を読む",
+            CodeMode::FirstLine => "最初の行だけ読む",
+        }
+    }
+}
+
+/// 文書中のコードブロックとインラインコードを、読み方に従って普通の文字列に置き換える
+pub fn replace_code(document: &MarkdownDocument, mode: CodeMode) -> MarkdownDocument {
+    MarkdownDocument::new(replace_in_collection(document.content(), mode))
+}
+
+fn replace_in_collection(
+    collection: &MarkdownElementCollection,
+    mode: CodeMode,
+) -> MarkdownElementCollection {
+    MarkdownElementCollection::new(
+        collection
+            .get()
+            .iter()
+            .map(|element| replace_in_element(element, mode))
+            .collect(),
+    )
+}
+
+fn replace_in_element(element: &MarkdownElement, mode: CodeMode) -> MarkdownElement {
+    let inner = |content| replace_in_collection(content, mode);
+    match element {
+        MarkdownElement::Plain(x) => Plain::new(x.content()).into(),
+        MarkdownElement::ItalicsStar(x) => ItalicsStar::new(inner(x.content())).into(),
+        MarkdownElement::ItalicsUnderscore(x) => ItalicsUnderscore::new(inner(x.content())).into(),
+        MarkdownElement::Bold(x) => Bold::new(inner(x.content())).into(),
+        MarkdownElement::Underline(x) => Underline::new(inner(x.content())).into(),
+        MarkdownElement::Strikethrough(x) => Strikethrough::new(inner(x.content())).into(),
+        MarkdownElement::Spoiler(x) => Spoiler::new(inner(x.content())).into(),
+        MarkdownElement::BlockQuote(x) => BlockQuote::new(inner(x.content())).into(),
+        MarkdownElement::OneLineCode(x) => Plain::new(read_code(x.content(), None, mode)).into(),
+        MarkdownElement::MultiLineCode(x) => {
+            Plain::new(read_code(x.content(), x.language(), mode)).into()
+        }
+    }
+}
+
+/// コード1つの読み方を返す
+fn read_code(content: &str, language: Option<&str>, mode: CodeMode) -> String {
+    match mode {
+        CodeMode::Skip => String::new(),
+        CodeMode::Replace => match language {
+            Some(language) => format!("{} {}", OMITTED, language),
+            None => OMITTED.to_string(),
+        },
+        CodeMode::FirstLine => content
+            .lines()
+            .map(str::trim)
+            .find(|x| !x.is_empty())
+            .unwrap_or("")
+            .to_string(),
+    }
+}
@@ -0,0 +1,219 @@
+//! 添付ファイル・スタンプ・埋め込みなど、本文以外の内容を短い説明にする
+
+use crate::app_state;
+use log::warn;
+use serde_json::Value;
+use serenity::client::Context;
+use serenity::http::request::RequestBuilder;
+use serenity::http::routing::RouteInfo;
+use serenity::model::channel::{Attachment, Message, MessageFlags, MessageType};
+use serenity::model::gateway::GatewayIntents;
+
+/// Discordのボイスメッセージの添付ファイル名
+const VOICE_MESSAGE_FILENAME: &str = "voice-message.ogg";
+/// ファイル名や埋め込みのタイトルを読む最大の文字数
+const MAX_NAME_LENGTH: usize = 30;
+
+/// 説明を読む内容の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+    Image,
+    Video,
+    File,
+    Sticker,
+    Embed,
+    VoiceMessage,
+    Poll,
+}
+
+impl Category {
+    pub const ALL: [Category; 7] = [
+        Category::Image,
+        Category::Video,
+        Category::File,
+        Category::Sticker,
+        Category::Embed,
+        Category::VoiceMessage,
+        Category::Poll,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Category::Image => "image",
+            Category::Video => "video",
+            Category::File => "file",
+            Category::Sticker => "sticker",
+            Category::Embed => "embed",
+            Category::VoiceMessage => "voice_message",
+            Category::Poll => "poll",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|x| x.name() == name)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Category::Image => "画像",
+            Category::Video => "動画",
+            Category::File => "ファイル",
+            Category::Sticker => "スタンプ",
+            Category::Embed => "リンクの埋め込み",
+            Category::VoiceMessage => "ボイスメッセージ",
+            Category::Poll => "投票",
+        }
+    }
+}
+
+/// メッセージの本文以外の内容を説明する文を返す
+/// `categories`に含まれない種類は説明しない
+pub async fn describe_message(
+    ctx: &Context,
+    msg: &Message,
+    categories: &[Category],
+) -> Vec<String> {
+    let enabled = |category| categories.contains(&category);
+    let mut descriptions = Vec::new();
+
+    let mut images = 0;
+    let mut videos = 0;
+    let mut files = Vec::new();
+    for attachment in &msg.attachments {
+        match classify(attachment) {
+            Category::Image => images += 1,
+            Category::Video => videos += 1,
+            Category::VoiceMessage if enabled(Category::VoiceMessage) => {
+                descriptions.push("ボイスメッセージ".to_string())
+            }
+            Category::File => files.push(attachment.filename.as_str()),
+            _ => {}
+        }
+    }
+    if images > 0 && enabled(Category::Image) {
+        descriptions.push(count_description("画像", images, "枚"));
+    }
+    if videos > 0 && enabled(Category::Video) {
+        descriptions.push(count_description("動画", videos, "本"));
+    }
+    if let (Some(first), true) = (files.first(), enabled(Category::File)) {
+        let mut description = format!("ファイル {}", shorten(first));
+        if files.len() > 1 {
+            description.push_str(&format!(" ほか{}件", files.len() - 1));
+        }
+        descriptions.push(description);
+    }
+
+    if enabled(Category::Sticker) {
+        descriptions.extend(
+            msg.sticker_items
+                .iter()
+                .map(|sticker| format!("スタンプ {}", shorten(&sticker.name))),
+        );
+    }
+
+    let embeds_suppressed = msg
+        .flags
+        .is_some_and(|flags| flags.contains(MessageFlags::SUPPRESS_EMBEDS));
+    if enabled(Category::Embed) && !embeds_suppressed {
+        descriptions.extend(
+            msg.embeds
+                .iter()
+                .filter_map(|embed| embed.title.as_deref())
+                .filter(|title| !title.trim().is_empty())
+                .map(shorten),
+        );
+    }
+
+    if enabled(Category::Poll) && is_possibly_poll(msg) && has_message_content(ctx).await {
+        if let Some(question) = fetch_poll_question(ctx, msg).await {
+            descriptions.push(format!("投票 {}", shorten(&question)));
+        }
+    }
+
+    descriptions
+}
+
+fn classify(attachment: &Attachment) -> Category {
+    if attachment.filename == VOICE_MESSAGE_FILENAME {
+        return Category::VoiceMessage;
+    }
+
+    let content_type = attachment.content_type.as_deref().unwrap_or("");
+    let extension = attachment
+        .filename
+        .rsplit_once('.')
+        .map(|(_, x)| x.to_lowercase())
+        .unwrap_or_default();
+    if content_type.starts_with("image/")
+        || matches!(
+            extension.as_str(),
+            "png" | "jpg" | "jpeg" | "gif" | "webp" | "bmp" | "heic" | "avif"
+        )
+    {
+        Category::Image
+    } else if content_type.starts_with("video/")
+        || matches!(extension.as_str(), "mp4" | "mov" | "webm" | "mkv" | "avi")
+    {
+        Category::Video
+    } else {
+        Category::File
+    }
+}
+
+fn count_description(label: &str, count: usize, unit: &str) -> String {
+    if count == 1 {
+        label.to_string()
+    } else {
+        format!("{}が{}{}", label, count, unit)
+    }
+}
+
+fn shorten(text: &str) -> String {
+    text.chars().take(MAX_NAME_LENGTH).collect()
+}
+
+/// 投票は現在のSerenityではメッセージに含まれず、ゲートウェイの内容からも読めない
+/// メッセージを取得し直すのは、投票以外の内容が何もない通常のメッセージか返信だけに限る
+/// 転送されたメッセージ（返信でないのに参照先を持つもの）やボタンなどだけのメッセージは除く
+fn is_possibly_poll(msg: &Message) -> bool {
+    let is_forward = msg.kind == MessageType::Regular && msg.message_reference.is_some();
+    matches!(msg.kind, MessageType::Regular | MessageType::InlineReply)
+        && !is_forward
+        && msg.content.is_empty()
+        && msg.attachments.is_empty()
+        && msg.sticker_items.is_empty()
+        && msg.embeds.is_empty()
+        && msg.components.is_empty()
+        && msg.activity.is_none()
+        && msg.interaction.is_none()
+}
+
+/// 本文を受け取るインテントを指定しているか
+/// 指定していないとすべてのメッセージの本文が空になり、投票かどうか見分けられないため取得し直さない
+async fn has_message_content(ctx: &Context) -> bool {
+    app_state::get(ctx)
+        .await
+        .is_ok_and(|state| state.intents.contains(GatewayIntents::MESSAGE_CONTENT))
+}
+
+/// メッセージを取得し直し、投票の質問文を返す
+async fn fetch_poll_question(ctx: &Context, msg: &Message) -> Option<String> {
+    let request = RequestBuilder::new(RouteInfo::GetMessage {
+        channel_id: msg.channel_id.into(),
+        message_id: msg.id.into(),
+    })
+    .build();
+    let message = match ctx.http.fire::<Value>(request).await {
+        Ok(message) => message,
+        Err(err) => {
+            warn!("Failed to fetch message {}: {}", msg.id, err);
+            return None;
+        }
+    };
+
+    message
+        .pointer("/poll/question/text")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
@@ -0,0 +1,153 @@
+//! サーバーの辞書による語句の置き換え
+
+use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
+use anyhow::Result;
+use bot_db::dict::{Entry, EntryKind, GetAllOption};
+use bot_db::redis;
+use log::warn;
+use serenity::model::id::GuildId;
+use std::ops::Range;
+
+/// 読み込んだサーバーの辞書
+pub struct Dictionary {
+    /// 適用する順に並べた項目
+    entries: Vec<Entry>,
+    /// 語句の項目とエンジンの辞書に登録した単語
+    words: Option<AhoCorasick>,
+}
+
+impl Dictionary {
+    pub async fn load(conn: &mut redis::aio::Connection, guild_id: GuildId) -> Result<Self> {
+        let entries = bot_db::dict::get_entries(
+            conn,
+            GetAllOption {
+                guild_id: guild_id.into(),
+            },
+        )
+        .await?;
+        let engine_words = bot_db::engine_dict::get_guild_words(conn, guild_id.into()).await?;
+        Self::new(entries, engine_words)
+    }
+
+    fn new(entries: Vec<Entry>, engine_words: Vec<String>) -> Result<Self> {
+        // エンジンの辞書に登録した単語はアクセントを活かすため置き換えない
+        let mut words = engine_words.clone();
+        words.extend(
+            entries
+                .iter()
+                .filter(|entry| entry.kind == EntryKind::Literal)
+                .map(|entry| entry.word.clone()),
+        );
+        let words = if words.is_empty() {
+            None
+        } else {
+            Some(
+                AhoCorasickBuilder::new()
+                    .match_kind(MatchKind::LeftmostLongest)
+                    .build(words)?,
+            )
+        };
+
+        let mut entries = entries
+            .into_iter()
+            .filter(|entry| entry.kind == EntryKind::Regex || !engine_words.contains(&entry.word))
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| {
+            b.priority
+                .cmp(&a.priority)
+                .then_with(|| (a.kind == EntryKind::Regex).cmp(&(b.kind == EntryKind::Regex)))
+                .then_with(|| a.word.cmp(&b.word))
+        });
+
+        Ok(Self { entries, words })
+    }
+
+    /// 辞書の項目を優先度の高い順に適用する
+    /// 同じ優先度では語句の置き換えを1回でまとめて行い（最長一致）、その後に正規表現を表記の順に適用する
+    pub fn replace(&self, text: &str) -> Result<String> {
+        let mut text = text.to_string();
+        for group in self
+            .entries
+            .chunk_by(|a, b| a.priority == b.priority && a.kind == b.kind)
+        {
+            text = match group[0].kind {
+                EntryKind::Literal => replace_literals(group, &text)?,
+                EntryKind::Regex => group.iter().fold(text, |text, entry| {
+                    match crate::regex::dict_pattern(&entry.word) {
+                        Ok(regex) => regex.replace_all(&text, entry.read_as.as_str()).into(),
+                        Err(err) => {
+                            warn!("Invalid dictionary pattern {:?}: {}", entry.word, err);
+                            text
+                        }
+                    }
+                }),
+            };
+        }
+
+        Ok(text)
+    }
+
+    /// 文章のうち、辞書の語句・正規表現とエンジンの辞書の単語に一致する範囲を返す
+    /// 変換ルールがこの範囲を書き換えると辞書が適用されなくなるため、ルールはこの範囲を避ける
+    pub fn word_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        let mut ranges = self.words.as_ref().map_or_else(Vec::new, |words| {
+            words.find_iter(text).map(|m| m.start()..m.end()).collect()
+        });
+        // 不正な正規表現は置き換えるときに警告するため、ここでは無視する
+        ranges.extend(
+            self.entries
+                .iter()
+                .filter(|entry| entry.kind == EntryKind::Regex)
+                .filter_map(|entry| crate::regex::dict_pattern(&entry.word).ok())
+                .flat_map(|regex| regex.find_iter(text).map(|m| m.range()).collect::<Vec<_>>()),
+        );
+        ranges
+    }
+}
+
+/// `range`が`ranges`のいずれかと重なるかどうか
+pub fn overlaps(range: &Range<usize>, ranges: &[Range<usize>]) -> bool {
+    ranges
+        .iter()
+        .any(|x| x.start < range.end && range.start < x.end)
+}
+
+fn replace_literals(entries: &[Entry], text: &str) -> Result<String> {
+    let word_list = entries.iter().map(|entry| &entry.word).collect::<Vec<_>>();
+    let read_as_list = entries
+        .iter()
+        .map(|entry| &entry.read_as)
+        .collect::<Vec<_>>();
+
+    let ac = AhoCorasickBuilder::new()
+        .match_kind(MatchKind::LeftmostLongest)
+        .build(word_list)?;
+
+    Ok(ac.replace_all(text, &read_as_list))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::read_setting::ReadConfig;
+    use crate::message::text_rule::RuleSet;
+
+    #[test]
+    fn rules_keep_regex_entries() {
+        let dictionary = Dictionary::new(
+            vec![Entry {
+                word: r"(?i)minecraft\S*".to_string(),
+                read_as: "マイクラ".to_string(),
+                kind: EntryKind::Regex,
+                priority: 0,
+            }],
+            Vec::new(),
+        )
+        .unwrap();
+        let config = ReadConfig::from_settings(&Default::default());
+
+        // 英単語のルールが有効でも、正規表現に一致する語はカタカナにせず辞書で置き換える
+        let text = RuleSet::default().apply("Minecraft_1.20 server", &config, &dictionary);
+        assert_eq!(dictionary.replace(&text).unwrap(), "マイクラ サーバー");
+    }
+}
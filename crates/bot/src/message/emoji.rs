@@ -0,0 +1,123 @@
+//! 絵文字を読み方に置き換える
+//!
+//! Unicodeの絵文字は同梱した表（`data/emoji_ja.tsv`）で、カスタム絵文字は名前で読む。
+
+use crate::regex::emoji_regex;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+
+static EMOJI_TABLE: Lazy<HashMap<String, &'static str>> = Lazy::new(|| {
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/data/emoji_ja.tsv"))
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with("# ") && *line != "#")
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(emoji, name)| (normalize(emoji), name))
+        .collect()
+});
+
+/// 国旗が表にない場合の読み方
+const UNKNOWN_FLAG: &str = "旗";
+
+/// 絵文字の読み方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmojiMode {
+    /// すべての絵文字を読む
+    Read,
+    /// 同じ絵文字が続く場合は一度だけ読む
+    #[default]
+    Collapse,
+    /// 絵文字を読まない
+    Drop,
+}
+
+impl EmojiMode {
+    pub const ALL: [EmojiMode; 3] = [EmojiMode::Read, EmojiMode::Collapse, EmojiMode::Drop];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EmojiMode::Read => "read",
+            EmojiMode::Collapse => "collapse",
+            EmojiMode::Drop => "drop",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|x| x.name() == name)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            EmojiMode::Read => "すべて読む",
+            EmojiMode::Collapse => "続けて同じ絵文字は一度だけ読む",
+            EmojiMode::Drop => "読まない",
+        }
+    }
+}
+
+/// 文章中の絵文字と`:name:`形式のカスタム絵文字を読み方に置き換える
+/// 読み方がわからない絵文字は取り除く
+pub fn replace_emojis(text: &str, mode: EmojiMode) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut last: Option<(&str, usize)> = None;
+
+    for m in emoji_regex().find_iter(text) {
+        let between = &text[last.map_or(0, |(_, end)| end)..m.start()];
+        // 空白だけを挟んで絵文字が続く場合は、空白の代わりに読点で区切る
+        let follows_emoji = last.is_some() && between.trim().is_empty();
+        let key = m.as_str().trim_matches(':');
+        if !follows_emoji || mode == EmojiMode::Drop {
+            result.push_str(between);
+        }
+
+        let collapsed =
+            mode == EmojiMode::Collapse && follows_emoji && last.is_some_and(|(x, _)| x == key);
+        if mode != EmojiMode::Drop && !collapsed {
+            let reading = read_emoji(m.as_str());
+            if follows_emoji && !reading.is_empty() && !result.is_empty() {
+                result.push('、');
+            }
+            result.push_str(&reading);
+        }
+        last = Some((key, m.end()));
+    }
+
+    result.push_str(&text[last.map_or(0, |(_, end)| end)..]);
+    result
+}
+
+/// 絵文字1つの読み方を返す
+fn read_emoji(emoji: &str) -> String {
+    // カスタム絵文字は名前の区切りで間を空けて読む
+    if let Some(name) = emoji.strip_prefix(':').and_then(|x| x.strip_suffix(':')) {
+        return name
+            .split('_')
+            .filter(|x| !x.is_empty())
+            .collect::<Vec<_>>()
+            .join("、");
+    }
+
+    let key = normalize(emoji);
+    if let Some(name) = EMOJI_TABLE.get(&key) {
+        return name.to_string();
+    }
+    if key.chars().all(is_regional_indicator) {
+        return UNKNOWN_FLAG.to_string();
+    }
+    // 表にない結合された絵文字は、最初の絵文字の読み方で読む
+    key.split('\u{200D}')
+        .next()
+        .and_then(|first| EMOJI_TABLE.get(first))
+        .map_or_else(String::new, |name| name.to_string())
+}
+
+/// 異体字セレクタと肌の色の修飾子を取り除く
+fn normalize(emoji: &str) -> String {
+    emoji
+        .chars()
+        .filter(|c| *c != '\u{FE0F}' && !('\u{1F3FB}'..='\u{1F3FF}').contains(c))
+        .collect()
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
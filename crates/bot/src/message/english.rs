@@ -0,0 +1,813 @@
+//! 英単語をカタカナで読む
+//!
+//! 英語カタカナ辞書（bep-eng形式）で読み、辞書にない語は綴りから読み方を推測する。
+//! 大文字だけの略語と母音のない語は1文字ずつ読む。
+//!
+//! 同梱の辞書（`data/english_katakana.dic`）は、読み方を1語ずつ確認したチャットでよく使う約2,500語に限っている。
+//! bep-eng.dicのような数万語の辞書は同梱せず、運用者が[`set_external_dictionary`]で指定した場合に初めて使うときに読み込む。
+
+use super::dictionary::overlaps;
+use crate::regex::english_word_regex;
+use log::{info, warn};
+use once_cell::sync::{Lazy, OnceCell};
+use regex::Captures;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::PathBuf;
+
+/// 同梱の辞書に加えて読み込む辞書のパス
+static EXTERNAL_DICTIONARY: OnceCell<PathBuf> = OnceCell::new();
+
+/// 大文字の英単語とカタカナの読み方の表
+/// 同じ語が両方にある場合は同梱の辞書の読み方を使う
+static ENGLISH_TABLE: Lazy<HashMap<String, String>> = Lazy::new(|| {
+    let mut table = HashMap::new();
+    if let Some(path) = EXTERNAL_DICTIONARY.get() {
+        match std::fs::read_to_string(path) {
+            Ok(content) => {
+                table.extend(parse_dictionary(&content));
+                info!("Loaded {} words from {:?}", table.len(), path);
+            }
+            Err(err) => warn!("Failed to load English dictionary {:?}: {}", path, err),
+        }
+    }
+    table.extend(parse_dictionary(include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/data/english_katakana.dic"
+    ))));
+    table
+});
+
+/// 同梱の辞書に加えて使う辞書（UTF-8のbep-eng形式）を指定する
+/// 読み込むのは最初に英単語を読むとき
+pub fn set_external_dictionary(path: impl Into<PathBuf>) {
+    let _ = EXTERNAL_DICTIONARY.set(path.into());
+}
+
+/// 1行に1語、大文字の英単語と読み方を空白で区切る。`#`で始まる行は読み飛ばす
+fn parse_dictionary(content: &str) -> impl Iterator<Item = (String, String)> + '_ {
+    content
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.trim().split_once(' '))
+        .map(|(word, reading)| (word.to_ascii_uppercase(), reading.trim().to_string()))
+}
+
+const LETTER_NAMES: [&str; 26] = [
+    "エー",
+    "ビー",
+    "シー",
+    "ディー",
+    "イー",
+    "エフ",
+    "ジー",
+    "エイチ",
+    "アイ",
+    "ジェー",
+    "ケー",
+    "エル",
+    "エム",
+    "エヌ",
+    "オー",
+    "ピー",
+    "キュー",
+    "アール",
+    "エス",
+    "ティー",
+    "ユー",
+    "ブイ",
+    "ダブリュー",
+    "エックス",
+    "ワイ",
+    "ゼット",
+];
+
+/// 文章中の英単語をカタカナに置き換える
+/// `protected`に含まれる範囲（辞書の語句など）と、数字と続けて書かれた語は変えない
+pub fn replace_english(text: &str, protected: &[Range<usize>]) -> String {
+    english_word_regex()
+        .replace_all(text, |caps: &Captures| {
+            let word = &caps[0];
+            let range = caps.get(0).map_or(0..0, |m| m.range());
+            let next_to_digit = text[..range.start]
+                .chars()
+                .next_back()
+                .is_some_and(|c| c.is_ascii_digit())
+                || text[range.end..]
+                    .chars()
+                    .next()
+                    .is_some_and(|c| c.is_ascii_digit());
+            if next_to_digit || overlaps(&range, protected) {
+                word.to_string()
+            } else {
+                read_word(word)
+            }
+        })
+        .into()
+}
+
+/// 英単語1つの読み方を返す
+fn read_word(word: &str) -> String {
+    let word = word.replace('’', "'");
+    if let Some(reading) = lookup(&word) {
+        return reading.to_string();
+    }
+
+    // `PlayStation`や`iOS`のような語は、大文字の位置で区切って読む
+    let parts = split_camel_case(&word);
+    if parts.len() > 1 {
+        return parts.into_iter().map(read_part).collect();
+    }
+    read_part(&word)
+}
+
+fn read_part(word: &str) -> String {
+    if let Some(reading) = lookup(word) {
+        return reading.to_string();
+    }
+    if let Some(base) = word.strip_suffix("'s").or_else(|| word.strip_suffix("'S")) {
+        return join_plural(&read_part(base));
+    }
+    if word.contains('\'') {
+        return read_part(&word.replace('\'', ""));
+    }
+
+    let is_acronym = word.len() >= 2 && word.bytes().all(|x| x.is_ascii_uppercase());
+    let has_vowel = word
+        .bytes()
+        .any(|x| b"aeiouy".contains(&x.to_ascii_lowercase()));
+    if is_acronym || !has_vowel || word.len() == 1 {
+        return spell(word);
+    }
+
+    let lower = word.to_ascii_lowercase();
+    read_inflected(&lower).unwrap_or_else(|| transliterate(&lower))
+}
+
+fn lookup(word: &str) -> Option<&'static str> {
+    ENGLISH_TABLE
+        .get(word.to_ascii_uppercase().replace('’', "'").as_str())
+        .map(String::as_str)
+}
+
+/// アルファベットを1文字ずつ読む
+fn spell(word: &str) -> String {
+    word.bytes()
+        .filter(u8::is_ascii_alphabetic)
+        .map(|x| LETTER_NAMES[(x.to_ascii_lowercase() - b'a') as usize])
+        .collect()
+}
+
+/// 小文字から大文字に変わる位置と、大文字の並びの最後の文字の前で区切る（`HTMLParser` → `HTML`・`Parser`）
+fn split_camel_case(word: &str) -> Vec<&str> {
+    let bytes = word.as_bytes();
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for i in 1..bytes.len() {
+        let lower_to_upper = bytes[i - 1].is_ascii_lowercase() && bytes[i].is_ascii_uppercase();
+        let acronym_end = bytes[i - 1].is_ascii_uppercase()
+            && bytes[i].is_ascii_uppercase()
+            && bytes.get(i + 1).is_some_and(u8::is_ascii_lowercase);
+        if lower_to_upper || acronym_end {
+            parts.push(&word[start..i]);
+            start = i;
+        }
+    }
+    parts.push(&word[start..]);
+    parts
+}
+
+/// 辞書にある語の複数形・過去形・進行形などを読む
+fn read_inflected(word: &str) -> Option<String> {
+    let base_reading = |base: &str| -> Option<&'static str> {
+        let undoubled = base
+            .len()
+            .checked_sub(2)
+            .filter(|i| base.as_bytes()[*i] == base.as_bytes()[*i + 1])
+            .map(|i| &base[..=i]);
+        lookup(base)
+            .or_else(|| lookup(&format!("{}e", base)))
+            .or_else(|| undoubled.and_then(lookup))
+    };
+
+    if let Some(base) = word.strip_suffix("ies") {
+        return lookup(&format!("{}y", base)).map(|x| format!("{}ズ", x));
+    }
+    if let Some(base) = word.strip_suffix("ing").filter(|x| x.len() >= 3) {
+        return base_reading(base).map(join_ing);
+    }
+    if let Some(base) = word.strip_suffix("ed").filter(|x| x.len() >= 2) {
+        return base_reading(base).map(|x| match x.strip_suffix('ト') {
+            Some(stem) => format!("{}テッド", stem),
+            None => match x.strip_suffix('ド') {
+                Some(stem) => format!("{}デッド", stem),
+                None => format!("{}ド", x),
+            },
+        });
+    }
+    if let Some(base) = word.strip_suffix("ly").filter(|x| x.len() >= 3) {
+        return lookup(base).map(|x| format!("{}リー", x));
+    }
+    if let Some(base) = word
+        .strip_suffix("es")
+        .filter(|x| x.ends_with(['s', 'x', 'z']) || x.ends_with("ch") || x.ends_with("sh"))
+    {
+        return lookup(base).map(|x| format!("{}イズ", x));
+    }
+    if let Some(base) = word
+        .strip_suffix('s')
+        .filter(|x| x.len() >= 3 && !x.ends_with('s'))
+    {
+        return lookup(base).map(join_plural);
+    }
+    None
+}
+
+/// 読み方の末尾に`ing`の読みを付ける（`メイク` → `メイキング`）
+fn join_ing(reading: &str) -> String {
+    const ROWS: [(&str, &str); 14] = [
+        ("シュ", "シ"),
+        ("ク", "キ"),
+        ("グ", "ギ"),
+        ("ス", "シ"),
+        ("ズ", "ジ"),
+        ("ト", "ティ"),
+        ("ド", "ディ"),
+        ("プ", "ピ"),
+        ("ブ", "ビ"),
+        ("ム", "ミ"),
+        ("ル", "リ"),
+        ("フ", "フィ"),
+        ("ン", "ンニ"),
+        ("チ", "チ"),
+    ];
+    for (from, to) in ROWS {
+        if let Some(stem) = reading.strip_suffix(from) {
+            return format!("{}{}ング", stem, to);
+        }
+    }
+    if reading.ends_with('ジ') {
+        return format!("{}ング", reading);
+    }
+    format!("{}イング", reading)
+}
+
+/// 読み方の末尾に複数形の`s`の読みを付ける（`ゲーム` → `ゲームズ`、`ブック` → `ブックス`）
+fn join_plural(reading: &str) -> String {
+    if let Some(stem) = reading.strip_suffix('ト') {
+        format!("{}ツ", stem)
+    } else if let Some(stem) = reading.strip_suffix('ド') {
+        format!("{}ズ", stem)
+    } else if reading.ends_with(['ク', 'プ', 'フ']) {
+        format!("{}ス", reading)
+    } else {
+        format!("{}ズ", reading)
+    }
+}
+
+/// 綴りから推測した音
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Sound {
+    /// 子音
+    Consonant(&'static str),
+    /// 母音。`base`の段で読み、`tail`（長音や二重母音の後半）を続ける。`palatal`は「キュ」のような拗音で読むこと
+    Vowel {
+        base: char,
+        tail: &'static str,
+        palatal: bool,
+    },
+    /// 促音
+    Sokuon,
+}
+
+const fn vowel(base: char, tail: &'static str) -> Sound {
+    Sound::Vowel {
+        base,
+        tail,
+        palatal: false,
+    }
+}
+
+const fn palatal(tail: &'static str) -> Sound {
+    Sound::Vowel {
+        base: 'u',
+        tail,
+        palatal: true,
+    }
+}
+
+/// 母音を表す綴りと音。長いものから順に調べる
+/// `r`で終わる綴りは、後ろに母音が続かない場合だけ使う
+const VOWEL_PATTERNS: [(&str, Sound); 32] = [
+    ("eigh", vowel('e', "イ")),
+    ("augh", vowel('o', "ー")),
+    ("ough", vowel('o', "ー")),
+    ("igh", vowel('a', "イ")),
+    ("air", vowel('e', "ア")),
+    ("ear", vowel('i', "ア")),
+    ("eer", vowel('i', "ア")),
+    ("our", vowel('a', "ワー")),
+    ("oor", vowel('o', "ー")),
+    ("ee", vowel('i', "ー")),
+    ("ea", vowel('i', "ー")),
+    ("ie", vowel('i', "ー")),
+    ("oo", vowel('u', "ー")),
+    ("oa", vowel('o', "ー")),
+    ("oi", vowel('o', "イ")),
+    ("oy", vowel('o', "イ")),
+    ("ai", vowel('e', "イ")),
+    ("ay", vowel('e', "イ")),
+    ("au", vowel('o', "ー")),
+    ("aw", vowel('o', "ー")),
+    ("ew", palatal("ー")),
+    ("eu", palatal("ー")),
+    ("ue", palatal("ー")),
+    ("ui", vowel('u', "ー")),
+    ("ey", vowel('i', "ー")),
+    ("ou", vowel('a', "ウ")),
+    ("ow", vowel('a', "ウ")),
+    ("ar", vowel('a', "ー")),
+    ("er", vowel('a', "ー")),
+    ("ir", vowel('a', "ー")),
+    ("ur", vowel('a', "ー")),
+    ("or", vowel('o', "ー")),
+];
+
+/// 語末の`re`で終わる綴りの音
+const R_ENDINGS: [(&str, Sound); 5] = [
+    ("are", vowel('e', "ア")),
+    ("ire", vowel('a', "イア")),
+    ("ore", vowel('o', "ー")),
+    ("ure", palatal("ア")),
+    ("ere", vowel('i', "ア")),
+];
+
+fn is_vowel_letter(x: u8) -> bool {
+    b"aeiou".contains(&x)
+}
+
+/// 辞書にない語の読み方を綴りから推測する
+/// `word`は小文字のアルファベットだけからなる
+fn transliterate(word: &str) -> String {
+    to_katakana(&sounds(word))
+}
+
+fn sounds(word: &str) -> Vec<Sound> {
+    let w = word.as_bytes();
+    let n = w.len();
+    let has_vowel_letter = w.iter().any(|x| is_vowel_letter(*x));
+    let mut result = Vec::new();
+    let mut i = 0;
+
+    while i < n {
+        let rest = &word[i..];
+        let current = w[i];
+        let next = w.get(i + 1).copied();
+        let after_next = w.get(i + 2).copied();
+        let after_short_vowel = matches!(
+            result.last(),
+            Some(Sound::Vowel {
+                tail: "",
+                palatal: false,
+                ..
+            })
+        );
+        let after_consonant = matches!(result.last(), Some(Sound::Consonant(_)));
+        let is_vowel_at = |x: Option<u8>| x.is_some_and(|x| is_vowel_letter(x) || x == b'y');
+
+        // 母音
+        let is_vowel =
+            is_vowel_letter(current) || (current == b'y' && !next.is_some_and(is_vowel_letter));
+        if is_vowel {
+            if let Some((pattern, sound)) = R_ENDINGS.iter().find(|(x, _)| rest == *x) {
+                result.push(*sound);
+                i += pattern.len();
+                continue;
+            }
+
+            // 子音1つと語末の`e`（`make`・`time`・`home`）で長く読む
+            let magic_e = next.is_some_and(|x| !is_vowel_letter(x) && !b"rwxy".contains(&x))
+                && after_next == Some(b'e')
+                && (i + 3 == n || (i + 4 == n && w[i + 3] == b's'));
+            if magic_e {
+                result.push(match current {
+                    b'a' => vowel('e', "イ"),
+                    b'e' => vowel('i', "ー"),
+                    b'o' => vowel('o', "ー"),
+                    b'u' => palatal("ー"),
+                    _ => vowel('a', "イ"),
+                });
+                push_consonant(&mut result, w[i + 1], Some(b'e'));
+                i += 3;
+                continue;
+            }
+
+            let pattern = VOWEL_PATTERNS.iter().find(|(x, _)| {
+                rest.starts_with(x)
+                    && (!x.ends_with('r')
+                        || !w
+                            .get(i + x.len())
+                            .is_some_and(|x| is_vowel_letter(*x) || *x == b'r' || *x == b'y'))
+            });
+            if let Some((pattern, sound)) = pattern {
+                let sound = match *pattern {
+                    // `book`・`good`は短く読む
+                    "oo" if matches!(after_next, Some(b'k' | b'd')) => vowel('u', ""),
+                    // `show`のように語末の`ow`は「オー」と読む
+                    "ow" if i + 2 == n => vowel('o', "ー"),
+                    "ew" | "eu" | "ue" if ends_with_plain_consonant(&result) => vowel('u', "ー"),
+                    _ => *sound,
+                };
+                result.push(sound);
+                i += pattern.len();
+                continue;
+            }
+
+            match current {
+                // `all`・`talk`
+                b'a' if next == Some(b'l') && matches!(after_next, Some(b'l' | b'k')) => {
+                    result.push(vowel('o', "ー"));
+                    i += if after_next == Some(b'k') { 2 } else { 1 };
+                    continue;
+                }
+                b'a' => result.push(vowel('a', "")),
+                // 語末の`e`は読まない
+                b'e' if i + 1 == n && has_other_vowel(w, i) => {}
+                b'e' if i + 1 == n => result.push(vowel('i', "ー")),
+                b'e' => result.push(vowel('e', "")),
+                b'i' => result.push(vowel('i', "")),
+                b'o' => result.push(vowel('o', "")),
+                b'u' => {
+                    let closed =
+                        next.is_some_and(|x| !is_vowel_letter(x)) && !is_vowel_at(after_next);
+                    if closed {
+                        result.push(vowel('a', ""));
+                    } else if ends_with_plain_consonant(&result) {
+                        result.push(vowel('u', "ー"));
+                    } else {
+                        result.push(palatal("ー"));
+                    }
+                }
+                // `y`
+                _ if i + 1 == n && has_vowel_letter => result.push(vowel('i', "ー")),
+                _ if i + 1 == n => result.push(vowel('a', "イ")),
+                _ => result.push(vowel('i', "")),
+            }
+            i += 1;
+            continue;
+        }
+
+        // 子音
+        if i == 0 {
+            if let Some(sound) = [("kn", "n"), ("wr", "r"), ("ps", "s"), ("wh", "w")]
+                .iter()
+                .find(|(x, _)| rest.starts_with(x))
+                .map(|(_, x)| x)
+            {
+                result.push(Sound::Consonant(sound));
+                i += 2;
+                continue;
+            }
+        }
+        if rest.starts_with("tch") || rest.starts_with("dge") {
+            if after_short_vowel {
+                result.push(Sound::Sokuon);
+            }
+            result.push(Sound::Consonant(if current == b't' { "ch" } else { "j" }));
+            i += 3;
+            continue;
+        }
+        if rest.starts_with("ssion") || rest.starts_with("tion") || rest.starts_with("sion") {
+            let len = if rest.starts_with("ssion") {
+                if after_short_vowel {
+                    result.push(Sound::Sokuon);
+                }
+                5
+            } else {
+                4
+            };
+            // `vision`・`version`は「ジョン」、`tension`・`action`は「ション」と読む
+            let voiced = current == b's' && len == 4 && !result.ends_with(&[Sound::Consonant("n")]);
+            result.push(Sound::Consonant(if voiced { "j" } else { "sh" }));
+            result.push(vowel('o', ""));
+            result.push(Sound::Consonant("n"));
+            i += len;
+            continue;
+        }
+        if rest.starts_with("ture") {
+            result.push(Sound::Consonant("ch"));
+            result.push(vowel('a', "ー"));
+            i += 4;
+            continue;
+        }
+        if rest == "le" && after_consonant {
+            result.push(Sound::Consonant("l"));
+            i += 2;
+            continue;
+        }
+        if rest.starts_with("sch") {
+            result.push(Sound::Consonant("s"));
+            result.push(Sound::Consonant("k"));
+            i += 3;
+            continue;
+        }
+        if let Some((pattern, sound)) = [
+            ("ch", "ch"),
+            ("sh", "sh"),
+            ("ph", "f"),
+            ("th", "s"),
+            ("ck", "k"),
+            ("qu", "kw"),
+        ]
+        .iter()
+        .find(|(x, _)| rest.starts_with(x))
+        {
+            let is_final = i + 2 == n;
+            if after_short_vowel && (*pattern == "ck" || (*pattern == "ch" && is_final)) {
+                result.push(Sound::Sokuon);
+            }
+            result.push(Sound::Consonant(sound));
+            i += 2;
+            continue;
+        }
+        if rest.starts_with("gh") {
+            if i == 0 {
+                result.push(Sound::Consonant("g"));
+            }
+            i += 2;
+            continue;
+        }
+        if rest.starts_with("ng") && !is_vowel_at(after_next) {
+            result.push(Sound::Consonant("ng"));
+            i += 2;
+            continue;
+        }
+        if current == b'x' {
+            if i == 0 {
+                result.push(Sound::Consonant("z"));
+            } else {
+                if after_short_vowel && i + 1 == n {
+                    result.push(Sound::Sokuon);
+                }
+                result.push(Sound::Consonant("k"));
+                result.push(Sound::Consonant("s"));
+            }
+            i += 1;
+            continue;
+        }
+        if next == Some(current) {
+            // 重なった子音は1つとして読み、短い母音の後では促音にする
+            let is_final = i + 2 == n;
+            let geminate = match current {
+                b't' | b'p' | b'k' | b'c' | b'd' | b'g' | b'z' => true,
+                b's' => !is_final,
+                _ => false,
+            };
+            if geminate && after_short_vowel {
+                result.push(Sound::Sokuon);
+            }
+            if current == b'c' && matches!(after_next, Some(b'e' | b'i' | b'y')) {
+                result.push(Sound::Consonant("k"));
+                result.push(Sound::Consonant("s"));
+            } else {
+                push_consonant(&mut result, current, after_next);
+            }
+            i += 2;
+            continue;
+        }
+        if i + 1 == n && after_short_vowel && b"tpkdg".contains(&current) {
+            result.push(Sound::Sokuon);
+        }
+        // 母音に挟まれた`s`は濁る（`music`・`easy`）
+        if current == b's'
+            && matches!(result.last(), Some(Sound::Vowel { .. }))
+            && is_vowel_at(next)
+            && !(next == Some(b'e') && i + 2 == n)
+        {
+            result.push(Sound::Consonant("z"));
+            i += 1;
+            continue;
+        }
+        push_consonant(&mut result, current, next);
+        i += 1;
+    }
+
+    result
+}
+
+/// 子音の文字を音にする。`next`は次の文字
+fn push_consonant(result: &mut Vec<Sound>, letter: u8, next: Option<u8>) {
+    let soft = matches!(next, Some(b'e' | b'i' | b'y'));
+    let is_vowel_next = next.is_some_and(|x| is_vowel_letter(x) || x == b'y');
+    let sound = match letter {
+        b'c' if soft => "s",
+        b'c' | b'k' | b'q' => "k",
+        b'g' if soft => "j",
+        b'g' => "g",
+        b'v' | b'b' => "b",
+        // 母音の前以外の`w`と`h`は読まない
+        b'w' | b'h' if !is_vowel_next => return,
+        b'w' => "w",
+        b'h' => "h",
+        b'j' => "j",
+        b'l' => "l",
+        b'r' => "r",
+        b'y' => "y",
+        b'd' => "d",
+        b'f' => "f",
+        b'm' => "m",
+        b'n' => "n",
+        b'p' => "p",
+        b's' => "s",
+        b't' => "t",
+        b'z' => "z",
+        _ => return,
+    };
+    result.push(Sound::Consonant(sound));
+}
+
+/// 直前の音が拗音にしない子音かどうか（`blue`・`true`・`super`）
+fn ends_with_plain_consonant(result: &[Sound]) -> bool {
+    matches!(
+        result.last(),
+        Some(Sound::Consonant(
+            "r" | "l" | "j" | "ch" | "sh" | "s" | "t" | "d"
+        ))
+    )
+}
+
+fn has_other_vowel(word: &[u8], index: usize) -> bool {
+    word.iter()
+        .enumerate()
+        .any(|(i, x)| i != index && (is_vowel_letter(*x) || *x == b'y'))
+}
+
+/// 音の並びをカタカナにする
+fn to_katakana(sounds: &[Sound]) -> String {
+    let mut result = String::new();
+    let mut pending: Option<&str> = None;
+    let mut after_vowel = false;
+
+    for (i, sound) in sounds.iter().enumerate() {
+        match *sound {
+            Sound::Consonant(consonant) => {
+                if let Some(pending) = pending.take() {
+                    result.push_str(final_kana(pending, Some(consonant), after_vowel));
+                    after_vowel = false;
+                }
+                pending = Some(consonant);
+            }
+            Sound::Vowel {
+                base,
+                tail,
+                palatal,
+            } => {
+                let consonant = pending.take().unwrap_or("");
+                if palatal {
+                    result.push_str(palatal_kana(consonant));
+                } else {
+                    result.push_str(syllable_kana(consonant, base));
+                }
+                result.push_str(tail);
+                after_vowel = true;
+            }
+            Sound::Sokuon => {
+                if let Some(pending) = pending.take() {
+                    result.push_str(final_kana(pending, None, after_vowel));
+                }
+                // 語頭や長音の後の促音は読まない
+                if i > 0 && !result.ends_with('ー') {
+                    result.push('ッ');
+                }
+                after_vowel = false;
+            }
+        }
+    }
+    if let Some(pending) = pending {
+        result.push_str(final_kana(pending, None, after_vowel));
+    }
+    result
+}
+
+fn syllable_kana(consonant: &str, vowel: char) -> &'static str {
+    let row: [&str; 5] = match consonant {
+        "k" => ["カ", "キ", "ク", "ケ", "コ"],
+        "g" => ["ガ", "ギ", "グ", "ゲ", "ゴ"],
+        "s" => ["サ", "シ", "ス", "セ", "ソ"],
+        "z" => ["ザ", "ジ", "ズ", "ゼ", "ゾ"],
+        "t" => ["タ", "ティ", "トゥ", "テ", "ト"],
+        "d" => ["ダ", "ディ", "ドゥ", "デ", "ド"],
+        "n" => ["ナ", "ニ", "ヌ", "ネ", "ノ"],
+        "h" => ["ハ", "ヒ", "フ", "ヘ", "ホ"],
+        "b" => ["バ", "ビ", "ブ", "ベ", "ボ"],
+        "p" => ["パ", "ピ", "プ", "ペ", "ポ"],
+        "m" => ["マ", "ミ", "ム", "メ", "モ"],
+        "y" => ["ヤ", "イ", "ユ", "イェ", "ヨ"],
+        "r" | "l" => ["ラ", "リ", "ル", "レ", "ロ"],
+        "w" => ["ワ", "ウィ", "ウ", "ウェ", "ウォ"],
+        "f" => ["ファ", "フィ", "フ", "フェ", "フォ"],
+        "j" => ["ジャ", "ジ", "ジュ", "ジェ", "ジョ"],
+        "ch" => ["チャ", "チ", "チュ", "チェ", "チョ"],
+        "sh" => ["シャ", "シ", "シュ", "シェ", "ショ"],
+        "kw" => ["クア", "クイ", "ク", "クエ", "クオ"],
+        "ng" => ["ンガ", "ンギ", "ング", "ンゲ", "ンゴ"],
+        _ => ["ア", "イ", "ウ", "エ", "オ"],
+    };
+    let index = match vowel {
+        'a' => 0,
+        'i' => 1,
+        'u' => 2,
+        'e' => 3,
+        _ => 4,
+    };
+    row[index]
+}
+
+fn palatal_kana(consonant: &str) -> &'static str {
+    match consonant {
+        "k" | "kw" => "キュ",
+        "g" => "ギュ",
+        "n" => "ニュ",
+        "h" => "ヒュ",
+        "b" => "ビュ",
+        "p" => "ピュ",
+        "m" => "ミュ",
+        "r" | "l" => "リュ",
+        "f" => "フュ",
+        "d" => "デュ",
+        "t" => "テュ",
+        "s" | "sh" => "シュ",
+        "z" | "j" => "ジュ",
+        "ch" => "チュ",
+        _ => "ユ",
+    }
+}
+
+/// 母音が続かない子音の読み方
+fn final_kana(consonant: &str, next: Option<&str>, after_vowel: bool) -> &'static str {
+    match consonant {
+        "m" if matches!(next, Some("b" | "p")) => "ン",
+        "m" => "ム",
+        "n" => "ン",
+        "ng" => "ング",
+        "r" if after_vowel => "ー",
+        "r" | "l" => "ル",
+        "k" | "kw" => "ク",
+        "g" => "グ",
+        "s" => "ス",
+        "z" => "ズ",
+        "t" => "ト",
+        "d" => "ド",
+        "b" => "ブ",
+        "p" => "プ",
+        "f" => "フ",
+        "j" => "ジ",
+        "ch" => "チ",
+        "sh" => "シュ",
+        "y" => "イ",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_words() {
+        let cases = [
+            // 辞書にある語
+            ("Minecraft", "マインクラフト"),
+            ("hello world", "ハロー ワールド"),
+            ("HELLO", "ハロー"),
+            ("games", "ゲームズ"),
+            ("playing", "プレイイング"),
+            // 略語と母音のない語
+            ("NHK", "エヌエイチケー"),
+            ("pls", "ピーエルエス"),
+            ("iOS", "アイオーエス"),
+            // 辞書にない語
+            ("fortnite", "フォートナイト"),
+            ("thunderbolt", "サンダーボルト"),
+            ("grumpy", "グランピー"),
+            ("snorkel", "スノーケル"),
+            // 短縮形と所有格
+            ("don’t", "ドント"),
+            ("cat's", "キャッツ"),
+            // 英字以外や数字と続く語はそのまま
+            ("mp3", "mp3"),
+            ("日本語とgameの話", "日本語とゲームの話"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(replace_english(input, &[]), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn keeps_protected_words() {
+        assert_eq!(
+            replace_english("minecraft server world", &[0..9, 17..22]),
+            "minecraft サーバー world"
+        );
+    }
+}
@@ -1,13 +1,33 @@
-use super::read::build_read_text;
+use super::read::{build_read_text, parse_kana_message, KANA_SYNTAX_HELP};
 use crate::app_state::{self, AppState};
 use crate::command::actions;
-use crate::command::actions::VoiceToggleOutcome;
+use crate::command::actions::{
+    ReadSettingsChange, TextRuleChange, UserNameChange, VoicePreviewOutcome, VoiceToggleOutcome,
+};
+use crate::component_interaction::voice_picker;
+use crate::engine_dict;
+use crate::voice::{
+    get_effective_profile, resolve_voice, to_speech_params, ResolvedVoice, VoiceParam,
+};
 use anyhow::{anyhow, Context as _, Result};
-use bot_db::{dict, voice::GetOption};
-use bot_speech::speech::{list_preset_ids, make_speech, SpeechRequest};
-const ZUNDAMON_UUID: &str = "388f246b-8c41-4ac1-8e2d-5d79f3ff56d9";
-use log::trace;
-use serenity::{client::Context, model::channel::Message};
+use bot_audio::DecodedAudio;
+use bot_db::dict;
+use bot_db::redis;
+use bot_speech::engine::{SynthesisRequest, TextFormat, WordType};
+use bot_speech::speech::{make_speech, make_speech_from_request, SpeechRequest};
+use bot_speech::voicevox::VoicevoxError;
+use log::{debug, trace, warn};
+use serenity::{
+    client::Context,
+    model::{
+        channel::{AttachmentType, Message},
+        id::{ChannelId, GuildId},
+    },
+};
+use std::time::{Duration, Instant};
+
+/// 音声合成の失敗を同じサーバーに続けて知らせない期間
+const SPEECH_ERROR_NOTICE_INTERVAL: Duration = Duration::from_secs(300);
 
 pub async fn handle(ctx: &Context, msg: Message) -> Result<()> {
     println!(
@@ -99,6 +119,7 @@ pub async fn handle(ctx: &Context, msg: Message) -> Result<()> {
     };
 
     // Only process messages from the bound text channel
+    let mut thread_name = None;
     {
         let mut ok = false;
         if guild_state.bound_text_channel == msg.channel_id {
@@ -109,6 +130,15 @@ pub async fn handle(ctx: &Context, msg: Message) -> Result<()> {
                 ok = true;
             }
         }
+        if !ok {
+            thread_name = thread_name_under(
+                ctx,
+                guild_id,
+                msg.channel_id,
+                guild_state.bound_text_channel,
+            );
+            ok = thread_name.is_some();
+        }
         if !ok {
             if let Some(cfg) = crate::autojoin::get_default_vc(guild_id.into()).await {
                 if cfg == msg.channel_id {
@@ -130,94 +160,177 @@ pub async fn handle(ctx: &Context, msg: Message) -> Result<()> {
 
     let mut conn = state.redis_client.get_async_connection().await?;
 
-    let text = build_read_text(
-        ctx,
-        &mut conn,
-        guild_id,
-        &msg,
-        &guild_state.last_message_read,
-    )
-    .await?;
-    trace!("Built text: {:?}", &text);
+    // カナで書かれたメッセージは、読みを変えないよう辞書などの処理を行わずに合成する
+    let (text, format) = match parse_kana_message(&msg.content) {
+        Some(kana) => (kana, TextFormat::Kana),
+        None => {
+            let text = build_read_text(
+                ctx,
+                &mut conn,
+                guild_id,
+                &msg,
+                &guild_state.last_message_read,
+                thread_name.as_deref(),
+            )
+            .await?;
+            (text, TextFormat::Plain)
+        }
+    };
+    trace!("Built text: {:?} ({:?})", &text, format);
 
     if text.is_empty() {
         trace!("Text is empty");
         return Ok(());
     }
 
-    let available_preset_ids = match list_preset_ids(&state.voicevox_client).await {
-        Ok(ids) => ids,
-        Err(_) => Vec::new(),
+    let audio = match synthesize(&state, &mut conn, guild_id, &msg, text, format).await {
+        Ok(audio) => audio,
+        Err(err) if format == TextFormat::Kana => {
+            return handle_kana_error(ctx, &state, &msg, guild_id, err).await
+        }
+        Err(err) => return handle_speech_error(ctx, &state, &msg, guild_id, err).await,
     };
 
-    if let Ok(speakers) = state.voicevox_client.speakers().await {
-        if let Some(z) = speakers
-            .iter()
-            .find(|s| s.speaker_uuid == ZUNDAMON_UUID || s.name.contains("ずんだもん"))
-        {
-            if let Some(style) = z
-                .styles
+    bot_call::enqueue(ctx, guild_id, audio.into()).await?;
+
+    guild_state.last_message_read = Some(msg);
+
+    Ok(())
+}
+
+/// `channel_id`が`parent_id`のチャンネルのスレッドならスレッド名を返す
+fn thread_name_under(
+    ctx: &Context,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    parent_id: ChannelId,
+) -> Option<String> {
+    ctx.cache
+        .guild_field(guild_id, |guild| {
+            guild
+                .threads
                 .iter()
-                .find(|st| st.name.contains("ノーマル"))
-                .or_else(|| z.styles.get(0))
-            {
-                let encoded_audio = bot_speech::speech::make_speech_by_style(
-                    &state.voicevox_client,
-                    text.clone(),
-                    style.id,
+                .find(|thread| thread.id == channel_id && thread.parent_id == Some(parent_id))
+                .map(|thread| thread.name.clone())
+        })
+        .flatten()
+}
+
+/// メッセージの送信者の声で`text`を合成する
+async fn synthesize(
+    state: &AppState,
+    conn: &mut redis::aio::Connection,
+    guild_id: GuildId,
+    msg: &Message,
+    text: String,
+    format: TextFormat,
+) -> Result<DecodedAudio> {
+    let profile = get_effective_profile(conn, guild_id, msg.author.id).await?;
+    let params = to_speech_params(&profile);
+    let engine = state.speech_engine.as_ref();
+    let cache = &state.audio_cache;
+
+    let (voice, morph) =
+        match resolve_voice(conn, engine, guild_id, msg.channel_id, msg.author.id).await? {
+            ResolvedVoice::Style(voice_id) => (voice_id, None),
+            ResolvedVoice::Morph(voice_id, morph) => (voice_id, Some(morph)),
+            ResolvedVoice::Preset(preset_id) => {
+                return make_speech(
+                    engine,
+                    cache,
+                    SpeechRequest {
+                        text,
+                        preset_id,
+                        params,
+                        format,
+                    },
                 )
                 .await
-                .context("Failed to execute TTS (Zundamon Normal)")?;
-                let raw_audio = encoded_audio.decode().await?.into();
-                bot_call::enqueue(ctx, guild_id, raw_audio).await?;
-                guild_state.last_message_read = Some(msg);
-                return Ok(());
+                .context("Failed to execute Text-to-Speech (preset)");
             }
-        }
-    };
-    if available_preset_ids.is_empty() {
-        let style_ids = bot_speech::speech::list_style_ids(&state.voicevox_client).await?;
-        let style_id = *style_ids
-            .first()
-            .ok_or_else(|| anyhow!("No styles available"))?;
-
-        let encoded_audio = bot_speech::speech::make_speech_by_style(
-            &state.voicevox_client,
-            text.clone(),
-            style_id,
-        )
-        .await
-        .context("Failed to execute Text-to-Speech (style fallback)")?;
-        let raw_audio = encoded_audio.decode().await?.into();
-
-        bot_call::enqueue(ctx, guild_id, raw_audio).await?;
-        guild_state.last_message_read = Some(msg);
-        return Ok(());
-    }
-    let fallback_preset_id = available_preset_ids
-        .first()
-        .ok_or_else(|| anyhow!("No presets available"))?
-        .into();
-    let preset_id = bot_db::voice::get(
-        &mut conn,
-        GetOption {
-            guild_id: guild_id.into(),
-            user_id: msg.author.id.into(),
-            fallback: fallback_preset_id,
+        };
+
+    make_speech_from_request(
+        engine,
+        cache,
+        SynthesisRequest {
+            text,
+            voice,
+            params,
+            morph,
+            format,
         },
     )
-    .await?
-    .into();
+    .await
+    .context("Failed to execute Text-to-Speech")
+}
 
-    let encoded_audio = make_speech(&state.voicevox_client, SpeechRequest { text, preset_id })
-        .await
-        .context("Failed to execute Text-to-Speech")?;
-    let raw_audio = encoded_audio.decode().await?.into();
+/// エンジンの停止中は定期的に知らせ、読めない文章は読み飛ばす
+async fn handle_speech_error(
+    ctx: &Context,
+    state: &AppState,
+    msg: &Message,
+    guild_id: GuildId,
+    err: anyhow::Error,
+) -> Result<()> {
+    let notice = match err.downcast_ref::<VoicevoxError>() {
+        Some(voicevox_err) if voicevox_err.is_unavailable() => {
+            warn!(
+                "Speech engine is unavailable in guild {}: {:?}",
+                guild_id, err
+            );
+            "音声合成エンジンに接続できないため、読み上げを一時的に停止しています。".to_string()
+        }
+        Some(VoicevoxError::UnknownSpeaker(style_id)) => {
+            warn!("Unknown style {} in guild {}", style_id, guild_id);
+            format!(
+                "設定されている声（スタイルID: {}）が見つかりません。`/profile set`で設定し直してください。",
+                style_id
+            )
+        }
+        Some(VoicevoxError::Rejected { .. }) => {
+            debug!(
+                "Skipped unreadable message in guild {}: {:?}",
+                guild_id, err
+            );
+            return Ok(());
+        }
+        _ => return Err(err),
+    };
 
-    bot_call::enqueue(ctx, guild_id, raw_audio).await?;
+    let now = Instant::now();
+    let recently_notified = state
+        .speech_error_notified_at
+        .get(&guild_id)
+        .is_some_and(|at| now.duration_since(*at) < SPEECH_ERROR_NOTICE_INTERVAL);
+    if !recently_notified {
+        state.speech_error_notified_at.insert(guild_id, now);
+        msg.channel_id.say(ctx, notice).await?;
+    }
 
-    guild_state.last_message_read = Some(msg);
+    Ok(())
+}
 
+/// カナの書き方の誤りは、読み飛ばさずに書き方を添えて返信する
+async fn handle_kana_error(
+    ctx: &Context,
+    state: &AppState,
+    msg: &Message,
+    guild_id: GuildId,
+    err: anyhow::Error,
+) -> Result<()> {
+    let Some(VoicevoxError::Rejected { detail, .. }) = err.downcast_ref::<VoicevoxError>() else {
+        return handle_speech_error(ctx, state, msg, guild_id, err).await;
+    };
+
+    msg.reply(
+        ctx,
+        format!(
+            "カナを読み取れませんでした: {}\n{}",
+            detail, KANA_SYNTAX_HELP
+        ),
+    )
+    .await?;
     Ok(())
 }
 
@@ -319,29 +432,109 @@ async fn handle_prefix_command(ctx: &Context, msg: &Message, state: &AppState) -
             let sub = args.first().copied().unwrap_or("");
             match sub {
                 "add" => {
+                    const USAGE: &str = "使い方: !dict add <単語> <読み> [アクセント] [品詞]";
                     let Some(word) = args.get(1) else {
-                        msg.reply(ctx, "使い方: !dict add <単語> <読み>").await?;
+                        msg.reply(ctx, USAGE).await?;
                         return Ok(());
                     };
-                    let read_as = args.iter().skip(2).cloned().collect::<Vec<_>>().join(" ");
+                    // 3番目以降が数字で始まる場合はアクセントの指定とみなす
+                    let (read_as, engine_word) = match args
+                        .get(3)
+                        .and_then(|x| x.parse::<u32>().ok())
+                    {
+                        Some(accent_type) => {
+                            let word_type = match args.get(4) {
+                                Some(name) => match engine_dict::parse_word_type(name) {
+                                    Some(word_type) => word_type,
+                                    None => {
+                                        msg.reply(ctx, "品詞は 固有名詞・普通名詞・動詞・形容詞・語尾 のいずれかを指定してください。").await?;
+                                        return Ok(());
+                                    }
+                                },
+                                None => WordType::ProperNoun,
+                            };
+                            let engine_word = actions::EngineWordOption {
+                                accent_type,
+                                word_type,
+                            };
+                            (args[2].to_string(), Some(engine_word))
+                        }
+                        None => (
+                            args.iter().skip(2).cloned().collect::<Vec<_>>().join(" "),
+                            None,
+                        ),
+                    };
                     if read_as.is_empty() {
-                        msg.reply(ctx, "使い方: !dict add <単語> <読み>").await?;
+                        msg.reply(ctx, USAGE).await?;
                         return Ok(());
                     }
-                    match actions::dict_add(state, guild_id, word, &read_as).await? {
-                        dict::InsertResponse::Success => {
-                            msg.reply(ctx, format!("辞書に登録しました: {} → {}", word, read_as))
+                    let entry = actions::DictEntryOption::default();
+                    match actions::dict_add(state, guild_id, word, &read_as, entry, engine_word)
+                        .await
+                    {
+                        Ok(outcome) => {
+                            msg.reply(ctx, actions::describe_dict_add(&outcome, word, &read_as))
                                 .await?;
                         }
-                        dict::InsertResponse::WordAlreadyExists => {
+                        Err(err) => {
+                            msg.reply(ctx, format!("辞書に登録できませんでした: {}", err))
+                                .await?;
+                        }
+                    }
+                }
+                "regex" => {
+                    const USAGE: &str = "使い方: !dict regex <正規表現> <置き換え後> [優先度]";
+                    let (Some(pattern), Some(replacement)) = (args.get(1), args.get(2)) else {
+                        msg.reply(ctx, USAGE).await?;
+                        return Ok(());
+                    };
+                    let priority = match args.get(3).map(|x| x.parse::<i64>()).transpose() {
+                        Ok(priority) => priority.unwrap_or(0),
+                        Err(_) => {
+                            msg.reply(ctx, USAGE).await?;
+                            return Ok(());
+                        }
+                    };
+                    let entry = actions::DictEntryOption {
+                        kind: dict::EntryKind::Regex,
+                        priority,
+                    };
+                    match actions::dict_add(state, guild_id, pattern, replacement, entry, None)
+                        .await
+                    {
+                        Ok(outcome) => {
                             msg.reply(
                                 ctx,
-                                "すでに登録済みです。上書きする場合はいったん削除してください。",
+                                actions::describe_dict_add(&outcome, pattern, replacement),
                             )
                             .await?;
                         }
+                        Err(err) => {
+                            msg.reply(ctx, format!("辞書に登録できませんでした: {}", err))
+                                .await?;
+                        }
                     }
                 }
+                "priority" => {
+                    let (Some(word), Some(Ok(priority))) =
+                        (args.get(1), args.get(2).map(|x| x.parse::<i64>()))
+                    else {
+                        msg.reply(ctx, "使い方: !dict priority <単語> <優先度>")
+                            .await?;
+                        return Ok(());
+                    };
+                    let content =
+                        match actions::dict_set_priority(state, guild_id, word, priority).await {
+                            Ok(dict::SetPriorityResponse::Success) => {
+                                format!("{} の優先度を{}にしました。", word, priority)
+                            }
+                            Ok(dict::SetPriorityResponse::WordDoesNotExist) => {
+                                "指定された単語は登録されていません。".to_string()
+                            }
+                            Err(err) => format!("優先度を変更できませんでした: {}", err),
+                        };
+                    msg.reply(ctx, content).await?;
+                }
                 "remove" => {
                     let Some(word) = args.get(1) else {
                         msg.reply(ctx, "使い方: !dict remove <単語>").await?;
@@ -373,12 +566,363 @@ async fn handle_prefix_command(ctx: &Context, msg: &Message, state: &AppState) -
                 _ => {
                     msg.reply(
                         ctx,
-                        "使い方: !dict add <単語> <読み> / !dict remove <単語> / !dict list",
+                        "使い方: !dict add <単語> <読み> [アクセント] [品詞] / !dict regex <正規表現> <置き換え後> [優先度] / !dict priority <単語> <優先度> / !dict remove <単語> / !dict list",
+                    )
+                    .await?;
+                }
+            }
+        }
+        "profile" => {
+            let (is_guild, args) = match args.split_first() {
+                Some((&"guild", rest)) => (true, rest),
+                _ => (false, args.as_slice()),
+            };
+            let sub = args.first().copied().unwrap_or("show");
+
+            if is_guild && sub != "show" && !can_manage_guild(ctx, msg).await? {
+                msg.reply(
+                    ctx,
+                    "サーバーの既定値を変更するにはサーバー管理権限が必要です。",
+                )
+                .await?;
+                return Ok(());
+            }
+            let scope = if is_guild {
+                crate::voice::guild_scope(guild_id)
+            } else {
+                crate::voice::user_scope(guild_id, msg.author.id)
+            };
+            let usage = "使い方: !profile [guild] show / !profile [guild] set <項目> <値> / !profile [guild] reset\n項目: style, speed, pitch, intonation, volume, pre_phoneme, post_phoneme";
+
+            match sub {
+                "show" => {
+                    let user_id = if is_guild { None } else { Some(msg.author.id) };
+                    let text = actions::voice_profile_describe(state, guild_id, user_id).await?;
+                    msg.reply(ctx, text).await?;
+                }
+                "set" => {
+                    let (Some(name), Some(value)) = (args.get(1), args.get(2)) else {
+                        msg.reply(ctx, usage).await?;
+                        return Ok(());
+                    };
+                    let result = if *name == "style" {
+                        actions::voice_profile_update(state, scope, Some(value), &[]).await
+                    } else {
+                        match (VoiceParam::from_name(name), value.parse::<f64>()) {
+                            (Some(param), Ok(value)) => {
+                                actions::voice_profile_update(state, scope, None, &[(param, value)])
+                                    .await
+                            }
+                            (None, _) => Err(anyhow!("不明な項目です: {}", name)),
+                            (_, Err(_)) => Err(anyhow!("値は数値で指定してください。")),
+                        }
+                    };
+                    match result {
+                        Ok(()) => {
+                            msg.reply(ctx, "声の設定を更新しました。").await?;
+                        }
+                        Err(err) => {
+                            msg.reply(ctx, format!("声の設定を更新できませんでした: {}", err))
+                                .await?;
+                        }
+                    }
+                }
+                "reset" => {
+                    actions::voice_profile_reset(state, scope).await?;
+                    msg.reply(ctx, "声の設定を削除しました。").await?;
+                }
+                _ => {
+                    msg.reply(ctx, usage).await?;
+                }
+            }
+        }
+        "voice" => match args.as_slice() {
+            [] => {
+                let picker =
+                    voice_picker::speaker_picker(state, guild_id, msg.author.id, 0).await?;
+                msg.channel_id
+                    .send_message(ctx, |m| {
+                        m.set_embed(picker.embed).set_components(picker.components)
+                    })
+                    .await?;
+            }
+            ["preview", voice_id] => {
+                match actions::preview_voice(ctx, state, guild_id, msg.author.id, voice_id).await {
+                    Ok(VoicePreviewOutcome::Played { name }) => {
+                        msg.reply(
+                            ctx,
+                            format!("`{}`の試聴をボイスチャンネルで再生します。", name),
+                        )
+                        .await?;
+                    }
+                    Ok(VoicePreviewOutcome::Audio { name, wav }) => {
+                        msg.channel_id
+                            .send_message(ctx, |m| {
+                                m.reference_message(msg)
+                                    .content(format!("`{}`の試聴です。", name))
+                                    .add_file(AttachmentType::Bytes {
+                                        data: wav.into(),
+                                        filename: actions::PREVIEW_FILE_NAME.to_string(),
+                                    })
+                            })
+                            .await?;
+                    }
+                    Err(err) => {
+                        msg.reply(ctx, format!("試聴できませんでした: {}", err))
+                            .await?;
+                    }
+                }
+            }
+            ["morph", rest @ ..] if rest.len() <= 2 => {
+                let rate = match rest.get(1).map(|x| x.parse::<f64>()).transpose() {
+                    Ok(rate) => rate.unwrap_or(actions::DEFAULT_MORPH_RATE),
+                    Err(_) => {
+                        msg.reply(ctx, "割合は数値で指定してください。").await?;
+                        return Ok(());
+                    }
+                };
+                let morph = rest.first().map(|target| (*target, rate));
+
+                match (
+                    actions::voice_morph_set(state, guild_id, msg.author.id, morph).await,
+                    morph,
+                ) {
+                    (Ok(()), Some(_)) => {
+                        msg.reply(ctx, "モーフィングを設定しました。").await?;
+                    }
+                    (Ok(()), None) => {
+                        msg.reply(ctx, "モーフィングの設定を削除しました。").await?;
+                    }
+                    (Err(err), _) => {
+                        msg.reply(ctx, format!("モーフィングを設定できませんでした: {}", err))
+                            .await?;
+                    }
+                }
+            }
+            [sub @ ("default" | "channel"), rest @ ..] if rest.len() <= 1 => {
+                if !can_manage_guild(ctx, msg).await? {
+                    msg.reply(ctx, "既定の声を変更するにはサーバー管理権限が必要です。")
+                        .await?;
+                    return Ok(());
+                }
+
+                let voice_id = rest.first().copied();
+                let result = if *sub == "default" {
+                    actions::voice_default_set(state, guild_id, voice_id).await
+                } else {
+                    actions::voice_channel_set(state, guild_id, msg.channel_id, voice_id).await
+                };
+
+                match (result, voice_id) {
+                    (Ok(()), Some(_)) => {
+                        msg.reply(ctx, "既定の声を設定しました。").await?;
+                    }
+                    (Ok(()), None) => {
+                        msg.reply(ctx, "既定の声の設定を削除しました。").await?;
+                    }
+                    (Err(err), _) => {
+                        msg.reply(ctx, format!("既定の声を変更できませんでした: {}", err))
+                            .await?;
+                    }
+                }
+            }
+            _ => {
+                msg.reply(
+                    ctx,
+                    "使い方: !voice / !voice preview <スタイルID> / !voice morph [スタイルID] [割合] / !voice default [スタイルID] / !voice channel [スタイルID]",
+                )
+                .await?;
+            }
+        },
+        "rules" => {
+            let change = match args.as_slice() {
+                [] | ["show"] => None,
+                ["enable", rule] => {
+                    Some(actions::parse_text_rule(rule).map(TextRuleChange::Enable))
+                }
+                ["disable", rule] => {
+                    Some(actions::parse_text_rule(rule).map(TextRuleChange::Disable))
+                }
+                ["move", rule, position] => Some(actions::parse_text_rule(rule).and_then(|rule| {
+                    let position = position
+                        .parse::<usize>()
+                        .map_err(|_| anyhow!("順番は数字で指定してください。"))?;
+                    Ok(TextRuleChange::Move(rule, position))
+                })),
+                ["reset"] => Some(Ok(TextRuleChange::Reset)),
+                _ => {
+                    msg.reply(
+                        ctx,
+                        "使い方: !rules / !rules enable <ルール> / !rules disable <ルール> / !rules move <ルール> <順番> / !rules reset",
+                    )
+                    .await?;
+                    return Ok(());
+                }
+            };
+
+            let result = match change {
+                None => actions::text_rules(state, guild_id).await,
+                Some(change) => {
+                    if !can_manage_guild(ctx, msg).await? {
+                        msg.reply(
+                            ctx,
+                            "読み上げルールを変更するにはサーバー管理権限が必要です。",
+                        )
+                        .await?;
+                        return Ok(());
+                    }
+                    match change {
+                        Ok(change) => actions::text_rules_update(state, guild_id, change).await,
+                        Err(err) => Err(err),
+                    }
+                }
+            };
+
+            match result {
+                Ok(rules) => {
+                    let embed = actions::build_text_rules_embed(&rules);
+                    msg.channel_id
+                        .send_message(ctx, |m| {
+                            m.set_embed(embed.clone());
+                            m
+                        })
+                        .await?;
+                }
+                Err(err) => {
+                    msg.reply(
+                        ctx,
+                        format!("読み上げルールを変更できませんでした: {}", err),
+                    )
+                    .await?;
+                }
+            }
+        }
+        "read" => {
+            let change = match args.as_slice() {
+                [] | ["show"] => None,
+                ["set", name, value @ ..] if !value.is_empty() => Some(
+                    actions::parse_read_option(name)
+                        .map(|option| ReadSettingsChange::Set(option, value.join(" "))),
+                ),
+                ["reset"] => Some(Ok(ReadSettingsChange::Reset(None))),
+                ["reset", name] => Some(
+                    actions::parse_read_option(name)
+                        .map(|option| ReadSettingsChange::Reset(Some(option))),
+                ),
+                _ => {
+                    msg.reply(
+                        ctx,
+                        "使い方: !read / !read set <項目> <値> / !read reset [項目]\n項目: max_length, truncation, suffix",
+                    )
+                    .await?;
+                    return Ok(());
+                }
+            };
+
+            let result = match change {
+                None => actions::read_settings(state, guild_id).await,
+                Some(change) => {
+                    if !can_manage_guild(ctx, msg).await? {
+                        msg.reply(
+                            ctx,
+                            "読み上げの設定を変更するにはサーバー管理権限が必要です。",
+                        )
+                        .await?;
+                        return Ok(());
+                    }
+                    match change {
+                        Ok(change) => actions::read_settings_update(state, guild_id, change).await,
+                        Err(err) => Err(err),
+                    }
+                }
+            };
+
+            match result {
+                Ok(settings) => {
+                    let embed = actions::build_read_settings_embed(&settings);
+                    msg.channel_id
+                        .send_message(ctx, |m| {
+                            m.set_embed(embed.clone());
+                            m
+                        })
+                        .await?;
+                }
+                Err(err) => {
+                    msg.reply(
+                        ctx,
+                        format!("読み上げの設定を変更できませんでした: {}", err),
+                    )
+                    .await?;
+                }
+            }
+        }
+        "name" => {
+            let change = match args.as_slice() {
+                [] | ["show"] => None,
+                ["reading", reading @ ..] if !reading.is_empty() => {
+                    Some(UserNameChange::Reading(reading.join(" ")))
+                }
+                ["announce", value @ ("on" | "off" | "default")] => Some(UserNameChange::Announce(
+                    crate::message::read_setting::parse_switch(value),
+                )),
+                ["reset"] => Some(UserNameChange::Reset),
+                _ => {
+                    msg.reply(
+                        ctx,
+                        "使い方: !name / !name reading <読み方> / !name announce <on|off|default> / !name reset",
                     )
                     .await?;
+                    return Ok(());
+                }
+            };
+
+            let Some(change) = change else {
+                let text = actions::user_name_describe(state, guild_id, msg.author.id).await?;
+                msg.reply(ctx, text).await?;
+                return Ok(());
+            };
+            match actions::user_name_update(state, guild_id, msg.author.id, change).await {
+                Ok(()) => {
+                    msg.reply(ctx, "名前の設定を更新しました。").await?;
+                }
+                Err(err) => {
+                    msg.reply(ctx, format!("名前の設定を更新できませんでした: {}", err))
+                        .await?;
                 }
             }
         }
+        "sing" => {
+            // `!sing style=3001 C4:ど:4 ...`のように先頭でスタイルを指定できる
+            let (voice_id, score) = match args.split_first() {
+                Some((first, rest)) if first.starts_with("style=") => {
+                    (first.strip_prefix("style="), rest)
+                }
+                _ => (None, args.as_slice()),
+            };
+            let text = score.join(" ");
+
+            let result = match actions::load_score(Some(&text), msg.attachments.first()).await {
+                Ok(score) => actions::sing(ctx, state, guild_id, score, voice_id).await,
+                Err(err) => Err(err),
+            };
+            match result {
+                Ok(name) => {
+                    msg.reply(ctx, format!("`{}`が歌います。", name)).await?;
+                }
+                Err(err) => {
+                    msg.reply(ctx, format!("歌えませんでした: {}", err)).await?;
+                }
+            }
+        }
+        "status" => {
+            let embed = actions::build_status_embed(state);
+            msg.channel_id
+                .send_message(ctx, |m| {
+                    m.set_embed(embed.clone());
+                    m
+                })
+                .await?;
+        }
         "help" => {
             let embed = actions::build_help_embed();
             msg.channel_id
@@ -396,3 +940,9 @@ async fn handle_prefix_command(ctx: &Context, msg: &Message, state: &AppState) -
 
     Ok(())
 }
+
+async fn can_manage_guild(ctx: &Context, msg: &Message) -> Result<bool> {
+    let member = msg.member(ctx).await?;
+    let permissions = member.permissions(&ctx.cache)?;
+    Ok(permissions.manage_guild())
+}
@@ -0,0 +1,131 @@
+//! 顔文字とアスキーアートの検出
+
+use super::dictionary::overlaps;
+use crate::regex::{emoji_regex, kaomoji_regex};
+use regex::Captures;
+use std::ops::Range;
+
+/// 顔文字によく使われる記号
+const FACE_CHARS: &str = "´`｀・･ω∀Дд▽≧≦^＾ﾟ゜°;；_＿ε∇△▼◕‿◡ᴗ꒳˘≖⊙ಠ益皿＊*";
+
+/// アスキーアートとみなす、記号の多い行の並びの空白を除いた最小の文字数
+const MIN_ART_LENGTH: usize = 8;
+
+/// 文章から顔文字と、記号が大半を占めるアスキーアートの行を取り除く
+pub fn remove_kaomoji(text: &str, protected: &[Range<usize>]) -> String {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        lines.push((offset..offset + line.len(), line));
+        offset += line.len();
+    }
+
+    // 記号の多い行が続く部分をまとめて判定する（1行ごとの文字数は少ないことが多いため）
+    lines
+        .chunk_by(|(_, a), (_, b)| is_symbolic(a) == is_symbolic(b))
+        .filter(|group| {
+            !(is_symbolic(group[0].1)
+                && is_ascii_art(group.iter().map(|(_, line)| *line))
+                && !group.iter().any(|(range, _)| overlaps(range, protected)))
+        })
+        .flatten()
+        .map(|(range, line)| {
+            kaomoji_regex().replace_all(line, |caps: &Captures| {
+                let matched = &caps[0];
+                let range = caps
+                    .get(0)
+                    .map_or(0..0, |m| range.start + m.start()..range.start + m.end());
+                if is_face(matched) && !overlaps(&range, protected) {
+                    String::new()
+                } else {
+                    matched.to_string()
+                }
+            })
+        })
+        .collect()
+}
+
+/// 括弧の中身が顔文字らしいかどうか
+/// 顔文字によく使う記号を含み、ひらがなや漢字、続けて書いた英数字を含まないものを顔文字とみなす
+fn is_face(kaomoji: &str) -> bool {
+    let inner = kaomoji
+        .split_once(['(', '（'])
+        .and_then(|(_, rest)| rest.rsplit_once([')', '）']))
+        .map_or(kaomoji, |(inner, _)| inner);
+
+    let chars = inner.chars().collect::<Vec<_>>();
+    let has_face_char = chars.iter().any(|c| FACE_CHARS.contains(*c));
+    let has_word = chars.iter().any(|c| is_hiragana(*c) || is_kanji(*c))
+        || chars
+            .windows(2)
+            .any(|x| x.iter().all(char::is_ascii_alphanumeric));
+    has_face_char && !has_word
+}
+
+/// 空白と絵文字を除いた文字
+fn visible_chars(line: &str) -> Vec<char> {
+    emoji_regex()
+        .replace_all(line, "")
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect()
+}
+
+/// 空白と絵文字を除いた文字の7割以上が記号の行
+fn is_symbolic(line: &str) -> bool {
+    let chars = visible_chars(line);
+    let symbol_count = chars.iter().filter(|c| !c.is_alphanumeric()).count();
+    !chars.is_empty() && symbol_count * 10 >= chars.len() * 7
+}
+
+/// 記号の多い行の並びが一定の文字数あり、記号が3種類以上あればアスキーアートとみなす
+/// `！！！！！！！！`や`--------`のような1種類の記号の並びは繰り返しとして縮めて読む
+fn is_ascii_art<'a>(lines: impl Iterator<Item = &'a str>) -> bool {
+    let chars = lines.flat_map(visible_chars).collect::<Vec<_>>();
+    let mut symbols = chars
+        .iter()
+        .filter(|c| !c.is_alphanumeric())
+        .collect::<Vec<_>>();
+    symbols.sort_unstable();
+    symbols.dedup();
+    chars.len() >= MIN_ART_LENGTH && symbols.len() >= 3
+}
+
+fn is_hiragana(c: char) -> bool {
+    matches!(c, 'ぁ'..='ゖ')
+}
+
+/// 顔文字の口に使う漢字は除く
+fn is_kanji(c: char) -> bool {
+    matches!(c, '一'..='鿿') && !matches!(c, '益' | '皿')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removes_kaomoji() {
+        let cases = [
+            ("こんにちは(´・ω・`)", "こんにちは"),
+            ("ヽ(・∀・)ﾉやった", "やった"),
+            ("(*^^*)ありがとう(≧▽≦)", "ありがとう"),
+            ("よろしく^^", "よろしく"),
+            // 括弧で囲んだ普通の文字列や式はそのまま
+            ("(笑)", "(笑)"),
+            ("関数f(x)", "関数f(x)"),
+            ("(see above)", "(see above)"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(remove_kaomoji(input, &[]), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn removes_ascii_art() {
+        let art = "見て\n　 ∧＿∧\n　（　´∀｀）\n　｜ ｜　|\n　（_＿）＿）\nかわいい";
+        assert_eq!(remove_kaomoji(art, &[]), "見て\nかわいい");
+        // 1種類の記号の並びは繰り返しとして扱う
+        assert_eq!(remove_kaomoji("！！！！！！！！", &[]), "！！！！！！！！");
+    }
+}
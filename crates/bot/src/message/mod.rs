@@ -1,2 +1,12 @@
+mod code;
+mod describe;
+mod dictionary;
+mod emoji;
+pub mod english;
 pub mod handler;
+mod kaomoji;
+mod number;
 mod read;
+pub mod read_setting;
+mod repetition;
+pub mod text_rule;
@@ -0,0 +1,478 @@
+//! 数字・日付・時刻・単位を日本語の読み方に置き換える
+//!
+//! 数量は漢数字で読み、電話番号やバージョンのような識別子は数字を1つずつ読む。
+//! 英字と続けて書かれた数字（`mp3`・`2ch`など）は識別子の一部とみなして変えない。
+
+use crate::regex::number_regex;
+use regex::Captures;
+
+const KANJI_DIGITS: [char; 10] = ['零', '一', '二', '三', '四', '五', '六', '七', '八', '九'];
+const DIGIT_NAMES: [&str; 10] = [
+    "ゼロ",
+    "イチ",
+    "ニー",
+    "サン",
+    "ヨン",
+    "ゴー",
+    "ロク",
+    "ナナ",
+    "ハチ",
+    "キュー",
+];
+/// 4桁ごとの位
+const LARGE_UNITS: [&str; 4] = ["", "万", "億", "兆"];
+
+/// 漢数字で読む最大の桁数。これより長い数字は識別子として読む
+const MAX_QUANTITY_DIGITS: usize = 16;
+/// 区切りのない数字をこの桁数以上で書いた場合は、単位がなければ識別子として読む
+const MIN_IDENTIFIER_DIGITS: usize = 9;
+/// 時刻として読む時の最大値（深夜の`25:00`のような書き方を含める）
+const MAX_HOUR: u32 = 29;
+
+/// 文章中の数字を読み方に置き換える
+pub fn normalize_numbers(text: &str) -> String {
+    let text = text
+        .chars()
+        .map(|c| match c {
+            '０'..='９' => char::from_u32(c as u32 - '０' as u32 + '0' as u32).unwrap_or(c),
+            _ => c,
+        })
+        .collect::<String>();
+
+    number_regex()
+        .replace_all(&text, |caps: &Captures| read_token(caps))
+        .into()
+}
+
+/// 数字の読み方
+enum Reading {
+    /// 単位や符号を付けて読める数量
+    Quantity(String),
+    /// 日付・時刻など、単位を付けない読み方
+    Fixed(String),
+    /// 識別子
+    Identifier(String),
+}
+
+fn read_token(caps: &Captures) -> String {
+    let original = caps[0].to_string();
+    if !caps["prefix"].is_empty() {
+        return original;
+    }
+
+    let number = to_half_width(&caps["number"]);
+    let unit = caps.name("unit").map(|x| to_half_width(x.as_str()));
+    let has_suffix = unit.is_some() || caps.name("currency").is_some();
+
+    let reading = match (read_number(&number, has_suffix), unit.as_deref()) {
+        (reading, None) => reading,
+        (Reading::Quantity(x), Some(unit)) => match unit_reading(unit) {
+            Some(unit) => Reading::Quantity(x + unit),
+            None => return original,
+        },
+        (Reading::Fixed(x), Some(unit)) => match unit.to_lowercase().as_str() {
+            "am" if is_time(&number) => Reading::Fixed(format!("午前{}", x)),
+            "pm" if is_time(&number) => Reading::Fixed(format!("午後{}", x)),
+            _ => return original,
+        },
+        (Reading::Identifier(_), Some(_)) => return original,
+    };
+
+    match reading {
+        Reading::Quantity(mut x) => {
+            if let Some(currency) = caps.name("currency") {
+                x.push_str(currency_reading(currency.as_str()));
+            }
+            if caps.name("sign").is_some() {
+                x.insert_str(0, "マイナス");
+            }
+            x
+        }
+        Reading::Fixed(x) | Reading::Identifier(x) => {
+            let symbols = [caps.name("currency"), caps.name("sign")]
+                .into_iter()
+                .flatten()
+                .map(|x| x.as_str())
+                .collect::<String>();
+            symbols + &x
+        }
+    }
+}
+
+/// 区切りを含む数字の並びを、形から数量・日付・時刻・識別子に分けて読む
+fn read_number(number: &str, has_suffix: bool) -> Reading {
+    let separators = number
+        .chars()
+        .filter(|c| !c.is_ascii_digit())
+        .collect::<Vec<_>>();
+    let groups = number
+        .split(|c: char| !c.is_ascii_digit())
+        .collect::<Vec<_>>();
+
+    if separators.is_empty() {
+        return read_integer_or_identifier(number, has_suffix);
+    }
+    // 漢数字で読める桁数を超える数字を含む並びは、比や範囲として読まない
+    let fits_quantity = groups.iter().all(|x| x.len() <= MAX_QUANTITY_DIGITS);
+    if separators.iter().all(|c| *c == ',' || *c == '.') {
+        if let Some((integer, fraction)) = parse_decimal(number) {
+            if integer.len() <= MAX_QUANTITY_DIGITS {
+                return Reading::Quantity(read_decimal(&integer, fraction));
+            }
+        }
+    }
+    if separators.iter().all(|c| *c == '.') {
+        // 区切りが2つ以上ある`1.2.3`や`192.168.0.1`は、バージョンやアドレスとして読む
+        return Reading::Identifier(read_identifier(&groups, "テン"));
+    }
+    if separators.iter().all(|c| *c == ':') {
+        if let Some(time) = read_time(&groups) {
+            return Reading::Fixed(time);
+        }
+        if let ([a, b], true) = (&groups[..], fits_quantity) {
+            return Reading::Fixed(format!("{}対{}", read_integer(a), read_integer(b)));
+        }
+    }
+    if separators.iter().all(|c| *c == '/' || *c == '-') {
+        if let Some(date) = read_date(&groups, &separators) {
+            return Reading::Fixed(date);
+        }
+    }
+    if separators.iter().all(|c| *c == '-') {
+        let is_identifier = groups.len() > 2
+            || !fits_quantity
+            || groups.iter().any(|x| x.len() > 1 && x.starts_with('0'))
+            || groups.iter().map(|x| x.len()).eq([3, 4]);
+        if is_identifier {
+            return Reading::Identifier(read_identifier(&groups, "の"));
+        }
+        if let [a, b] = groups[..] {
+            return Reading::Quantity(format!("{}から{}", read_integer(a), read_integer(b)));
+        }
+    }
+
+    // 解釈できない並びは、区切りを残して数字をそれぞれ読む
+    let mut result = String::new();
+    for (i, group) in groups.iter().enumerate() {
+        result.push_str(&match read_integer_or_identifier(group, false) {
+            Reading::Quantity(x) | Reading::Fixed(x) | Reading::Identifier(x) => x,
+        });
+        if let Some(separator) = separators.get(i) {
+            result.push(*separator);
+        }
+    }
+    Reading::Quantity(result)
+}
+
+/// 区切りのない数字を読む
+/// `0`で始まる数字や長い数字は、単位が付いていなければ識別子とみなす
+fn read_integer_or_identifier(digits: &str, has_suffix: bool) -> Reading {
+    let is_identifier = digits.len() > MAX_QUANTITY_DIGITS
+        || (!has_suffix
+            && ((digits.len() > 1 && digits.starts_with('0'))
+                || digits.len() >= MIN_IDENTIFIER_DIGITS));
+    if is_identifier {
+        Reading::Identifier(read_identifier(&[digits], ""))
+    } else {
+        Reading::Quantity(read_integer(digits))
+    }
+}
+
+/// `1,000`・`3.5`・`1,234.56`のような数を整数部と小数部に分ける
+/// 3桁ごとでないカンマ区切りは数として扱わない
+fn parse_decimal(number: &str) -> Option<(String, Option<&str>)> {
+    let (integer, fraction) = match number.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (number, None),
+    };
+    if fraction.is_some_and(|x| !x.chars().all(|c| c.is_ascii_digit())) {
+        return None;
+    }
+
+    let mut groups = integer.split(',');
+    let first = groups.next()?;
+    let rest = groups.collect::<Vec<_>>();
+    if !rest.is_empty() && (first.len() > 3 || rest.iter().any(|x| x.len() != 3)) {
+        return None;
+    }
+    Some((std::iter::once(first).chain(rest).collect(), fraction))
+}
+
+/// `12:30`・`12:30:45`を時刻として読む
+fn read_time(groups: &[&str]) -> Option<String> {
+    let (hour, rest) = groups.split_first()?;
+    if hour.len() > 2
+        || hour.parse::<u32>().map_or(true, |x| x > MAX_HOUR)
+        || rest.is_empty()
+        || rest.len() > 2
+    {
+        return None;
+    }
+    let mut values = rest
+        .iter()
+        .map(|x| x.parse::<u32>().ok().filter(|_| x.len() == 2));
+    let minute = values.next()??;
+    let second = values.next().flatten();
+    if minute >= 60 || second.is_some_and(|x| x >= 60) || (rest.len() == 2 && second.is_none()) {
+        return None;
+    }
+
+    let mut result = format!("{}時", read_integer(hour));
+    if minute > 0 || second.is_some() {
+        result.push_str(&format!("{}分", read_integer(&minute.to_string())));
+    }
+    if let Some(second) = second {
+        result.push_str(&format!("{}秒", read_integer(&second.to_string())));
+    }
+    Some(result)
+}
+
+fn is_time(number: &str) -> bool {
+    number.contains(':')
+}
+
+/// `2026/10/17`・`2026-10-17`・`10/17`を日付として読む
+fn read_date(groups: &[&str], separators: &[char]) -> Option<String> {
+    if separators.windows(2).any(|x| x[0] != x[1]) {
+        return None;
+    }
+    let (year, month, day) = match groups {
+        [year, month, day] if year.len() == 4 => (Some(*year), *month, *day),
+        // 月と日だけの書き方は`/`で区切ったものに限る
+        [month, day] if separators[0] == '/' => (None, *month, *day),
+        _ => return None,
+    };
+    if month.len() > 2 || day.len() > 2 {
+        return None;
+    }
+    let valid_month = month.parse::<u32>().is_ok_and(|x| (1..=12).contains(&x));
+    let valid_day = day.parse::<u32>().is_ok_and(|x| (1..=31).contains(&x));
+    if !valid_month || !valid_day {
+        return None;
+    }
+
+    let year = year.map_or_else(String::new, |x| format!("{}年", read_integer(x)));
+    Some(format!(
+        "{}{}月{}日",
+        year,
+        read_integer(month),
+        read_integer(day)
+    ))
+}
+
+/// 整数を漢数字で読む（`1000` → `千`、`10000` → `一万`）
+fn read_integer(digits: &str) -> String {
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        return KANJI_DIGITS[0].to_string();
+    }
+
+    let values = digits
+        .bytes()
+        .map(|x| (x - b'0') as usize)
+        .collect::<Vec<_>>();
+    let mut result = String::new();
+    for (i, chunk) in values.rchunks(4).enumerate().rev() {
+        if chunk.iter().all(|x| *x == 0) {
+            continue;
+        }
+        for (j, digit) in chunk.iter().enumerate() {
+            if *digit == 0 {
+                continue;
+            }
+            let place = chunk.len() - 1 - j;
+            // 十・百・千の前の「一」は読まない
+            if *digit != 1 || place == 0 {
+                result.push(KANJI_DIGITS[*digit]);
+            }
+            result.push_str(["", "十", "百", "千"][place]);
+        }
+        result.push_str(LARGE_UNITS[i]);
+    }
+    result
+}
+
+fn read_decimal(integer: &str, fraction: Option<&str>) -> String {
+    let mut result = read_integer(integer);
+    if let Some(fraction) = fraction {
+        result.push('点');
+        result.extend(fraction.bytes().map(|x| KANJI_DIGITS[(x - b'0') as usize]));
+    }
+    result
+}
+
+/// 識別子の数字を1つずつ読み、区切りの位置に`separator`を入れる
+fn read_identifier(groups: &[&str], separator: &str) -> String {
+    groups
+        .iter()
+        .map(|group| {
+            group
+                .bytes()
+                .map(|x| DIGIT_NAMES[(x - b'0') as usize])
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+fn unit_reading(unit: &str) -> Option<&'static str> {
+    // 1文字の単位は大文字と小文字を区別する
+    let reading = match unit {
+        "%" => "パーセント",
+        "℃" | "°C" | "°" => "度",
+        "m" => "メートル",
+        "g" => "グラム",
+        "t" => "トン",
+        "l" | "L" => "リットル",
+        "W" => "ワット",
+        "V" => "ボルト",
+        _ if unit.chars().count() == 1 => return None,
+        _ => match unit.to_lowercase().as_str() {
+            "mm" => "ミリ",
+            "cm" => "センチ",
+            "km" => "キロ",
+            "kg" => "キロ",
+            "mg" => "ミリグラム",
+            "ml" => "ミリリットル",
+            "kb" => "キロバイト",
+            "mb" => "メガバイト",
+            "gb" => "ギガバイト",
+            "tb" => "テラバイト",
+            "mbps" => "メガビーピーエス",
+            "gbps" => "ギガビーピーエス",
+            "hz" => "ヘルツ",
+            "khz" => "キロヘルツ",
+            "mhz" => "メガヘルツ",
+            "ghz" => "ギガヘルツ",
+            "kw" => "キロワット",
+            "mah" => "ミリアンペアアワー",
+            "kcal" => "キロカロリー",
+            "ms" => "ミリ秒",
+            "sec" => "秒",
+            "min" => "分",
+            "fps" => "エフピーエス",
+            "px" => "ピクセル",
+            "dpi" => "ディーピーアイ",
+            "cc" => "シーシー",
+            "pt" => "ポイント",
+            "yen" => "円",
+            _ => return None,
+        },
+    };
+    Some(reading)
+}
+
+fn currency_reading(currency: &str) -> &'static str {
+    match currency {
+        "$" | "＄" => "ドル",
+        "€" => "ユーロ",
+        "£" => "ポンド",
+        _ => "円",
+    }
+}
+
+/// 全角の英字と記号を半角にする
+fn to_half_width(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            'Ａ'..='Ｚ' | 'ａ'..='ｚ' | '％' | '，' | '．' | '：' | '／' | '－' => {
+                char::from_u32(c as u32 - 0xFEE0).unwrap_or(c)
+            }
+            _ => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 入力と期待する読み方の組
+    const GOLDEN: &[(&str, &str)] = &[
+        // 数量
+        ("1,000円", "千円"),
+        ("1000円", "千円"),
+        ("１，０００円", "千円"),
+        ("10,000人", "一万人"),
+        ("2026年", "二千二十六年"),
+        ("1億2000万", "一億二千万"),
+        ("12345678", "千二百三十四万五千六百七十八"),
+        ("0", "零"),
+        ("3.14", "三点一四"),
+        ("1,234.05", "千二百三十四点零五"),
+        ("-5℃", "マイナス五度"),
+        ("第３回", "第三回"),
+        ("3-5人", "三から五人"),
+        ("1,2,3", "一,二,三"),
+        // 単位と通貨
+        ("10kg", "十キロ"),
+        ("3.5GB", "三点五ギガバイト"),
+        ("50%", "五十パーセント"),
+        ("５０％", "五十パーセント"),
+        ("10-20%", "十から二十パーセント"),
+        ("100m走", "百メートル走"),
+        ("144Hz", "百四十四ヘルツ"),
+        ("$100", "百ドル"),
+        ("￥1,500", "千五百円"),
+        // 時刻
+        ("12:30", "十二時三十分"),
+        ("１２：３０", "十二時三十分"),
+        ("9:05から", "九時五分から"),
+        ("18:00", "十八時"),
+        ("1:02:03", "一時二分三秒"),
+        ("7:30pm", "午後七時三十分"),
+        ("3:2", "三対二"),
+        ("25:30", "二十五時三十分"),
+        ("99:59", "九十九対五十九"),
+        // 日付
+        ("2026/10/17", "二千二十六年十月十七日"),
+        ("2026-01-05", "二千二十六年一月五日"),
+        ("10/17", "十月十七日"),
+        ("100/200", "百/二百"),
+        // 識別子
+        (
+            "090-1234-5678",
+            "ゼロキューゼロのイチニーサンヨンのゴーロクナナハチ",
+        ),
+        (
+            "09012345678",
+            "ゼロキューゼロイチニーサンヨンゴーロクナナハチ",
+        ),
+        ("123-4567", "イチニーサンのヨンゴーロクナナ"),
+        ("007", "ゼロゼロナナ"),
+        ("123456789", "イチニーサンヨンゴーロクナナハチキュー"),
+        ("1.2.3", "イチテンニーテンサン"),
+        // 英字と続けて書かれた数字は変えない
+        ("mp3", "mp3"),
+        ("Windows11", "Windows11"),
+        ("2ch", "2ch"),
+        ("4K", "4K"),
+        ("v1.2", "v1.2"),
+        // 文章の中の数字
+        (
+            "明日の12:30に駅前で、参加費は1,000円です。",
+            "明日の十二時三十分に駅前で、参加費は千円です。",
+        ),
+        (
+            "残り3.5GBで50%くらい",
+            "残り三点五ギガバイトで五十パーセントくらい",
+        ),
+        ("数字なし", "数字なし"),
+        // 漢数字で読める桁数を超える比や範囲
+        (
+            "12345678901234567890-5",
+            "イチニーサンヨンゴーロクナナハチキューゼロイチニーサンヨンゴーロクナナハチキューゼロのゴー",
+        ),
+        (
+            "12345678901234567890:5",
+            "イチニーサンヨンゴーロクナナハチキューゼロイチニーサンヨンゴーロクナナハチキューゼロ:五",
+        ),
+    ];
+
+    #[test]
+    fn normalizes_golden_cases() {
+        for (input, expected) in GOLDEN {
+            assert_eq!(normalize_numbers(input), *expected, "input: {}", input);
+        }
+    }
+}
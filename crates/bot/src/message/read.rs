@@ -1,61 +1,192 @@
-use crate::regex::{custom_emoji_regex, url_regex};
-use aho_corasick::{AhoCorasickBuilder, MatchKind};
+use super::code;
+use super::describe;
+use super::dictionary::Dictionary;
+use super::number;
+use super::read_setting::{self, ReadConfig, ThreadMode};
+use super::text_rule::{self, RuleSet};
+use crate::regex::custom_emoji_regex;
 use anyhow::Result;
-use bot_db::{dict::GetAllOption, redis};
+use bot_db::{redis, user_name};
+use bot_speech::kana::to_katakana;
 use discord_md::generate::{ToMarkdownString, ToMarkdownStringOption};
 use serenity::{
     client::Context,
-    model::{channel::Message, id::GuildId},
+    model::{channel::Message, id::GuildId, user::User},
     utils::ContentSafeOptions,
 };
 
+/// メッセージの先頭にこの文字を付けると、AquesTalk風記法のカナとして読み上げる
+const KANA_PREFIXES: [char; 2] = ['%', '％'];
+
+pub const KANA_SYNTAX_HELP: &str = "書き方: `%コンニチワ'`のように、先頭に`%`を付けてカタカナで書きます。`'`はアクセントの位置、`/`と`、`はアクセント句の区切り（`、`は息継ぎあり）、`_`は直後の母音の無声化、末尾の`？`は疑問の抑揚を表します。";
+
+/// カナで書かれたメッセージならカナの部分を返す
+/// ひらがなはカタカナに変換する
+pub fn parse_kana_message(content: &str) -> Option<String> {
+    let kana = content.strip_prefix(KANA_PREFIXES)?.trim();
+    Some(to_katakana(kana))
+}
+
+/// メッセージを読み上げる文章を作る
+/// `thread_name`は、メッセージが読み上げ対象のチャンネルのスレッドで送信された場合のスレッド名
 pub async fn build_read_text(
     ctx: &Context,
     conn: &mut redis::aio::Connection,
     guild_id: GuildId,
     msg: &Message,
-    _last_msg: &Option<Message>,
+    last_msg: &Option<Message>,
+    thread_name: Option<&str>,
 ) -> Result<String> {
-    let _author_name = build_author_name(ctx, msg).await;
+    let settings = read_setting::load(conn, guild_id).await?;
+    let config = read_setting::ReadConfig::from_settings(&settings);
+    if thread_name.is_some() && config.thread == ThreadMode::Off {
+        return Ok(String::new());
+    }
 
     let content = plain_content(ctx, msg);
     let content = replace_custom_emojis(&content);
-    let content = discord_md::parse(&content).to_markdown_string(
+    let document = code::replace_code(&discord_md::parse(&content), config.code);
+    let content = document.to_markdown_string(
         &ToMarkdownStringOption::new()
             .omit_format(true)
             .omit_spoiler(true),
     );
-    let content = improved_text_processing(&content);
+    let rules = text_rule::load(conn, guild_id).await?;
+    let dictionary = Dictionary::load(conn, guild_id).await?;
+    let text = rules.apply(&content, &config, &dictionary);
+
+    let text = dictionary.replace(&text)?;
 
-    let text = content;
+    let text = number::normalize_numbers(&text);
 
-    let text = replace_words_on_dict(conn, guild_id, &text).await?;
+    let text = config.truncate(&text);
+
+    // 添付ファイルなどの説明は省略されないよう本文の後に付ける
+    let descriptions = describe::describe_message(ctx, msg, &config.describe).await;
+    let text = std::iter::once(text)
+        .chain(descriptions)
+        .filter(|x| !x.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("、");
+    if text.is_empty() {
+        return Ok(text);
+    }
+
+    let mut prefixes = Vec::new();
+
+    // 別のチャンネルやスレッドから移ってきた場合だけスレッド名を読む
+    if let (Some(thread_name), ThreadMode::Name) = (thread_name, config.thread) {
+        if last_msg
+            .as_ref()
+            .is_none_or(|last_msg| last_msg.channel_id != msg.channel_id)
+        {
+            let thread_name = convert_name(&rules, &config, &dictionary, thread_name)?;
+            prefixes.push(format!("スレッド {}", thread_name));
+        }
+    }
+
+    let author = user_name::get(
+        conn,
+        user_name::GetOption {
+            guild_id: guild_id.into(),
+            user_id: msg.author.id.into(),
+        },
+    )
+    .await?;
+    if author.announce.unwrap_or(config.author_name)
+        && should_read_author_name(msg, last_msg, config.author_name_interval)
+    {
+        let author_name = read_user_name(
+            ctx,
+            guild_id,
+            &msg.author,
+            author.reading,
+            &rules,
+            &config,
+            &dictionary,
+        )
+        .await?;
+        if !author_name.trim().is_empty() {
+            prefixes.push(format!("{}{}", author_name, config.author_suffix));
+        }
+    }
 
-    // 文字数を60文字に制限
-    if text.chars().count() > 60 {
-        Ok(text.chars().take(60 - 4).collect::<String>() + "、以下略")
-    } else {
-        Ok(text)
+    if let (Some(referenced), true) = (&msg.referenced_message, config.reply) {
+        let reading = user_name::get(
+            conn,
+            user_name::GetOption {
+                guild_id: guild_id.into(),
+                user_id: referenced.author.id.into(),
+            },
+        )
+        .await?
+        .reading;
+        let name = read_user_name(
+            ctx,
+            guild_id,
+            &referenced.author,
+            reading,
+            &rules,
+            &config,
+            &dictionary,
+        )
+        .await?;
+        if !name.trim().is_empty() {
+            prefixes.push(format!("{}{}への返信", name, config.author_suffix));
+        }
     }
+
+    prefixes.push(text);
+    Ok(prefixes.join("、"))
 }
 
-#[allow(dead_code)]
-fn should_read_author_name(msg: &Message, last_msg: &Option<Message>) -> bool {
+/// 送信者が変わったか、前のメッセージから`interval`秒より長く空いた場合に名前を読む
+fn should_read_author_name(msg: &Message, last_msg: &Option<Message>, interval: u32) -> bool {
     let last_msg = match last_msg {
         Some(msg) => msg,
         None => return true,
     };
 
     msg.author != last_msg.author
-        || (msg.timestamp.unix_timestamp() - last_msg.timestamp.unix_timestamp()) > 10
+        || (msg.timestamp.unix_timestamp() - last_msg.timestamp.unix_timestamp()) > interval as i64
+}
+
+/// ユーザーの名前を読む文字列を返す
+/// 登録された読み方はそのまま読み、表示名は本文と同じように変換する
+async fn read_user_name(
+    ctx: &Context,
+    guild_id: GuildId,
+    user: &User,
+    reading: Option<String>,
+    rules: &RuleSet,
+    config: &ReadConfig,
+    dictionary: &Dictionary,
+) -> Result<String> {
+    match reading {
+        Some(reading) => Ok(reading),
+        None => {
+            let name = build_user_name(ctx, guild_id, user).await;
+            convert_name(rules, config, dictionary, &name)
+        }
+    }
 }
 
-async fn build_author_name(ctx: &Context, msg: &Message) -> String {
-    msg.author_nick(&ctx.http)
+/// 名前に本文と同じ変換ルールと辞書を適用する
+fn convert_name(
+    rules: &RuleSet,
+    config: &ReadConfig,
+    dictionary: &Dictionary,
+    name: &str,
+) -> Result<String> {
+    dictionary.replace(&rules.apply(name, config, dictionary))
+}
+
+async fn build_user_name(ctx: &Context, guild_id: GuildId, user: &User) -> String {
+    user.nick_in(ctx, guild_id)
         .await
         // FIXME: `User::name`はユーザーの表示名ではなく一意のユーザー名を返す。現在のSerenityの実装では、ユーザーの表示名を取得する方法がない。
         // cf. https://github.com/serenity-rs/serenity/discussions/2500
-        .unwrap_or_else(|| msg.author.name.clone())
+        .unwrap_or_else(|| user.name.clone())
 }
 
 /// [Message]の内容を返す。ID表記されたメンションやチャンネル名は読める形に書き換える。
@@ -79,147 +210,3 @@ fn plain_content(ctx: &Context, msg: &Message) -> String {
 fn replace_custom_emojis(text: &str) -> String {
     custom_emoji_regex().replace_all(text, "$1").into()
 }
-
-async fn replace_words_on_dict(
-    conn: &mut redis::aio::Connection,
-    guild_id: GuildId,
-    text: &str,
-) -> Result<String> {
-    let dict = bot_db::dict::get_all(
-        conn,
-        GetAllOption {
-            guild_id: guild_id.into(),
-        },
-    )
-    .await?;
-
-    let word_list = dict.iter().map(|(word, _)| word).collect::<Vec<_>>();
-    let read_as_list = dict.iter().map(|(_, read_as)| read_as).collect::<Vec<_>>();
-
-    let ac = AhoCorasickBuilder::new()
-        .match_kind(MatchKind::LeftmostLongest)
-        .build(word_list)?;
-
-    Ok(ac.replace_all(text, &read_as_list))
-}
-
-/// 改善されたテキスト処理（仕様書の要求に基づく）
-fn improved_text_processing(text: &str) -> String {
-    let mut result = text.to_string();
-
-    // URL を省略
-    result = regex::Regex::new(r"https?://\S+")
-        .unwrap()
-        .replace_all(&result, "リンク省略")
-        .to_string();
-
-    // カスタム絵文字を除去
-    result = regex::Regex::new(r"<a?:\w+:\d+>")
-        .unwrap()
-        .replace_all(&result, "")
-        .to_string();
-
-    // Unicode絵文字を除去
-    result = regex::Regex::new(r":[^:\s]{1,20}:")
-        .unwrap()
-        .replace_all(&result, "")
-        .to_string();
-
-    // xaero関連をウェイポイント共有に置換
-    result = regex::Regex::new(r"\bxaero[^\s]*")
-        .unwrap()
-        .replace_all(&result, "ウェイポイント共有")
-        .to_string();
-
-    // ファイル/画像の言及を除去
-    result = regex::Regex::new(r"(?i)(画像|ファイル|画像ファイル)")
-        .unwrap()
-        .replace_all(&result, "")
-        .to_string();
-
-    // Discordメンション（@everyone, @here, @user）を除去
-    result = regex::Regex::new(r"@\w+")
-        .unwrap()
-        .replace_all(&result, "")
-        .to_string();
-
-    // 括弧類を除去
-    result = regex::Regex::new(r"[（()（]")
-        .unwrap()
-        .replace_all(&result, "")
-        .to_string();
-
-    // 英語の読み上げを改善
-    result = improve_english_pronunciation(&result);
-
-    result
-}
-
-/// 英語の読み上げを改善（アルファベットをより自然に）
-fn improve_english_pronunciation(text: &str) -> String {
-    let mut result = text.to_string();
-
-    // 一般的な英単語をカタカナに置換
-    let english_replacements = [
-        ("hello", "ハロー"),
-        ("thanks", "サンクス"),
-        ("thank you", "サンキュー"),
-        ("yes", "イエス"),
-        ("no", "ノー"),
-        ("ok", "オーケー"),
-        ("okay", "オーケー"),
-        ("good", "グッド"),
-        ("bad", "バッド"),
-        ("nice", "ナイス"),
-        ("cool", "クール"),
-        ("wow", "ワオ"),
-        ("sorry", "ソーリー"),
-        ("please", "プリーズ"),
-        ("welcome", "ウェルカム"),
-        ("you", "ユー"),
-        ("me", "ミー"),
-        ("help", "ヘルプ"),
-        ("stop", "ストップ"),
-        ("start", "スタート"),
-        ("go", "ゴー"),
-        ("come", "カム"),
-        ("minecraft", "マインクラフト"),
-        ("discord", "ディスコード"),
-        ("game", "ゲーム"),
-        ("play", "プレイ"),
-        ("player", "プレイヤー"),
-        ("server", "サーバー"),
-        ("world", "ワールド"),
-        ("build", "ビルド"),
-        ("craft", "クラフト"),
-        ("mine", "マイン"),
-    ];
-
-    for (english, katakana) in english_replacements.iter() {
-        result = regex::Regex::new(&format!(r"(?i)\b{}\b", regex::escape(english)))
-            .unwrap()
-            .replace_all(&result, *katakana)
-            .to_string();
-    }
-
-    // 残った英語の単語を少しマシにする（アルファベット一文字ずつ読まれるのを防ぐ）
-    result = regex::Regex::new(r"\b[a-zA-Z]{2,}\b")
-        .unwrap()
-        .replace_all(&result, |caps: &regex::Captures| {
-            let word = &caps[0];
-            // 短い単語はそのまま、長い単語は区切って読みやすくする
-            if word.len() <= 4 {
-                word.to_string()
-            } else {
-                format!("{}、{}", &word[..word.len() / 2], &word[word.len() / 2..])
-            }
-        })
-        .to_string();
-
-    result
-}
-
-/// メッセージのURLを除去（レガシー用途）
-fn remove_url(text: &str) -> String {
-    url_regex().replace_all(text, "、").into()
-}
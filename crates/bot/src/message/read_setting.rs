@@ -0,0 +1,560 @@
+//! 読み上げる文章の長さなど、サーバーごとの読み上げの設定
+//!
+//! 設定は[`bot_db::read_settings`]に文字列として保存し、ここで解釈する。
+
+use super::code::CodeMode;
+use super::describe::Category;
+use super::emoji::EmojiMode;
+use anyhow::{anyhow, bail, Result};
+use bot_db::read_settings::{self, GetOption, ReadSettings};
+use bot_db::redis;
+use serenity::model::id::GuildId;
+
+pub const DEFAULT_MAX_LENGTH: u32 = 60;
+pub const MAX_MAX_LENGTH: u32 = 2000;
+pub const DEFAULT_SUFFIX: &str = "、以下略";
+const MAX_SUFFIX_LENGTH: usize = 20;
+/// 同じ送信者でも名前を読み上げ直すまでの既定の間隔（秒）
+pub const DEFAULT_AUTHOR_NAME_INTERVAL: u32 = 10;
+const MAX_AUTHOR_NAME_INTERVAL: u32 = 3600;
+pub const DEFAULT_AUTHOR_SUFFIX: &str = "さん";
+/// 同じ文字を続けて読む既定の最大の回数
+pub const DEFAULT_MAX_REPEAT: u32 = 3;
+const MAX_MAX_REPEAT: u32 = 20;
+
+/// 省略時に何も付けないことを表す値
+const NO_SUFFIX: &str = "none";
+
+/// 文の区切りとみなす文字
+const SENTENCE_DELIMITERS: [char; 7] = ['。', '．', '！', '？', '!', '?', '\n'];
+
+/// 長い文章の省略のしかた
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Truncation {
+    /// 最大の文字数で切る
+    #[default]
+    Chars,
+    /// 最大の文字数に収まる最後の文の区切りで切る
+    Sentence,
+    /// 最初の行だけを読む
+    FirstLine,
+    /// 省略しない
+    None,
+}
+
+impl Truncation {
+    pub const ALL: [Truncation; 4] = [
+        Truncation::Chars,
+        Truncation::Sentence,
+        Truncation::FirstLine,
+        Truncation::None,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Truncation::Chars => "chars",
+            Truncation::Sentence => "sentence",
+            Truncation::FirstLine => "first_line",
+            Truncation::None => "none",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|x| x.name() == name)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Truncation::Chars => "文字数で切る",
+            Truncation::Sentence => "文の区切りで切る",
+            Truncation::FirstLine => "最初の行だけ読む",
+            Truncation::None => "省略しない",
+        }
+    }
+}
+
+/// 読み上げ対象のチャンネルのスレッドのメッセージの扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ThreadMode {
+    /// スレッドのメッセージは読まない
+    Off,
+    /// スレッドのメッセージも読む
+    #[default]
+    On,
+    /// スレッドのメッセージを読み、スレッドが変わったときはスレッド名を読む
+    Name,
+}
+
+impl ThreadMode {
+    pub const ALL: [ThreadMode; 3] = [ThreadMode::Off, ThreadMode::On, ThreadMode::Name];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ThreadMode::Off => "off",
+            ThreadMode::On => "on",
+            ThreadMode::Name => "name",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|x| x.name() == name)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ThreadMode::Off => "読まない",
+            ThreadMode::On => "読む",
+            ThreadMode::Name => "スレッド名とともに読む",
+        }
+    }
+}
+
+/// コマンドで変更できる読み上げの設定の項目
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadOption {
+    MaxLength,
+    Truncation,
+    Suffix,
+    AuthorName,
+    AuthorNameInterval,
+    AuthorSuffix,
+    Emoji,
+    Describe,
+    Reply,
+    Thread,
+    Code,
+    MaxRepeat,
+}
+
+impl ReadOption {
+    pub const ALL: [ReadOption; 12] = [
+        ReadOption::MaxLength,
+        ReadOption::Truncation,
+        ReadOption::Suffix,
+        ReadOption::AuthorName,
+        ReadOption::AuthorNameInterval,
+        ReadOption::AuthorSuffix,
+        ReadOption::Emoji,
+        ReadOption::Describe,
+        ReadOption::Reply,
+        ReadOption::Thread,
+        ReadOption::Code,
+        ReadOption::MaxRepeat,
+    ];
+
+    /// コマンドで指定するときの名前
+    pub fn name(self) -> &'static str {
+        match self {
+            ReadOption::MaxLength => read_settings::FIELD_MAX_LENGTH,
+            ReadOption::Truncation => read_settings::FIELD_TRUNCATION,
+            ReadOption::Suffix => read_settings::FIELD_SUFFIX,
+            ReadOption::AuthorName => read_settings::FIELD_AUTHOR_NAME,
+            ReadOption::AuthorNameInterval => read_settings::FIELD_AUTHOR_NAME_INTERVAL,
+            ReadOption::AuthorSuffix => read_settings::FIELD_AUTHOR_SUFFIX,
+            ReadOption::Emoji => read_settings::FIELD_EMOJI,
+            ReadOption::Describe => read_settings::FIELD_DESCRIBE,
+            ReadOption::Reply => read_settings::FIELD_REPLY,
+            ReadOption::Thread => read_settings::FIELD_THREAD,
+            ReadOption::Code => read_settings::FIELD_CODE,
+            ReadOption::MaxRepeat => read_settings::FIELD_MAX_REPEAT,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|x| x.name() == name)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ReadOption::MaxLength => "最大文字数",
+            ReadOption::Truncation => "省略のしかた",
+            ReadOption::Suffix => "省略時の末尾",
+            ReadOption::AuthorName => "送信者の名前",
+            ReadOption::AuthorNameInterval => "名前を読み直す間隔",
+            ReadOption::AuthorSuffix => "名前の後に付ける文字列",
+            ReadOption::Emoji => "絵文字の読み方",
+            ReadOption::Describe => "説明を読む添付ファイルなど",
+            ReadOption::Reply => "返信先",
+            ReadOption::Thread => "スレッドのメッセージ",
+            ReadOption::Code => "コードの読み方",
+            ReadOption::MaxRepeat => "同じ文字を続けて読む最大の回数",
+        }
+    }
+
+    /// 指定できる値の説明
+    pub fn values(self) -> String {
+        match self {
+            ReadOption::MaxLength => format!("1〜{}", MAX_MAX_LENGTH),
+            ReadOption::Truncation => Truncation::ALL
+                .iter()
+                .map(|x| format!("`{}`（{}）", x.name(), x.label()))
+                .collect::<Vec<_>>()
+                .join(", "),
+            ReadOption::Suffix | ReadOption::AuthorSuffix => format!(
+                "{}文字以内の文字列（`{}`で何も付けない）",
+                MAX_SUFFIX_LENGTH, NO_SUFFIX
+            ),
+            ReadOption::AuthorName => {
+                "`on`（送信者が変わったときなどに読む）, `off`（読まない）".to_string()
+            }
+            ReadOption::AuthorNameInterval => format!("0〜{}（秒）", MAX_AUTHOR_NAME_INTERVAL),
+            ReadOption::Emoji => EmojiMode::ALL
+                .iter()
+                .map(|x| format!("`{}`（{}）", x.name(), x.label()))
+                .collect::<Vec<_>>()
+                .join(", "),
+            ReadOption::Describe => format!(
+                "`all`、`none`、または次をカンマ区切りで: {}",
+                Category::ALL
+                    .iter()
+                    .map(|x| format!("`{}`（{}）", x.name(), x.label()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            ReadOption::Reply => "`on`（「○○さんへの返信」と読む）, `off`（読まない）".to_string(),
+            ReadOption::Thread => ThreadMode::ALL
+                .iter()
+                .map(|x| format!("`{}`（{}）", x.name(), x.label()))
+                .collect::<Vec<_>>()
+                .join(", "),
+            ReadOption::Code => CodeMode::ALL
+                .iter()
+                .map(|x| format!("`{}`（{}）", x.name(), x.label()))
+                .collect::<Vec<_>>()
+                .join(", "),
+            ReadOption::MaxRepeat => format!("1〜{}", MAX_MAX_REPEAT),
+        }
+    }
+
+    /// 値を確認したうえで`settings`に設定する
+    pub fn set(self, settings: &mut ReadSettings, value: &str) -> Result<()> {
+        match self {
+            ReadOption::MaxLength => {
+                let Ok(value) = value.parse::<u32>() else {
+                    bail!("{}は数字で指定してください。", self.label());
+                };
+                if !(1..=MAX_MAX_LENGTH).contains(&value) {
+                    bail!(
+                        "{}は1から{}の範囲で指定してください。",
+                        self.label(),
+                        MAX_MAX_LENGTH
+                    );
+                }
+                settings.max_length = Some(value);
+            }
+            ReadOption::Truncation => {
+                let Some(truncation) = Truncation::from_name(value) else {
+                    bail!(
+                        "{}は次のいずれかで指定してください: {}",
+                        self.label(),
+                        self.values()
+                    );
+                };
+                settings.truncation = Some(truncation.name().to_string());
+            }
+            ReadOption::Suffix => settings.suffix = Some(self.parse_suffix(value)?),
+            ReadOption::AuthorName => {
+                let Some(value) = parse_switch(value) else {
+                    bail!("{}は`on`か`off`で指定してください。", self.label());
+                };
+                settings.author_name = Some(value);
+            }
+            ReadOption::AuthorNameInterval => {
+                let Ok(value) = value.parse::<u32>() else {
+                    bail!("{}は数字で指定してください。", self.label());
+                };
+                if value > MAX_AUTHOR_NAME_INTERVAL {
+                    bail!(
+                        "{}は0から{}の範囲で指定してください。",
+                        self.label(),
+                        MAX_AUTHOR_NAME_INTERVAL
+                    );
+                }
+                settings.author_name_interval = Some(value);
+            }
+            ReadOption::AuthorSuffix => settings.author_suffix = Some(self.parse_suffix(value)?),
+            ReadOption::Emoji => {
+                let Some(mode) = EmojiMode::from_name(value) else {
+                    bail!(
+                        "{}は次のいずれかで指定してください: {}",
+                        self.label(),
+                        self.values()
+                    );
+                };
+                settings.emoji = Some(mode.name().to_string());
+            }
+            ReadOption::Describe => {
+                let categories = match value {
+                    "all" => Category::ALL.to_vec(),
+                    "none" => Vec::new(),
+                    _ => value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|x| !x.is_empty())
+                        .map(|name| {
+                            Category::from_name(name)
+                                .ok_or_else(|| anyhow!("不明な種類です: {}", name))
+                        })
+                        .collect::<Result<Vec<_>>>()?,
+                };
+                settings.describe = Some(
+                    Category::ALL
+                        .into_iter()
+                        .filter(|x| categories.contains(x))
+                        .map(Category::name)
+                        .collect::<Vec<_>>()
+                        .join(","),
+                );
+            }
+            ReadOption::Reply => {
+                let Some(value) = parse_switch(value) else {
+                    bail!("{}は`on`か`off`で指定してください。", self.label());
+                };
+                settings.reply = Some(value);
+            }
+            ReadOption::Thread => {
+                let Some(mode) = ThreadMode::from_name(value) else {
+                    bail!(
+                        "{}は次のいずれかで指定してください: {}",
+                        self.label(),
+                        self.values()
+                    );
+                };
+                settings.thread = Some(mode.name().to_string());
+            }
+            ReadOption::Code => {
+                let Some(mode) = CodeMode::from_name(value) else {
+                    bail!(
+                        "{}は次のいずれかで指定してください: {}",
+                        self.label(),
+                        self.values()
+                    );
+                };
+                settings.code = Some(mode.name().to_string());
+            }
+            ReadOption::MaxRepeat => {
+                let Ok(value) = value.parse::<u32>() else {
+                    bail!("{}は数字で指定してください。", self.label());
+                };
+                if !(1..=MAX_MAX_REPEAT).contains(&value) {
+                    bail!(
+                        "{}は1から{}の範囲で指定してください。",
+                        self.label(),
+                        MAX_MAX_REPEAT
+                    );
+                }
+                settings.max_repeat = Some(value);
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_suffix(self, value: &str) -> Result<String> {
+        let value = if value == NO_SUFFIX { "" } else { value };
+        if value.chars().count() > MAX_SUFFIX_LENGTH {
+            bail!(
+                "{}は{}文字以内で指定してください。",
+                self.label(),
+                MAX_SUFFIX_LENGTH
+            );
+        }
+        Ok(value.to_string())
+    }
+
+    /// 現在の値を表示用の文字列にする
+    pub fn describe(self, settings: &ReadSettings) -> String {
+        let is_default = match self {
+            ReadOption::MaxLength => settings.max_length.is_none(),
+            ReadOption::Truncation => settings.truncation.is_none(),
+            ReadOption::Suffix => settings.suffix.is_none(),
+            ReadOption::AuthorName => settings.author_name.is_none(),
+            ReadOption::AuthorNameInterval => settings.author_name_interval.is_none(),
+            ReadOption::AuthorSuffix => settings.author_suffix.is_none(),
+            ReadOption::Emoji => settings.emoji.is_none(),
+            ReadOption::Describe => settings.describe.is_none(),
+            ReadOption::Reply => settings.reply.is_none(),
+            ReadOption::Thread => settings.thread.is_none(),
+            ReadOption::Code => settings.code.is_none(),
+            ReadOption::MaxRepeat => settings.max_repeat.is_none(),
+        };
+        let config = ReadConfig::from_settings(settings);
+        let value = match self {
+            ReadOption::MaxLength => config.max_length.to_string(),
+            ReadOption::Truncation => {
+                format!(
+                    "`{}`（{}）",
+                    config.truncation.name(),
+                    config.truncation.label()
+                )
+            }
+            ReadOption::Suffix => describe_suffix(&config.suffix),
+            ReadOption::AuthorName if config.author_name => "`on`".to_string(),
+            ReadOption::AuthorName => "`off`".to_string(),
+            ReadOption::AuthorNameInterval => format!("{}秒", config.author_name_interval),
+            ReadOption::AuthorSuffix => describe_suffix(&config.author_suffix),
+            ReadOption::Emoji => format!("`{}`（{}）", config.emoji.name(), config.emoji.label()),
+            ReadOption::Describe if config.describe.is_empty() => "`none`".to_string(),
+            ReadOption::Describe => config
+                .describe
+                .iter()
+                .map(|x| format!("`{}`", x.name()))
+                .collect::<Vec<_>>()
+                .join(", "),
+            ReadOption::Reply if config.reply => "`on`".to_string(),
+            ReadOption::Reply => "`off`".to_string(),
+            ReadOption::Thread => {
+                format!("`{}`（{}）", config.thread.name(), config.thread.label())
+            }
+            ReadOption::Code => format!("`{}`（{}）", config.code.name(), config.code.label()),
+            ReadOption::MaxRepeat => format!("{}回", config.max_repeat),
+        };
+
+        if is_default {
+            format!("{} (既定)", value)
+        } else {
+            value
+        }
+    }
+}
+
+/// 既定値を補った読み上げの設定
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadConfig {
+    pub max_length: usize,
+    pub truncation: Truncation,
+    pub suffix: String,
+    pub author_name: bool,
+    pub author_name_interval: u32,
+    pub author_suffix: String,
+    pub emoji: EmojiMode,
+    pub describe: Vec<Category>,
+    pub reply: bool,
+    pub thread: ThreadMode,
+    pub code: CodeMode,
+    pub max_repeat: usize,
+}
+
+impl ReadConfig {
+    /// 保存された設定から作る。解釈できない値は既定値として扱う
+    pub fn from_settings(settings: &ReadSettings) -> Self {
+        Self {
+            max_length: settings.max_length.unwrap_or(DEFAULT_MAX_LENGTH) as usize,
+            truncation: settings
+                .truncation
+                .as_deref()
+                .and_then(Truncation::from_name)
+                .unwrap_or_default(),
+            suffix: settings
+                .suffix
+                .clone()
+                .unwrap_or_else(|| DEFAULT_SUFFIX.to_string()),
+            author_name: settings.author_name.unwrap_or(false),
+            author_name_interval: settings
+                .author_name_interval
+                .unwrap_or(DEFAULT_AUTHOR_NAME_INTERVAL),
+            author_suffix: settings
+                .author_suffix
+                .clone()
+                .unwrap_or_else(|| DEFAULT_AUTHOR_SUFFIX.to_string()),
+            emoji: settings
+                .emoji
+                .as_deref()
+                .and_then(EmojiMode::from_name)
+                .unwrap_or_default(),
+            describe: settings.describe.as_deref().map_or_else(
+                || Category::ALL.to_vec(),
+                |x| x.split(',').filter_map(Category::from_name).collect(),
+            ),
+            reply: settings.reply.unwrap_or(true),
+            thread: settings
+                .thread
+                .as_deref()
+                .and_then(ThreadMode::from_name)
+                .unwrap_or_default(),
+            code: settings
+                .code
+                .as_deref()
+                .and_then(CodeMode::from_name)
+                .unwrap_or_default(),
+            max_repeat: settings.max_repeat.unwrap_or(DEFAULT_MAX_REPEAT) as usize,
+        }
+    }
+
+    /// 設定に従って文章を省略する
+    /// 省略した場合は、末尾の文字列を含めて最大の文字数に収める
+    pub fn truncate(&self, text: &str) -> String {
+        match self.truncation {
+            Truncation::Chars => self.truncate_chars(text),
+            Truncation::Sentence => self.truncate_sentence(text),
+            Truncation::FirstLine => {
+                let mut lines = text.lines().map(str::trim).filter(|x| !x.is_empty());
+                let first = lines.next().unwrap_or("");
+                if first.chars().count() > self.max_length {
+                    self.truncate_chars(first)
+                } else if lines.next().is_some() {
+                    format!("{}{}", first, self.suffix)
+                } else {
+                    first.to_string()
+                }
+            }
+            Truncation::None => text.to_string(),
+        }
+    }
+
+    fn truncate_chars(&self, text: &str) -> String {
+        if text.chars().count() <= self.max_length {
+            return text.to_string();
+        }
+        text.chars().take(self.budget()).collect::<String>() + &self.suffix
+    }
+
+    fn truncate_sentence(&self, text: &str) -> String {
+        if text.chars().count() <= self.max_length {
+            return text.to_string();
+        }
+        let head = text.chars().take(self.budget()).collect::<String>();
+        match head.rfind(SENTENCE_DELIMITERS) {
+            Some(index) => {
+                let end = index + head[index..].chars().next().map_or(0, char::len_utf8);
+                head[..end].trim_end().to_string() + &self.suffix
+            }
+            // 区切りがなければ文字数で切る
+            None => head + &self.suffix,
+        }
+    }
+
+    /// 省略したときに本文に使える文字数
+    fn budget(&self) -> usize {
+        self.max_length
+            .saturating_sub(self.suffix.chars().count())
+            .max(1)
+    }
+}
+
+/// `on`・`off`を解釈する
+pub fn parse_switch(value: &str) -> Option<bool> {
+    match value {
+        "on" | "true" => Some(true),
+        "off" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+fn describe_suffix(suffix: &str) -> String {
+    if suffix.is_empty() {
+        "（なし）".to_string()
+    } else {
+        format!("「{}」", suffix)
+    }
+}
+
+/// サーバーの読み上げの設定を読み込む
+pub async fn load(conn: &mut redis::aio::Connection, guild_id: GuildId) -> Result<ReadSettings> {
+    read_settings::get(
+        conn,
+        GetOption {
+            guild_id: guild_id.into(),
+        },
+    )
+    .await
+}
@@ -0,0 +1,144 @@
+//! 笑いを表す「w」と、同じ文字の繰り返しの読み方
+
+use super::dictionary::overlaps;
+use crate::regex::laughter_regex;
+use regex::Captures;
+use std::ops::Range;
+
+/// 笑いを表す`w`の並びの読み方
+const LAUGHTER: &str = "わら";
+
+/// 文末の`w`の並びを「わら」と読み、続けて書かれた「草」を1つにまとめる
+/// 日本語に続く`w`は語末（英数字が続かない）であれば笑いとみなす
+/// 英文の中や文頭の`w`の並びは、文の終わり（文章の終わり・改行・句読点の前）にある場合だけ笑いとみなす
+/// `wow`のように英字と続く`w`や、英文の中の1文字の`w`はそのまま残す
+pub fn replace_laughter(text: &str, protected: &[Range<usize>]) -> String {
+    laughter_regex()
+        .replace_all(text, |caps: &Captures| {
+            let matched = &caps[0];
+            let range = caps.get(0).map_or(0..0, |m| m.range());
+            if overlaps(&range, protected) {
+                return matched.to_string();
+            }
+            if matched.starts_with('草') {
+                return "草".to_string();
+            }
+
+            let prev = text[..range.start].chars().next_back();
+            let next = text[range.end..].chars().next();
+            let next_to_word = prev.is_some_and(is_word_char) || next.is_some_and(is_word_char);
+            // 空白を挟んでも、直前が日本語なら日本語の文の中とみなす
+            let after_japanese = text[..range.start]
+                .trim_end()
+                .chars()
+                .next_back()
+                .is_some_and(|c| !c.is_ascii());
+            let is_laughter = if after_japanese {
+                // 1文字の`w`は日本語に続けて書いた場合だけ笑いとみなす
+                matched.chars().count() > 1 || prev.is_some_and(|c| !c.is_whitespace())
+            } else {
+                matched.chars().count() > 1 && at_sentence_end(&text[range.end..])
+            };
+            if next_to_word || !is_laughter {
+                matched.to_string()
+            } else {
+                LAUGHTER.to_string()
+            }
+        })
+        .into()
+}
+
+/// 同じ文字が`max`回より多く続く部分を`max`回に縮める
+/// 数字の並び（`1000000`）は縮めない
+pub fn collapse_repeats(text: &str, max: usize, protected: &[Range<usize>]) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut run: Option<(char, usize)> = None;
+    for (i, c) in text.char_indices() {
+        let count = match run {
+            Some((prev, count)) if prev == c => count + 1,
+            _ => 1,
+        };
+        run = Some((c, count));
+
+        let range = i..i + c.len_utf8();
+        if count <= max || c.is_numeric() || overlaps(&range, protected) {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// `rest`の前が文の終わりか
+/// 句読点の直後に英数字が続く場合（`www.example.com`）は文の終わりとみなさない
+fn at_sentence_end(rest: &str) -> bool {
+    let mut chars = rest.trim_start_matches([' ', '\t', '　']).chars();
+    match chars.next() {
+        None | Some('\n') => true,
+        Some(c) if is_sentence_punctuation(c) => !chars.next().is_some_and(is_word_char),
+        _ => false,
+    }
+}
+
+fn is_sentence_punctuation(c: char) -> bool {
+    matches!(
+        c,
+        '.' | ','
+            | '!'
+            | '?'
+            | '。'
+            | '、'
+            | '！'
+            | '？'
+            | '．'
+            | '，'
+            | '…'
+            | ')'
+            | '）'
+            | '」'
+            | '』'
+    )
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, 'Ａ'..='Ｚ' | 'ａ'..='ｚ' | '０'..='９')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_laughter() {
+        let cases = [
+            ("それなwwwwww", "それなわら"),
+            ("おもしろいw", "おもしろいわら"),
+            ("ｗｗｗ", "わら"),
+            ("それな ｗｗｗ 本当に", "それな わら 本当に"),
+            ("lol www", "lol わら"),
+            ("草草草草", "草"),
+            // 英単語の一部や英文の中の`w`はそのまま
+            ("wow", "wow"),
+            ("w/ friends", "w/ friends"),
+            // 文の途中の`w`の並びや、URLのようなものもそのまま
+            ("the www site", "the www site"),
+            ("www.example.com", "www.example.com"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(replace_laughter(input, &[]), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn collapses_repeats() {
+        assert_eq!(
+            collapse_repeats("すごーーーーーい！！！！！", 3, &[]),
+            "すごーーーい！！！"
+        );
+        assert_eq!(collapse_repeats("あああああ", 1, &[]), "あ");
+        assert_eq!(collapse_repeats("1000000円", 3, &[]), "1000000円");
+        assert_eq!(
+            collapse_repeats("ああああ、ええええ", 2, &[0..12, 15..27]),
+            "ああああ、ええええ"
+        );
+    }
+}
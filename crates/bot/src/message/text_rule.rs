@@ -0,0 +1,203 @@
+//! 読み上げる文章に順に適用する変換ルール
+//!
+//! サーバーごとにルールの有効・無効と適用順を変更できる。設定は[`bot_db::text_rule`]に保存する。
+
+use super::dictionary::Dictionary;
+use super::read_setting::ReadConfig;
+use super::{emoji, english, kaomoji, repetition};
+use crate::regex::{attachment_word_regex, bracket_regex, mention_regex, url_regex, xaero_regex};
+use anyhow::Result;
+use bot_db::redis;
+use bot_db::text_rule::{self, GetOption, RuleSetting, SetOption};
+use serenity::model::id::GuildId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    Url,
+    Kaomoji,
+    Emoji,
+    Xaero,
+    AttachmentWord,
+    Mention,
+    Bracket,
+    Laughter,
+    Repeat,
+    English,
+}
+
+impl Rule {
+    /// 既定の適用順
+    pub const ALL: [Rule; 10] = [
+        Rule::Url,
+        Rule::Kaomoji,
+        Rule::Emoji,
+        Rule::Xaero,
+        Rule::AttachmentWord,
+        Rule::Mention,
+        Rule::Bracket,
+        Rule::Laughter,
+        Rule::Repeat,
+        Rule::English,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Rule::Url => "url",
+            Rule::Kaomoji => "kaomoji",
+            Rule::Emoji => "emoji",
+            Rule::Xaero => "xaero",
+            Rule::AttachmentWord => "attachment_word",
+            Rule::Mention => "mention",
+            Rule::Bracket => "bracket",
+            Rule::Laughter => "laughter",
+            Rule::Repeat => "repeat",
+            Rule::English => "english",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|x| x.name() == name)
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Rule::Url => "URLを「リンク省略」と読む",
+            Rule::Kaomoji => "顔文字と、記号が大半を占めるアスキーアートの行を読まない",
+            Rule::Emoji => "絵文字を名前で読む（読み方は`/read`の`emoji`で設定）",
+            Rule::Xaero => "xaeroで始まる語を「ウェイポイント共有」と読む",
+            Rule::AttachmentWord => "「画像」「ファイル」という語を読まない",
+            Rule::Mention => "@で始まるメンションを読まない",
+            Rule::Bracket => "括弧を読まない",
+            Rule::Laughter => "文末の「w」を「わら」と読み、続けて書かれた「草」を1つにまとめる",
+            Rule::Repeat => "同じ文字の繰り返しを`/read`の`max_repeat`の回数までに縮める",
+            Rule::English => "英単語を同梱の辞書でカタカナで読み、辞書にない語は綴りから推測し、略語は1文字ずつ読む",
+        }
+    }
+
+    /// `dictionary`の語句に一致する部分は、辞書が適用されるよう書き換えない
+    pub fn apply(self, text: &str, config: &ReadConfig, dictionary: &Dictionary) -> String {
+        match self {
+            Rule::Url => url_regex().replace_all(text, "リンク省略").into(),
+            Rule::Kaomoji => kaomoji::remove_kaomoji(text, &dictionary.word_ranges(text)),
+            Rule::Emoji => emoji::replace_emojis(text, config.emoji),
+            Rule::Xaero => xaero_regex().replace_all(text, "ウェイポイント共有").into(),
+            Rule::AttachmentWord => attachment_word_regex().replace_all(text, "").into(),
+            Rule::Mention => mention_regex().replace_all(text, "").into(),
+            Rule::Bracket => bracket_regex().replace_all(text, "").into(),
+            Rule::Laughter => repetition::replace_laughter(text, &dictionary.word_ranges(text)),
+            Rule::Repeat => {
+                repetition::collapse_repeats(text, config.max_repeat, &dictionary.word_ranges(text))
+            }
+            Rule::English => english::replace_english(text, &dictionary.word_ranges(text)),
+        }
+    }
+}
+
+/// 適用順に並んだルールと、それぞれが有効かどうか
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleSet {
+    pub rules: Vec<(Rule, bool)>,
+}
+
+/// 既定ではすべてのルールを有効にする
+impl Default for RuleSet {
+    fn default() -> Self {
+        Self {
+            rules: Rule::ALL.into_iter().map(|rule| (rule, true)).collect(),
+        }
+    }
+}
+
+impl RuleSet {
+    /// 保存された設定からルールの並びを作る
+    /// 知らないルールは無視し、設定にないルール（後から追加されたもの）は既定の順序で直前にあるルールの後ろに入れる
+    fn from_settings(settings: Vec<RuleSetting>) -> Self {
+        let mut rules: Vec<(Rule, bool)> = Vec::new();
+        for setting in settings {
+            let Some(rule) = Rule::from_name(&setting.name) else {
+                continue;
+            };
+            if !rules.iter().any(|(x, _)| *x == rule) {
+                rules.push((rule, setting.enabled));
+            }
+        }
+
+        for (i, rule) in Rule::ALL.into_iter().enumerate() {
+            if rules.iter().any(|(x, _)| *x == rule) {
+                continue;
+            }
+            let position = Rule::ALL[..i]
+                .iter()
+                .rev()
+                .find_map(|prev| rules.iter().position(|(x, _)| x == prev))
+                .map_or(0, |x| x + 1);
+            rules.insert(position, (rule, true));
+        }
+
+        Self { rules }
+    }
+
+    fn to_settings(&self) -> Vec<RuleSetting> {
+        self.rules
+            .iter()
+            .map(|(rule, enabled)| RuleSetting {
+                name: rule.name().to_string(),
+                enabled: *enabled,
+            })
+            .collect()
+    }
+
+    /// 有効なルールを順に適用する
+    pub fn apply(&self, text: &str, config: &ReadConfig, dictionary: &Dictionary) -> String {
+        self.rules
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .fold(text.to_string(), |text, (rule, _)| {
+                rule.apply(&text, config, dictionary)
+            })
+    }
+
+    pub fn set_enabled(&mut self, rule: Rule, enabled: bool) {
+        if let Some(entry) = self.rules.iter_mut().find(|(x, _)| *x == rule) {
+            entry.1 = enabled;
+        }
+    }
+
+    /// ルールを`index`番目（0始まり）に移動する
+    pub fn move_to(&mut self, rule: Rule, index: usize) {
+        let Some(current) = self.rules.iter().position(|(x, _)| *x == rule) else {
+            return;
+        };
+        let entry = self.rules.remove(current);
+        self.rules.insert(index.min(self.rules.len()), entry);
+    }
+}
+
+/// サーバーのルールの設定を読み込む
+pub async fn load(conn: &mut redis::aio::Connection, guild_id: GuildId) -> Result<RuleSet> {
+    let settings = text_rule::get(
+        conn,
+        GetOption {
+            guild_id: guild_id.into(),
+        },
+    )
+    .await?;
+
+    Ok(settings.map_or_else(RuleSet::default, RuleSet::from_settings))
+}
+
+/// サーバーのルールの設定を保存する
+pub async fn save(
+    conn: &mut redis::aio::Connection,
+    guild_id: GuildId,
+    rules: &RuleSet,
+) -> Result<()> {
+    text_rule::set(
+        conn,
+        SetOption {
+            guild_id: guild_id.into(),
+            rules: rules.to_settings(),
+        },
+    )
+    .await
+}
@@ -1,4 +1,6 @@
-use regex::Regex;
+use regex::{Regex, RegexBuilder};
+use std::collections::HashMap;
+use std::sync::Mutex;
 
 // https://docs.rs/once_cell/latest/once_cell/#lazily-compiled-regex
 macro_rules! regex {
@@ -9,9 +11,85 @@ macro_rules! regex {
 }
 
 pub fn url_regex() -> &'static Regex {
-    regex!(r"https?://\S\S+")
+    regex!(r"https?://\S+")
 }
 
+/// カスタム絵文字（`<:name:id>`・`<a:name:id>`）
 pub fn custom_emoji_regex() -> &'static Regex {
-    regex!(r"<(:\w+:)\d+>")
+    regex!(r"<a?(:\w+:)\d+>")
+}
+
+/// `:name:`形式の絵文字と、Unicodeの絵文字（異体字セレクタ・肌の色・ZWJで結合した並び、国旗、キーキャップを含む）
+/// `12:30:45`のような時刻に一致しないよう、`:name:`の名前は数字以外で始まるものに限る
+pub fn emoji_regex() -> &'static Regex {
+    regex!(
+        r":[\w--\d]\w{1,31}:|\p{Regional_Indicator}{2}|[0-9#*]\x{FE0F}?\x{20E3}|\p{Extended_Pictographic}[\x{FE0F}\x{1F3FB}-\x{1F3FF}]*(?:\x{200D}\p{Extended_Pictographic}[\x{FE0F}\x{1F3FB}-\x{1F3FF}]*)*"
+    )
+}
+
+/// 数字の並びと、その前後の英字・通貨記号・符号・単位
+/// 数字の間には区切り（`,` `.` `:` `/` `-`）を1つずつ含められる
+pub fn number_regex() -> &'static Regex {
+    regex!(
+        r"(?P<prefix>[A-Za-zＡ-Ｚａ-ｚ_]*)(?P<currency>[$＄¥￥€£])?(?P<sign>[-−－])?(?P<number>[0-9](?:[,，.．:：/／\-－]?[0-9])*)(?P<unit>[A-Za-zＡ-Ｚａ-ｚ]+|[%％℃]|°C?)?"
+    )
+}
+
+pub fn mention_regex() -> &'static Regex {
+    regex!(r"@\w+")
+}
+
+pub fn bracket_regex() -> &'static Regex {
+    regex!(r"[（）()]")
+}
+
+pub fn xaero_regex() -> &'static Regex {
+    regex!(r"\bxaero[^\s]*")
+}
+
+pub fn attachment_word_regex() -> &'static Regex {
+    regex!(r"(?i)(画像|ファイル|画像ファイル)")
+}
+
+/// 笑いを表す`w`の並びと、続けて書かれた「草」
+pub fn laughter_regex() -> &'static Regex {
+    regex!(r"[wｗＷ]+|草{2,}")
+}
+
+/// 顔文字の候補（括弧で囲んだ部分と、その前後の腕や手、`^^`・`^_^`）
+/// 括弧の中身が顔文字らしいかどうかは呼び出し側で確かめる
+pub fn kaomoji_regex() -> &'static Regex {
+    regex!(
+        r"(?:[ヽ＼\\٩ｍmΣ┌⊂σ]\s*)?[(（][^()（）\n]{1,16}[)）](?:\s*[ノﾉ／/۶ｍmゞ┐⊃σ彡vｖ]+)?|[\^＾][_＿oｏωー-]?[\^＾]"
+    )
+}
+
+/// 英単語（`don't`のようにアポストロフィを含むものを含む）
+pub fn english_word_regex() -> &'static Regex {
+    regex!(r"[A-Za-z]+(?:['’][A-Za-z]+)*")
+}
+
+/// 辞書に登録する正規表現をコンパイルしたときの大きさの上限
+const DICT_PATTERN_SIZE_LIMIT: usize = 256 * 1024;
+
+/// 辞書の正規表現をコンパイルする
+/// 一度コンパイルしたものは保持しておき、メッセージごとにコンパイルし直さない
+pub fn dict_pattern(pattern: &str) -> Result<Regex, regex::Error> {
+    static CACHE: once_cell::sync::Lazy<Mutex<HashMap<String, Regex>>> =
+        once_cell::sync::Lazy::new(Default::default);
+
+    if let Some(regex) = CACHE.lock().unwrap().get(pattern) {
+        return Ok(regex.clone());
+    }
+
+    let regex = RegexBuilder::new(pattern)
+        .size_limit(DICT_PATTERN_SIZE_LIMIT)
+        .build()?;
+    let mut cache = CACHE.lock().unwrap();
+    // 削除された項目が溜まり続けないよう、大きくなったら作り直す
+    if cache.len() >= 1024 {
+        cache.clear();
+    }
+    cache.insert(pattern.to_string(), regex.clone());
+    Ok(regex)
 }
@@ -1,6 +1,10 @@
 use crate::app_state::{self, AppState};
+use crate::voice;
 use anyhow::{anyhow, Result};
 use bot_audio::EncodedAudio;
+use bot_db::voice_profile;
+use bot_speech::engine::VoiceId;
+use bot_speech::speech::{list_voice_ids, make_speech_by_voice};
 use chrono::Timelike;
 use log::{error, info};
 use once_cell::sync::OnceCell;
@@ -93,8 +97,22 @@ async fn start_time_signal_loop(ctx: SerenityContext) -> Result<()> {
                 continue;
             }
 
-            if let Some(audio) = config.audio.clone() {
-                if let Err(err) = bot_call::enqueue(&ctx, guild_id, (*audio.pcm).clone()).await {
+            // 音声が設定されていなければお知らせの文章を読み上げる
+            let pcm = match config.audio.clone() {
+                Some(audio) => Some((*audio.pcm).clone()),
+                None => match speak_announcement(&state, guild_id, &announcement).await {
+                    Ok(pcm) => Some(pcm),
+                    Err(err) => {
+                        error!(
+                            "Time signal: failed to synthesize announcement in guild {}: {:?}",
+                            guild_id, err
+                        );
+                        None
+                    }
+                },
+            };
+            if let Some(pcm) = pcm {
+                if let Err(err) = bot_call::enqueue(&ctx, guild_id, pcm).await {
                     error!(
                         "Time signal: failed to enqueue audio in guild {}: {}",
                         guild_id, err
@@ -118,6 +136,40 @@ async fn start_time_signal_loop(ctx: SerenityContext) -> Result<()> {
     }
 }
 
+/// サーバーの既定の声でお知らせの文章を合成する
+async fn speak_announcement(state: &AppState, guild_id: GuildId, text: &str) -> Result<Vec<u8>> {
+    let mut conn = state.redis_client.get_async_connection().await?;
+    let profile = voice_profile::get(
+        &mut conn,
+        voice_profile::GetOption {
+            scope: voice::guild_scope(guild_id),
+        },
+    )
+    .await?;
+
+    let engine = state.speech_engine.as_ref();
+    let voice = match &profile.voice_id {
+        Some(voice_id) => voice_id.parse::<VoiceId>()?,
+        None => list_voice_ids(engine)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("No styles available"))?,
+    };
+
+    let audio = make_speech_by_voice(
+        engine,
+        &state.audio_cache,
+        text.to_string(),
+        voice,
+        None,
+        &voice::to_speech_params(&profile),
+    )
+    .await?;
+
+    Ok(audio.into())
+}
+
 async fn has_listeners(
     ctx: &SerenityContext,
     guild_id: GuildId,
@@ -0,0 +1,279 @@
+use anyhow::{anyhow, bail, Result};
+use bot_db::redis;
+use bot_db::voice_profile::{self, Scope, VoiceProfile};
+use bot_speech::engine::{Morph, SpeechEngine, Voice, VoiceId};
+use bot_speech::speech::{
+    list_preset_ids, list_voice_ids, PresetId, VoiceParams, DEFAULT_SPEED_SCALE,
+};
+use serenity::model::id::{ChannelId, GuildId, UserId};
+
+/// ユーザーが調整できる声のパラメータ
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoiceParam {
+    Speed,
+    Pitch,
+    Intonation,
+    Volume,
+    PrePhonemeLength,
+    PostPhonemeLength,
+}
+
+impl VoiceParam {
+    pub const ALL: [VoiceParam; 6] = [
+        VoiceParam::Speed,
+        VoiceParam::Pitch,
+        VoiceParam::Intonation,
+        VoiceParam::Volume,
+        VoiceParam::PrePhonemeLength,
+        VoiceParam::PostPhonemeLength,
+    ];
+
+    /// コマンドで指定するときの名前
+    pub fn name(self) -> &'static str {
+        match self {
+            VoiceParam::Speed => "speed",
+            VoiceParam::Pitch => "pitch",
+            VoiceParam::Intonation => "intonation",
+            VoiceParam::Volume => "volume",
+            VoiceParam::PrePhonemeLength => "pre_phoneme",
+            VoiceParam::PostPhonemeLength => "post_phoneme",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            VoiceParam::Speed => "話速",
+            VoiceParam::Pitch => "音高",
+            VoiceParam::Intonation => "抑揚",
+            VoiceParam::Volume => "音量",
+            VoiceParam::PrePhonemeLength => "開始無音",
+            VoiceParam::PostPhonemeLength => "終了無音",
+        }
+    }
+
+    /// 設定できる値の範囲（VOICEVOXエディタの範囲に準拠）
+    pub fn range(self) -> (f64, f64) {
+        match self {
+            VoiceParam::Speed => (0.5, 2.0),
+            VoiceParam::Pitch => (-0.15, 0.15),
+            VoiceParam::Intonation => (0.0, 2.0),
+            VoiceParam::Volume => (0.0, 2.0),
+            VoiceParam::PrePhonemeLength => (0.0, 1.5),
+            VoiceParam::PostPhonemeLength => (0.0, 1.5),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|param| param.name() == name)
+    }
+
+    pub fn get(self, profile: &VoiceProfile) -> Option<f64> {
+        match self {
+            VoiceParam::Speed => profile.speed_scale,
+            VoiceParam::Pitch => profile.pitch_scale,
+            VoiceParam::Intonation => profile.intonation_scale,
+            VoiceParam::Volume => profile.volume_scale,
+            VoiceParam::PrePhonemeLength => profile.pre_phoneme_length,
+            VoiceParam::PostPhonemeLength => profile.post_phoneme_length,
+        }
+    }
+
+    /// 範囲を確認したうえで`profile`に値を設定する
+    pub fn set(self, profile: &mut VoiceProfile, value: f64) -> Result<()> {
+        let (min, max) = self.range();
+        if !(min..=max).contains(&value) {
+            bail!(
+                "{}は{}から{}の範囲で指定してください。",
+                self.label(),
+                min,
+                max
+            );
+        }
+
+        let slot = match self {
+            VoiceParam::Speed => &mut profile.speed_scale,
+            VoiceParam::Pitch => &mut profile.pitch_scale,
+            VoiceParam::Intonation => &mut profile.intonation_scale,
+            VoiceParam::Volume => &mut profile.volume_scale,
+            VoiceParam::PrePhonemeLength => &mut profile.pre_phoneme_length,
+            VoiceParam::PostPhonemeLength => &mut profile.post_phoneme_length,
+        };
+        *slot = Some(value);
+
+        Ok(())
+    }
+}
+
+pub fn user_scope(guild_id: GuildId, user_id: UserId) -> Scope {
+    Scope::User {
+        guild_id: guild_id.into(),
+        user_id: user_id.into(),
+    }
+}
+
+pub fn guild_scope(guild_id: GuildId) -> Scope {
+    Scope::Guild {
+        guild_id: guild_id.into(),
+    }
+}
+
+/// ユーザーの設定にサーバーの既定値を補った声の設定を返す
+pub async fn get_effective_profile(
+    conn: &mut redis::aio::Connection,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> Result<VoiceProfile> {
+    let user = voice_profile::get(
+        conn,
+        voice_profile::GetOption {
+            scope: user_scope(guild_id, user_id),
+        },
+    )
+    .await?;
+    let guild = voice_profile::get(
+        conn,
+        voice_profile::GetOption {
+            scope: guild_scope(guild_id),
+        },
+    )
+    .await?;
+
+    Ok(user.or(guild))
+}
+
+/// 読み上げに使う声
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResolvedVoice {
+    Style(VoiceId),
+    /// ユーザーが設定した2つのスタイルのモーフィング
+    Morph(VoiceId, Morph),
+    /// ユーザーが以前に設定したプリセット
+    Preset(PresetId),
+}
+
+/// 読み上げに使う声を決める
+/// ユーザーの声、チャンネルの声、サーバーの既定の声、エンジンの最初のスタイルの順に探す
+pub async fn resolve_voice(
+    conn: &mut redis::aio::Connection,
+    engine: &dyn SpeechEngine,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    user_id: UserId,
+) -> Result<ResolvedVoice> {
+    let user = voice_profile::get(
+        conn,
+        voice_profile::GetOption {
+            scope: user_scope(guild_id, user_id),
+        },
+    )
+    .await?;
+    if let Some(voice_id) = user.voice_id {
+        let voice_id = voice_id.parse()?;
+        return Ok(match (user.morph_target, user.morph_rate) {
+            (Some(target), Some(rate)) => ResolvedVoice::Morph(
+                voice_id,
+                Morph {
+                    target: target.parse()?,
+                    rate,
+                },
+            ),
+            _ => ResolvedVoice::Style(voice_id),
+        });
+    }
+
+    let preset_id = bot_db::voice::get(
+        conn,
+        bot_db::voice::GetOption {
+            guild_id: guild_id.into(),
+            user_id: user_id.into(),
+        },
+    )
+    .await?;
+    if let Some(preset_id) = preset_id.map(PresetId) {
+        // エンジンを切り替えた後などで存在しないプリセットは無視する
+        let available = list_preset_ids(engine).await.unwrap_or_default();
+        if available.contains(&preset_id) {
+            return Ok(ResolvedVoice::Preset(preset_id));
+        }
+    }
+
+    let channel = bot_db::voice::get_channel(
+        conn,
+        bot_db::voice::GetChannelOption {
+            guild_id: guild_id.into(),
+            channel_id: channel_id.into(),
+        },
+    )
+    .await?;
+    if let Some(voice_id) = channel {
+        return Ok(ResolvedVoice::Style(voice_id.parse()?));
+    }
+
+    let guild = voice_profile::get(
+        conn,
+        voice_profile::GetOption {
+            scope: guild_scope(guild_id),
+        },
+    )
+    .await?;
+    if let Some(voice_id) = guild.voice_id {
+        return Ok(ResolvedVoice::Style(voice_id.parse()?));
+    }
+
+    let voice_id = list_voice_ids(engine)
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("No styles available"))?;
+    Ok(ResolvedVoice::Style(voice_id))
+}
+
+pub fn to_speech_params(profile: &VoiceProfile) -> VoiceParams {
+    VoiceParams {
+        speed_scale: profile.speed_scale,
+        pitch_scale: profile.pitch_scale,
+        intonation_scale: profile.intonation_scale,
+        volume_scale: profile.volume_scale,
+        pre_phoneme_length: profile.pre_phoneme_length,
+        post_phoneme_length: profile.post_phoneme_length,
+    }
+}
+
+/// 声を`話者（スタイル）`の形式で表示する
+pub fn voice_name(voice: &Voice) -> String {
+    format!("{}（{}）", voice.speaker_name, voice.style_name)
+}
+
+/// 声の設定を表示用の文字列にする
+/// `own`が未設定の項目は`fallback`（サーバーの既定値）の値を表示する
+pub fn describe_profile(own: &VoiceProfile, fallback: &VoiceProfile) -> String {
+    let mut lines = Vec::new();
+
+    let style = match (&own.voice_id, &fallback.voice_id) {
+        (Some(id), _) => id.clone(),
+        (None, Some(id)) => format!("{} (サーバー既定)", id),
+        (None, None) => "未設定".to_string(),
+    };
+    lines.push(format!("スタイルID: {}", style));
+    if let (Some(target), Some(rate)) = (&own.morph_target, own.morph_rate) {
+        lines.push(format!(
+            "モーフィング: スタイルID {} を {:.0}% 混ぜる",
+            target,
+            rate * 100.0
+        ));
+    }
+
+    for param in VoiceParam::ALL {
+        let value = match (param.get(own), param.get(fallback)) {
+            (Some(x), _) => format!("{:.2}", x),
+            (None, Some(x)) => format!("{:.2} (サーバー既定)", x),
+            (None, None) if param == VoiceParam::Speed => {
+                format!("{:.2} (既定)", DEFAULT_SPEED_SCALE)
+            }
+            (None, None) => "未設定".to_string(),
+        };
+        lines.push(format!("{} (`{}`): {}", param.label(), param.name(), value));
+    }
+
+    lines.join("\n")
+}